@@ -0,0 +1,5 @@
+ALTER TABLE items
+	DROP COLUMN task_state,
+	DROP COLUMN due_at;
+
+DROP TYPE task_state;
\ No newline at end of file
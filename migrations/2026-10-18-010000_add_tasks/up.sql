@@ -0,0 +1,5 @@
+CREATE TYPE task_state AS ENUM ('todo', 'done', 'cancelled');
+
+ALTER TABLE items
+	ADD COLUMN task_state task_state NULL,
+	ADD COLUMN due_at TIMESTAMP NULL;
\ No newline at end of file
@@ -0,0 +1,24 @@
+CREATE EXTENSION IF NOT EXISTS "uuid-ossp";
+
+CREATE TYPE webhook_event AS ENUM ('rename', 'edit', 'edit_text', 'publish', 'unpublish', 'trash', 'delete');
+
+CREATE TABLE webhooks (
+	id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+	document_id uuid NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+	user_id uuid NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+	url TEXT NOT NULL,
+	secret TEXT NOT NULL,
+	created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE webhook_deliveries (
+	id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+	webhook_id uuid NOT NULL REFERENCES webhooks(id) ON DELETE CASCADE,
+	delivery_id uuid NOT NULL,
+	event webhook_event NOT NULL,
+	attempt INT NOT NULL,
+	status_code INT NULL,
+	error TEXT NULL,
+	succeeded BOOLEAN NOT NULL,
+	attempted_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
\ No newline at end of file
@@ -0,0 +1,3 @@
+DROP TABLE activities;
+
+DROP TYPE activity_event;
\ No newline at end of file
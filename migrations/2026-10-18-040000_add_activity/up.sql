@@ -0,0 +1,14 @@
+CREATE EXTENSION IF NOT EXISTS "uuid-ossp";
+
+CREATE TYPE activity_event AS ENUM ('create', 'rename', 'edit', 'edit_text', 'add_category', 'remove_category', 'trash', 'publish', 'unpublish', 'copy');
+
+CREATE TABLE activities (
+	id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+	document_id uuid NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+	user_id uuid NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+	event activity_event NOT NULL,
+	summary TEXT NOT NULL,
+	created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX activities_document_created ON activities (document_id, created_at DESC);
\ No newline at end of file
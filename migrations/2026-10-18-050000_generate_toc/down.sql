@@ -0,0 +1,9 @@
+ALTER TABLE items
+	DROP COLUMN heading,
+	DROP COLUMN toc_target_id;
+
+ALTER TABLE documents
+	DROP COLUMN toc_generation,
+	DROP COLUMN toc_depth;
+
+DROP TYPE toc_generation;
\ No newline at end of file
@@ -0,0 +1,9 @@
+CREATE TYPE toc_generation AS ENUM ('headings', 'depth');
+
+ALTER TABLE documents
+	ADD COLUMN toc_generation toc_generation NULL,
+	ADD COLUMN toc_depth INT NOT NULL DEFAULT 2;
+
+ALTER TABLE items
+	ADD COLUMN heading BOOLEAN NOT NULL DEFAULT FALSE,
+	ADD COLUMN toc_target_id uuid NULL REFERENCES items(id) ON DELETE SET NULL;
\ No newline at end of file
@@ -0,0 +1,4 @@
+ALTER TABLE documents
+	DROP COLUMN numbering_scheme;
+
+DROP TYPE numbering_scheme;
\ No newline at end of file
@@ -0,0 +1,4 @@
+CREATE TYPE numbering_scheme AS ENUM ('decimal', 'outline', 'legal');
+
+ALTER TABLE documents
+	ADD COLUMN numbering_scheme numbering_scheme NULL;
\ No newline at end of file
@@ -0,0 +1,7 @@
+ALTER TYPE style_property RENAME TO style_property_old;
+CREATE TYPE style_property AS ENUM ('background_color', 'color', 'font_size', 'line_height', 'font_weight', 'font_style', 'text_decoration', 'font_family', 'text_align', 'text_indent');
+
+ALTER TABLE styles
+	ALTER COLUMN property TYPE style_property USING property::text::style_property;
+
+DROP TYPE style_property_old;
\ No newline at end of file
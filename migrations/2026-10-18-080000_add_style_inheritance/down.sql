@@ -0,0 +1,4 @@
+DROP TABLE document_styles;
+
+ALTER TABLE styles
+	DROP COLUMN inherit;
\ No newline at end of file
@@ -0,0 +1,11 @@
+ALTER TABLE styles
+	ADD COLUMN inherit BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE document_styles (
+	document_id uuid NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+	property style_property NOT NULL,
+	value_number INT NULL,
+	value_string TEXT NULL,
+	unit style_unit NULL,
+	PRIMARY KEY(document_id, property)
+);
\ No newline at end of file
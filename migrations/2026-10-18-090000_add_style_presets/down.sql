@@ -0,0 +1,6 @@
+ALTER TABLE items
+	DROP COLUMN style_preset;
+
+DROP TABLE style_preset_properties;
+
+DROP TABLE style_presets;
\ No newline at end of file
@@ -0,0 +1,24 @@
+CREATE EXTENSION IF NOT EXISTS "uuid-ossp";
+
+CREATE TABLE style_presets (
+	id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+	user_id uuid NULL REFERENCES users(id) ON DELETE CASCADE,
+	document_id uuid NULL REFERENCES documents(id) ON DELETE CASCADE,
+	name TEXT NOT NULL,
+	created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	CHECK ((user_id IS NULL) <> (document_id IS NULL)),
+	UNIQUE(user_id, name),
+	UNIQUE(document_id, name)
+);
+
+CREATE TABLE style_preset_properties (
+	preset_id uuid NOT NULL REFERENCES style_presets(id) ON DELETE CASCADE,
+	property style_property NOT NULL,
+	value_number INT NULL,
+	value_string TEXT NULL,
+	unit style_unit NULL,
+	PRIMARY KEY(preset_id, property)
+);
+
+ALTER TABLE items
+	ADD COLUMN style_preset TEXT NULL;
\ No newline at end of file
@@ -0,0 +1,10 @@
+ALTER TABLE items
+	ADD COLUMN heading BOOLEAN NOT NULL DEFAULT FALSE;
+
+UPDATE items SET heading = TRUE WHERE kind = 'heading';
+
+ALTER TABLE items
+	DROP COLUMN code_language,
+	DROP COLUMN kind;
+
+DROP TYPE item_kind;
\ No newline at end of file
@@ -0,0 +1,10 @@
+CREATE TYPE item_kind AS ENUM ('paragraph', 'heading', 'quote', 'code', 'divider');
+
+ALTER TABLE items
+	ADD COLUMN kind item_kind NOT NULL DEFAULT 'paragraph',
+	ADD COLUMN code_language TEXT NULL;
+
+UPDATE items SET kind = 'heading' WHERE heading;
+
+ALTER TABLE items
+	DROP COLUMN heading;
\ No newline at end of file
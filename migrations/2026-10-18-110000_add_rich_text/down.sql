@@ -0,0 +1,2 @@
+ALTER TABLE items
+	DROP COLUMN rich_text;
\ No newline at end of file
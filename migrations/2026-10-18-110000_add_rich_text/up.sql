@@ -0,0 +1,2 @@
+ALTER TABLE items
+	ADD COLUMN rich_text JSONB NULL;
\ No newline at end of file
@@ -0,0 +1,16 @@
+CREATE EXTENSION IF NOT EXISTS "uuid-ossp";
+
+CREATE TABLE attachments (
+	id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+	document_id uuid NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+	item_id uuid NULL REFERENCES items(id) ON DELETE SET NULL,
+	user_id uuid NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+	file_name TEXT NOT NULL,
+	content_type TEXT NOT NULL,
+	size_bytes BIGINT NOT NULL,
+	storage_key TEXT NOT NULL,
+	thumbnail_key TEXT NULL,
+	created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX attachments_document_id_idx ON attachments(document_id);
\ No newline at end of file
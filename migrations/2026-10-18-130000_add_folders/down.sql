@@ -0,0 +1,4 @@
+ALTER TABLE documents
+	DROP COLUMN folder_id;
+
+DROP TABLE folders;
\ No newline at end of file
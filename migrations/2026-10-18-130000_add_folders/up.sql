@@ -0,0 +1,14 @@
+CREATE EXTENSION IF NOT EXISTS "uuid-ossp";
+
+CREATE TABLE folders (
+	id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+	user_id uuid NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+	parent_id uuid NULL REFERENCES folders(id) ON DELETE SET NULL,
+	name TEXT NOT NULL,
+	created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX folders_user_id_idx ON folders(user_id);
+
+ALTER TABLE documents
+	ADD COLUMN folder_id uuid NULL REFERENCES folders(id) ON DELETE SET NULL;
\ No newline at end of file
@@ -0,0 +1 @@
+DROP TABLE document_forks;
\ No newline at end of file
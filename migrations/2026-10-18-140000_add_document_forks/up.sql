@@ -0,0 +1,11 @@
+CREATE TABLE document_forks (
+	document_id uuid PRIMARY KEY REFERENCES documents(id) ON DELETE CASCADE,
+	source_document_id uuid NULL REFERENCES documents(id) ON DELETE SET NULL,
+	source_modified_at TIMESTAMP NOT NULL,
+	base_root_id uuid NOT NULL,
+	base_items JSONB NOT NULL,
+	created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	synced_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX document_forks_source_document_id_idx ON document_forks(source_document_id);
\ No newline at end of file
@@ -0,0 +1,4 @@
+DROP TABLE stars;
+
+ALTER TABLE users
+	DROP COLUMN public_profile;
\ No newline at end of file
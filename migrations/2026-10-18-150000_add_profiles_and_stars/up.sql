@@ -0,0 +1,11 @@
+ALTER TABLE users
+	ADD COLUMN public_profile BOOL NOT NULL DEFAULT FALSE;
+
+CREATE TABLE stars (
+	document_id uuid NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+	user_id uuid NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+	created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+	PRIMARY KEY(document_id, user_id)
+);
+
+CREATE INDEX stars_user_id_idx ON stars(user_id);
\ No newline at end of file
@@ -0,0 +1 @@
+DROP TABLE subtree_shares;
\ No newline at end of file
@@ -0,0 +1,8 @@
+CREATE TABLE subtree_shares (
+	id uuid DEFAULT uuid_generate_v4() PRIMARY KEY,
+	document_id uuid NOT NULL REFERENCES documents(id) ON DELETE CASCADE,
+	item_id uuid NOT NULL,
+	created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX subtree_shares_document_id_idx ON subtree_shares(document_id);
\ No newline at end of file
@@ -0,0 +1,3 @@
+ALTER TABLE items
+	DROP COLUMN locked_at,
+	DROP COLUMN locked_by;
\ No newline at end of file
@@ -0,0 +1,3 @@
+ALTER TABLE items
+	ADD COLUMN locked_by uuid REFERENCES users(id) ON DELETE SET NULL,
+	ADD COLUMN locked_at TIMESTAMP;
\ No newline at end of file
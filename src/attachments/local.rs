@@ -0,0 +1,56 @@
+use attachments::AttachmentStore;
+
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+// stores each attachment as a file named after its key in a single directory
+pub struct LocalStore {
+    root: PathBuf,
+}
+
+impl LocalStore {
+    pub fn new(root: &str) -> io::Result<LocalStore> {
+        fs::create_dir_all(root)?;
+
+        Ok(LocalStore {
+            root: PathBuf::from(root),
+        })
+    }
+
+    // keys are generated by the server, but are still checked so that they can
+    // never escape the storage directory
+    fn path_for(&self, key: &str) -> io::Result<PathBuf> {
+        let valid_key = key != ""
+            && !key.starts_with('.')
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '.');
+
+        if valid_key {
+            Ok(self.root.join(key))
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Invalid attachment storage key.",
+            ))
+        }
+    }
+}
+
+impl AttachmentStore for LocalStore {
+    fn put(&self, key: &str, bytes: &[u8]) -> io::Result<()> {
+        fs::write(self.path_for(key)?, bytes)
+    }
+
+    fn get(&self, key: &str) -> io::Result<Vec<u8>> {
+        fs::read(self.path_for(key)?)
+    }
+
+    fn delete(&self, key: &str) -> io::Result<()> {
+        match fs::remove_file(self.path_for(key)?) {
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            r => r,
+        }
+    }
+}
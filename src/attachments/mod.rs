@@ -0,0 +1,260 @@
+pub mod local;
+
+use config::SeriatimConfig;
+
+use data::attachment::Attachment;
+use data::sanitize::clean_text;
+
+use image;
+use image::ImageOutputFormat;
+
+use std::io;
+
+const THUMBNAIL_SIZE: u32 = 256;
+const MAX_THUMBNAIL_PIXELS: u64 = 40_000_000;
+const MAX_FILE_NAME_LENGTH: usize = 255;
+
+pub trait AttachmentStore: Send + Sync {
+    fn put(&self, key: &str, bytes: &[u8]) -> io::Result<()>;
+    fn get(&self, key: &str) -> io::Result<Vec<u8>>;
+    fn delete(&self, key: &str) -> io::Result<()>;
+}
+
+pub struct Attachments {
+    pub store: Box<dyn AttachmentStore>,
+    pub max_size: u64,
+}
+
+impl Attachments {
+    // runs once the attachment's row is gone, so a file that can't be removed
+    // is logged rather than failing a change that has already been made
+    pub fn delete_files(&self, attachment: &Attachment) {
+        let keys = std::iter::once(&attachment.data.storage_key)
+            .chain(attachment.data.thumbnail_key.iter());
+
+        for key in keys {
+            if let Err(e) = self.store.delete(key) {
+                println!("Could not delete attachment file {}: {}", key, e);
+            }
+        }
+    }
+}
+
+pub fn init(cfg: &SeriatimConfig) -> io::Result<Attachments> {
+    Ok(Attachments {
+        store: Box::new(local::LocalStore::new(&cfg.attachment_dir)?),
+        max_size: cfg.max_attachment_size,
+    })
+}
+
+// the content type is taken from the file's magic number rather than trusting
+// what the client sent; anything unrecognized is rejected
+pub fn sniff_content_type(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if bytes.starts_with(b"\xff\xd8\xff") {
+        Some("image/jpeg")
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else if bytes.starts_with(b"%PDF-") {
+        Some("application/pdf")
+    } else {
+        None
+    }
+}
+
+pub fn is_image(content_type: &str) -> bool {
+    content_type.starts_with("image/")
+}
+
+fn read_u16_be(bytes: &[u8], at: usize) -> Option<u32> {
+    let b = bytes.get(at..at + 2)?;
+    Some((b[0] as u32) << 8 | b[1] as u32)
+}
+
+fn read_u16_le(bytes: &[u8], at: usize) -> Option<u32> {
+    let b = bytes.get(at..at + 2)?;
+    Some((b[1] as u32) << 8 | b[0] as u32)
+}
+
+fn read_u24_le(bytes: &[u8], at: usize) -> Option<u32> {
+    let b = bytes.get(at..at + 3)?;
+    Some((b[2] as u32) << 16 | (b[1] as u32) << 8 | b[0] as u32)
+}
+
+fn read_u32_be(bytes: &[u8], at: usize) -> Option<u32> {
+    let b = bytes.get(at..at + 4)?;
+    Some((b[0] as u32) << 24 | (b[1] as u32) << 16 | (b[2] as u32) << 8 | b[3] as u32)
+}
+
+fn read_u32_le(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(read_u32_be(bytes, at)?.swap_bytes())
+}
+
+fn skip_gif_sub_blocks(bytes: &[u8], mut i: usize) -> Option<usize> {
+    loop {
+        let size = *bytes.get(i)? as usize;
+        i += 1 + size;
+
+        if size == 0 {
+            return Some(i);
+        }
+    }
+}
+
+// frames may be larger than the logical screen, and each is decoded into a
+// buffer of its own size
+fn gif_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    let (mut width, mut height) = (read_u16_le(bytes, 6)?, read_u16_le(bytes, 8)?);
+
+    let flags = *bytes.get(10)?;
+    let mut i = 13;
+    if flags & 0x80 != 0 {
+        i += 3 << ((flags & 0x07) + 1);
+    }
+
+    loop {
+        match *bytes.get(i)? {
+            0x21 => i = skip_gif_sub_blocks(bytes, i + 2)?,
+            0x2c => {
+                width = width.max(read_u16_le(bytes, i + 5)?);
+                height = height.max(read_u16_le(bytes, i + 7)?);
+
+                let frame_flags = *bytes.get(i + 9)?;
+                i += 10;
+                if frame_flags & 0x80 != 0 {
+                    i += 3 << ((frame_flags & 0x07) + 1);
+                }
+
+                // skips the LZW minimum code size before the image data
+                i = skip_gif_sub_blocks(bytes, i + 1)?;
+            }
+            0x3b => return Some((width, height)),
+            _ => return None,
+        }
+    }
+}
+
+fn jpeg_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    let mut i = 2;
+
+    loop {
+        if *bytes.get(i)? != 0xff {
+            return None;
+        }
+        while *bytes.get(i)? == 0xff {
+            i += 1;
+        }
+
+        let marker = bytes[i];
+        i += 1;
+
+        match marker {
+            // restart markers and TEM stand alone, without a length
+            0x01 | 0xd0..=0xd7 => continue,
+            0xc0..=0xcf if marker != 0xc4 && marker != 0xc8 && marker != 0xcc => {
+                return Some((read_u16_be(bytes, i + 5)?, read_u16_be(bytes, i + 3)?));
+            }
+            0xd9 | 0xda => return None,
+            _ => i += read_u16_be(bytes, i)? as usize,
+        }
+    }
+}
+
+fn webp_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    match bytes.get(12..16)? {
+        b"VP8 " => Some((
+            read_u16_le(bytes, 26)? & 0x3fff,
+            read_u16_le(bytes, 28)? & 0x3fff,
+        )),
+        b"VP8L" => {
+            let bits = read_u32_le(bytes, 21)?;
+            Some(((bits & 0x3fff) + 1, ((bits >> 14) & 0x3fff) + 1))
+        }
+        b"VP8X" => Some((read_u24_le(bytes, 24)? + 1, read_u24_le(bytes, 27)? + 1)),
+        _ => None,
+    }
+}
+
+// reads the dimensions from the image header alone, so that they can be
+// checked before anything is decoded
+pub fn image_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    match sniff_content_type(bytes)? {
+        "image/png" => Some((read_u32_be(bytes, 16)?, read_u32_be(bytes, 20)?)),
+        "image/gif" => gif_dimensions(bytes),
+        "image/jpeg" => jpeg_dimensions(bytes),
+        "image/webp" => webp_dimensions(bytes),
+        _ => None,
+    }
+}
+
+// scales an image down to fit within a square, always encoding it as PNG;
+// images too large to decode safely are left without a thumbnail
+pub fn make_thumbnail(bytes: &[u8]) -> Option<Vec<u8>> {
+    let (width, height) = image_dimensions(bytes)?;
+    if width as u64 * height as u64 > MAX_THUMBNAIL_PIXELS {
+        return None;
+    }
+
+    let img = image::load_from_memory(bytes).ok()?;
+    let thumbnail = img.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+
+    let mut encoded = Vec::new();
+    thumbnail
+        .write_to(&mut encoded, ImageOutputFormat::PNG)
+        .ok()?;
+
+    Some(encoded)
+}
+
+pub fn sanitize_file_name(file_name: &str) -> String {
+    let base_name = file_name
+        .rsplit(|c| c == '/' || c == '\\')
+        .next()
+        .unwrap_or("");
+
+    let cleaned: String = clean_text(base_name)
+        .chars()
+        .filter(|c| *c != '"' && *c != '\n' && *c != '\t')
+        .take(MAX_FILE_NAME_LENGTH)
+        .collect();
+
+    if cleaned.trim() == "" {
+        "attachment".to_string()
+    } else {
+        cleaned.trim().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_png_dimensions() {
+        let mut png = b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR".to_vec();
+        png.extend_from_slice(&[0, 0, 0x27, 0x10, 0, 0, 0x13, 0x88]);
+
+        assert_eq!(image_dimensions(&png), Some((10000, 5000)));
+        assert_eq!(make_thumbnail(&png), None);
+    }
+
+    #[test]
+    fn reads_largest_gif_frame() {
+        let mut gif = b"GIF89a\x01\x00\x01\x00\x00\x00\x00".to_vec();
+        gif.extend_from_slice(&[0x2c, 0, 0, 0, 0, 0xff, 0xff, 0x10, 0x00, 0x00]);
+        gif.extend_from_slice(&[0x02, 0x01, 0x00, 0x00, 0x3b]);
+
+        assert_eq!(image_dimensions(&gif), Some((65535, 16)));
+    }
+
+    #[test]
+    fn reads_jpeg_dimensions() {
+        let mut jpeg = b"\xff\xd8\xff\xe0\x00\x04\x00\x00".to_vec();
+        jpeg.extend_from_slice(&[0xff, 0xc0, 0x00, 0x11, 0x08, 0x01, 0xe0, 0x02, 0x80]);
+
+        assert_eq!(image_dimensions(&jpeg), Some((640, 480)));
+    }
+}
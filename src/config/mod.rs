@@ -16,6 +16,8 @@ pub struct SeriatimConfig {
     pub max_user_sessions: Option<usize>,
     pub database_url: String,
     pub redis_url: String,
+    pub attachment_dir: String,
+    pub max_attachment_size: u64,
 }
 
 impl SeriatimConfig {
@@ -37,6 +39,12 @@ impl SeriatimConfig {
                 .and_then(|v| v.parse::<usize>().ok()),
             database_url: env::var("DATABASE_URL").unwrap(),
             redis_url: env::var("REDIS_URL").unwrap(),
+            attachment_dir: env::var("SERIATIM_ATTACHMENT_DIR")
+                .unwrap_or("attachments".to_string()),
+            max_attachment_size: env::var("SERIATIM_MAX_ATTACHMENT_SIZE")
+                .ok()
+                .and_then(|v| v.parse::<u64>().ok())
+                .unwrap_or(10 * 1024 * 1024),
         }
     }
 }
@@ -67,6 +75,16 @@ impl fmt::Display for SeriatimConfig {
             "    => seriatim_max_user_sessions: {}",
             self.max_user_sessions.unwrap_or(0),
         )?;
+        writeln!(
+            f,
+            "    => seriatim_attachment_dir:    {}",
+            self.attachment_dir
+        )?;
+        writeln!(
+            f,
+            "    => seriatim_max_attachment_size: {}",
+            self.max_attachment_size
+        )?;
         writeln!(f, "    => database_url:               set")?;
         write!(f, "    => redis_url:                  set")
     }
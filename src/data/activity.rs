@@ -0,0 +1,167 @@
+use diesel;
+use diesel::prelude::*;
+
+use data::db::Connection;
+use data::document::DocumentID;
+use data::schema::activities;
+use data::schema::activities::dsl::*;
+use data::schema::ActivityEvent;
+use data::user::UserID;
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use std::time::SystemTime;
+
+use uuid;
+
+#[derive(TaggedID, Serialize, Deserialize)]
+pub struct ActivityID(uuid::Uuid);
+
+pub struct Activity<'a> {
+    connection: &'a Connection,
+    pub data: Data,
+}
+
+#[derive(Debug, Queryable, Identifiable)]
+#[table_name = "activities"]
+pub struct Data {
+    id: uuid::Uuid,
+    document_id: uuid::Uuid,
+    user_id: uuid::Uuid,
+    pub event: ActivityEvent,
+    pub summary: String,
+    pub created_at: SystemTime,
+}
+
+#[derive(Insertable)]
+#[table_name = "activities"]
+struct NewActivity {
+    document_id: uuid::Uuid,
+    user_id: uuid::Uuid,
+    event: ActivityEvent,
+    summary: String,
+}
+
+impl<'a> Activity<'a> {
+    pub const MAX_SUMMARY_LENGTH: usize = 140;
+    pub const DEFAULT_PAGE_SIZE: i64 = 25;
+    pub const MAX_PAGE_SIZE: i64 = 100;
+
+    pub fn get_id(&self) -> ActivityID {
+        ActivityID::from_uuid(self.data.id.clone())
+    }
+
+    pub fn get_document_id(&self) -> DocumentID {
+        DocumentID::from_uuid(self.data.document_id.clone())
+    }
+
+    pub fn get_user_id(&self) -> UserID {
+        UserID::from_uuid(self.data.user_id.clone())
+    }
+
+    pub fn record(
+        connection: &'a Connection,
+        p_document_id: &DocumentID,
+        p_user_id: &UserID,
+        p_event: ActivityEvent,
+        p_summary: &str,
+    ) -> QueryResult<Self> {
+        let data = diesel::insert_into(activities)
+            .values(NewActivity {
+                document_id: **p_document_id,
+                user_id: **p_user_id,
+                event: p_event,
+                summary: p_summary.chars().take(Self::MAX_SUMMARY_LENGTH).collect(),
+            })
+            .get_result(&connection.pg_connection)?;
+
+        Ok(Activity { connection, data })
+    }
+
+    pub fn count_by_document(
+        connection: &'a Connection,
+        p_document_id: &DocumentID,
+    ) -> QueryResult<i64> {
+        activities
+            .filter(document_id.eq(&**p_document_id))
+            .count()
+            .get_result(&connection.pg_connection)
+    }
+
+    pub fn get_by_document(
+        connection: &'a Connection,
+        p_document_id: &DocumentID,
+        page: i64,
+        per_page: i64,
+    ) -> QueryResult<Vec<Self>> {
+        // pages too far out to address are past the end of the log anyway
+        let offset = match page.checked_mul(per_page) {
+            Some(o) if o >= 0 => o,
+            _ => return Ok(Vec::new()),
+        };
+
+        let activities_list = activities
+            .filter(document_id.eq(&**p_document_id))
+            .order(created_at.desc())
+            .offset(offset)
+            .limit(per_page)
+            .load::<Data>(&connection.pg_connection)?;
+
+        Ok(activities_list
+            .into_iter()
+            .map(|data| Activity { connection, data })
+            .collect())
+    }
+
+    // the log from the latest publication on, newest first; a document with no
+    // publication on record was published before the log began, so all of it
+    // happened in public
+    pub fn get_since_published(
+        connection: &'a Connection,
+        p_document_id: &DocumentID,
+        limit: i64,
+    ) -> QueryResult<Vec<Self>> {
+        let published_at = activities
+            .select(created_at)
+            .filter(document_id.eq(&**p_document_id))
+            .filter(event.eq(ActivityEvent::Publish))
+            .order(created_at.desc())
+            .first::<SystemTime>(&connection.pg_connection)
+            .optional()?;
+
+        let mut query = activities
+            .filter(document_id.eq(&**p_document_id))
+            .into_boxed();
+        if let Some(p_published_at) = published_at {
+            query = query.filter(created_at.ge(p_published_at));
+        }
+
+        let activities_list = query
+            .order(created_at.desc())
+            .limit(limit)
+            .load::<Data>(&connection.pg_connection)?;
+
+        Ok(activities_list
+            .into_iter()
+            .map(|data| Activity { connection, data })
+            .collect())
+    }
+}
+
+impl<'a> Serialize for Activity<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut serialized = serializer.serialize_struct("Activity", 6)?;
+
+        serialized.serialize_field("activity_id", &self.get_id())?;
+        serialized.serialize_field("document_id", &self.get_document_id())?;
+        serialized.serialize_field("user_id", &self.get_user_id())?;
+        serialized.serialize_field("event", &self.data.event)?;
+        serialized.serialize_field("summary", &self.data.summary)?;
+        serialized.serialize_field("created_at", &self.data.created_at)?;
+
+        serialized.end()
+    }
+}
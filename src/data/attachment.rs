@@ -0,0 +1,210 @@
+use diesel;
+use diesel::prelude::*;
+
+use data::db::Connection;
+use data::document::DocumentID;
+use data::item::{Item, ItemID};
+use data::schema::attachments;
+use data::schema::attachments::dsl::*;
+use data::user::UserID;
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use std::time::SystemTime;
+
+use uuid;
+
+#[derive(TaggedID, Serialize, Deserialize)]
+pub struct AttachmentID(uuid::Uuid);
+
+pub struct Attachment<'a> {
+    connection: &'a Connection,
+    pub data: Data,
+}
+
+#[derive(Debug, Queryable, Identifiable)]
+#[table_name = "attachments"]
+pub struct Data {
+    id: uuid::Uuid,
+    document_id: uuid::Uuid,
+    item_id: Option<uuid::Uuid>,
+    user_id: uuid::Uuid,
+    pub file_name: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub storage_key: String,
+    pub thumbnail_key: Option<String>,
+    pub created_at: SystemTime,
+}
+
+#[derive(Insertable)]
+#[table_name = "attachments"]
+struct NewAttachment<'a> {
+    id: uuid::Uuid,
+    document_id: uuid::Uuid,
+    item_id: Option<uuid::Uuid>,
+    user_id: uuid::Uuid,
+    file_name: &'a str,
+    content_type: &'a str,
+    size_bytes: i64,
+    storage_key: &'a str,
+    thumbnail_key: Option<&'a str>,
+}
+
+pub struct AttachmentFile<'b> {
+    pub file_name: &'b str,
+    pub content_type: &'b str,
+    pub size_bytes: i64,
+    pub has_thumbnail: bool,
+}
+
+impl<'a> Attachment<'a> {
+    fn results_list(connection: &'a Connection, attachments_list: Vec<Data>) -> Vec<Self> {
+        attachments_list
+            .into_iter()
+            .map(|data| Attachment { connection, data })
+            .collect()
+    }
+
+    pub fn get_id(&self) -> AttachmentID {
+        AttachmentID::from_uuid(self.data.id.clone())
+    }
+
+    pub fn get_document_id(&self) -> DocumentID {
+        DocumentID::from_uuid(self.data.document_id.clone())
+    }
+
+    pub fn get_item_id(&self) -> Option<ItemID> {
+        self.data.item_id.map(ItemID::from_uuid)
+    }
+
+    pub fn get_user_id(&self) -> UserID {
+        UserID::from_uuid(self.data.user_id.clone())
+    }
+
+    // the ID is chosen up front so that the file can be stored under it before
+    // the row is written
+    pub fn new_id() -> AttachmentID {
+        AttachmentID::from_uuid(uuid::Uuid::new_v4())
+    }
+
+    pub fn storage_key_for(p_attachment_id: &AttachmentID) -> String {
+        p_attachment_id.json_str()
+    }
+
+    pub fn thumbnail_key_for(p_attachment_id: &AttachmentID) -> String {
+        p_attachment_id.json_str() + ".thumb.png"
+    }
+
+    pub fn create(
+        connection: &'a Connection,
+        p_attachment_id: &AttachmentID,
+        p_item: &Item,
+        p_user_id: &UserID,
+        p_file: &AttachmentFile,
+    ) -> QueryResult<Self> {
+        let p_thumbnail_key = if p_file.has_thumbnail {
+            Some(Self::thumbnail_key_for(p_attachment_id))
+        } else {
+            None
+        };
+
+        let data = diesel::insert_into(attachments)
+            .values(NewAttachment {
+                id: **p_attachment_id,
+                document_id: p_item.data.document_id,
+                item_id: Some(*p_item.get_id()),
+                user_id: **p_user_id,
+                file_name: p_file.file_name,
+                content_type: p_file.content_type,
+                size_bytes: p_file.size_bytes,
+                storage_key: &Self::storage_key_for(p_attachment_id),
+                thumbnail_key: p_thumbnail_key.as_ref().map(|k| k.as_str()),
+            })
+            .get_result(&connection.pg_connection)?;
+
+        Ok(Attachment { connection, data })
+    }
+
+    pub fn get_by_id(
+        connection: &'a Connection,
+        p_document_id: &DocumentID,
+        p_attachment_id: &AttachmentID,
+    ) -> QueryResult<Self> {
+        let data = attachments
+            .filter(id.eq(&**p_attachment_id))
+            .filter(document_id.eq(&**p_document_id))
+            .first::<Data>(&connection.pg_connection)?;
+
+        Ok(Attachment { connection, data })
+    }
+
+    pub fn get_by_item(
+        connection: &'a Connection,
+        p_document_id: &DocumentID,
+        p_item_id: &ItemID,
+    ) -> QueryResult<Vec<Self>> {
+        let attachments_list = attachments
+            .filter(document_id.eq(&**p_document_id))
+            .filter(item_id.eq(&**p_item_id))
+            .order(created_at.asc())
+            .load::<Data>(&connection.pg_connection)?;
+
+        Ok(Self::results_list(connection, attachments_list))
+    }
+
+    pub fn get_by_document(
+        connection: &'a Connection,
+        p_document_id: &DocumentID,
+    ) -> QueryResult<Vec<Self>> {
+        let attachments_list = attachments
+            .filter(document_id.eq(&**p_document_id))
+            .order(created_at.asc())
+            .load::<Data>(&connection.pg_connection)?;
+
+        Ok(Self::results_list(connection, attachments_list))
+    }
+
+    // items get new IDs whenever the outline is edited, so edits re-link the
+    // attachments they carry; attachments left unlinked stay with the document
+    pub fn move_to_item(
+        connection: &'a Connection,
+        p_document_id: &DocumentID,
+        p_attachment_ids: &Vec<AttachmentID>,
+        p_item_id: &ItemID,
+    ) -> QueryResult<usize> {
+        let attachment_uuids: Vec<uuid::Uuid> = p_attachment_ids.iter().map(|a| **a).collect();
+
+        diesel::update(attachments)
+            .filter(document_id.eq(&**p_document_id))
+            .filter(id.eq_any(attachment_uuids))
+            .set(item_id.eq(Some(**p_item_id)))
+            .execute(&connection.pg_connection)
+    }
+
+    pub fn delete(&mut self) -> QueryResult<usize> {
+        diesel::delete(attachments)
+            .filter(id.eq(self.data.id))
+            .execute(&self.connection.pg_connection)
+    }
+}
+
+impl<'a> Serialize for Attachment<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut serialized = serializer.serialize_struct("Attachment", 8)?;
+
+        serialized.serialize_field("attachment_id", &self.get_id())?;
+        serialized.serialize_field("document_id", &self.get_document_id())?;
+        serialized.serialize_field("item_id", &self.get_item_id())?;
+        serialized.serialize_field("file_name", &self.data.file_name)?;
+        serialized.serialize_field("content_type", &self.data.content_type)?;
+        serialized.serialize_field("size", &self.data.size_bytes)?;
+        serialized.serialize_field("has_thumbnail", &self.data.thumbnail_key.is_some())?;
+        serialized.serialize_field("created_at", &self.data.created_at)?;
+
+        serialized.end()
+    }
+}
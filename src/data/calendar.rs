@@ -0,0 +1,99 @@
+use diesel;
+use diesel::prelude::*;
+use diesel::Connection as DieselConnection;
+
+use data::db::Connection;
+use data::schema::calendar_tokens;
+use data::schema::calendar_tokens::dsl::*;
+use data::user::UserID;
+
+use std::time::SystemTime;
+
+use uuid;
+
+#[derive(TaggedID, Serialize, Deserialize)]
+pub struct CalendarTokenID(uuid::Uuid);
+
+pub struct CalendarToken<'a> {
+    connection: &'a Connection,
+    pub data: Data,
+}
+
+#[derive(Debug, Queryable, Identifiable)]
+#[table_name = "calendar_tokens"]
+pub struct Data {
+    id: uuid::Uuid,
+    user_id: uuid::Uuid,
+    pub created_at: SystemTime,
+}
+
+#[derive(Insertable)]
+#[table_name = "calendar_tokens"]
+struct NewCalendarToken {
+    user_id: uuid::Uuid,
+}
+
+impl<'a> CalendarToken<'a> {
+    pub fn get_id(&self) -> CalendarTokenID {
+        CalendarTokenID::from_uuid(self.data.id.clone())
+    }
+
+    pub fn get_user_id(&self) -> UserID {
+        UserID::from_uuid(self.data.user_id.clone())
+    }
+
+    pub fn get_by_id(
+        connection: &'a Connection,
+        p_token_id: &CalendarTokenID,
+    ) -> QueryResult<CalendarToken<'a>> {
+        let p_uuid = **p_token_id;
+
+        let data = calendar_tokens
+            .filter(id.eq(&p_uuid))
+            .first::<Data>(&connection.pg_connection)?;
+
+        Ok(CalendarToken { connection, data })
+    }
+
+    pub fn get_by_user(
+        connection: &'a Connection,
+        p_user_id: &UserID,
+    ) -> QueryResult<CalendarToken<'a>> {
+        let p_user_uuid = **p_user_id;
+
+        let data = calendar_tokens
+            .filter(user_id.eq(&p_user_uuid))
+            .first::<Data>(&connection.pg_connection)?;
+
+        Ok(CalendarToken { connection, data })
+    }
+
+    pub fn revoke_for_user(connection: &'a Connection, p_user_id: &UserID) -> QueryResult<usize> {
+        let p_user_uuid = **p_user_id;
+
+        diesel::delete(calendar_tokens)
+            .filter(user_id.eq(&p_user_uuid))
+            .execute(&connection.pg_connection)
+    }
+
+    pub fn reset_for_user(
+        connection: &'a Connection,
+        p_user_id: &UserID,
+    ) -> QueryResult<CalendarToken<'a>> {
+        let p_user_uuid = **p_user_id;
+
+        let data = connection
+            .pg_connection
+            .transaction::<_, diesel::result::Error, _>(|| {
+                Self::revoke_for_user(connection, p_user_id)?;
+
+                diesel::insert_into(calendar_tokens)
+                    .values(NewCalendarToken {
+                        user_id: p_user_uuid,
+                    })
+                    .get_result::<Data>(&connection.pg_connection)
+            })?;
+
+        Ok(CalendarToken { connection, data })
+    }
+}
@@ -28,6 +28,12 @@ pub struct Data {
     pub category_name: String,
 }
 
+#[derive(Debug, Queryable, Serialize)]
+pub struct CategorySummary {
+    pub category_name: String,
+    pub document_count: i64,
+}
+
 #[derive(Insertable)]
 #[table_name = "categories"]
 pub struct NewCategory {
@@ -42,7 +48,7 @@ impl<'a> Category<'a> {
 
     // copied from https://stackoverflow.com/a/38406885
     // surprisingly tough in rust, but I guess that's unicode for you
-    fn sanitize_name(s: &str) -> String {
+    pub fn sanitize_name(s: &str) -> String {
         let lowercased = s.to_ascii_lowercase();
         let mut c = lowercased.chars();
 
@@ -132,6 +138,83 @@ impl<'a> Category<'a> {
         Self::results_list(connection, categories_list)
     }
 
+    pub fn get_summaries_for_user(
+        connection: &Connection,
+        p_user_id: &UserID,
+    ) -> QueryResult<Vec<CategorySummary>> {
+        categories
+            .filter(user_id.eq(&**p_user_id))
+            .group_by(category_name)
+            .select((category_name, diesel::dsl::count_star()))
+            .order(category_name.asc())
+            .load::<CategorySummary>(&connection.pg_connection)
+    }
+
+    pub fn exists_for_user(
+        connection: &Connection,
+        p_user_id: &UserID,
+        p_name: &str,
+    ) -> QueryResult<bool> {
+        let found = categories
+            .filter(user_id.eq(&**p_user_id))
+            .filter(category_name.eq(&Category::sanitize_name(p_name)))
+            .first::<Data>(&connection.pg_connection)
+            .optional()?;
+
+        Ok(found.is_some())
+    }
+
+    // moves every document in one of the user's categories into another; where
+    // a document is already in both, the old entry is simply dropped
+    pub fn rename_for_user(
+        connection: &Connection,
+        p_user_id: &UserID,
+        p_from: &str,
+        p_to: &str,
+    ) -> QueryResult<usize> {
+        let p_user_uuid = **p_user_id;
+        let from_name = Category::sanitize_name(p_from);
+        let to_name = Category::sanitize_name(p_to);
+
+        if from_name == to_name {
+            return Ok(0);
+        }
+
+        connection
+            .pg_connection
+            .transaction::<_, diesel::result::Error, _>(|| {
+                let already_categorized = categories
+                    .filter(user_id.eq(&p_user_uuid))
+                    .filter(category_name.eq(&to_name))
+                    .select(document_id);
+
+                let dropped = diesel::delete(categories)
+                    .filter(user_id.eq(&p_user_uuid))
+                    .filter(category_name.eq(&from_name))
+                    .filter(document_id.eq_any(already_categorized))
+                    .execute(&connection.pg_connection)?;
+
+                let renamed = diesel::update(categories)
+                    .filter(user_id.eq(&p_user_uuid))
+                    .filter(category_name.eq(&from_name))
+                    .set(category_name.eq(&to_name))
+                    .execute(&connection.pg_connection)?;
+
+                Ok(dropped + renamed)
+            })
+    }
+
+    pub fn delete_for_user(
+        connection: &Connection,
+        p_user_id: &UserID,
+        p_name: &str,
+    ) -> QueryResult<usize> {
+        diesel::delete(categories)
+            .filter(user_id.eq(&**p_user_id))
+            .filter(category_name.eq(&Category::sanitize_name(p_name)))
+            .execute(&connection.pg_connection)
+    }
+
     pub fn delete(&mut self) -> QueryResult<usize> {
         diesel::delete(categories)
             .filter(id.eq(self.data.id))
@@ -0,0 +1,267 @@
+// https://www.w3.org/TR/css-color-4/#named-colors
+const NAMED_COLORS: [&'static str; 149] = [
+    "aliceblue",
+    "antiquewhite",
+    "aqua",
+    "aquamarine",
+    "azure",
+    "beige",
+    "bisque",
+    "black",
+    "blanchedalmond",
+    "blue",
+    "blueviolet",
+    "brown",
+    "burlywood",
+    "cadetblue",
+    "chartreuse",
+    "chocolate",
+    "coral",
+    "cornflowerblue",
+    "cornsilk",
+    "crimson",
+    "cyan",
+    "darkblue",
+    "darkcyan",
+    "darkgoldenrod",
+    "darkgray",
+    "darkgreen",
+    "darkgrey",
+    "darkkhaki",
+    "darkmagenta",
+    "darkolivegreen",
+    "darkorange",
+    "darkorchid",
+    "darkred",
+    "darksalmon",
+    "darkseagreen",
+    "darkslateblue",
+    "darkslategray",
+    "darkslategrey",
+    "darkturquoise",
+    "darkviolet",
+    "deeppink",
+    "deepskyblue",
+    "dimgray",
+    "dimgrey",
+    "dodgerblue",
+    "firebrick",
+    "floralwhite",
+    "forestgreen",
+    "fuchsia",
+    "gainsboro",
+    "ghostwhite",
+    "gold",
+    "goldenrod",
+    "gray",
+    "green",
+    "greenyellow",
+    "grey",
+    "honeydew",
+    "hotpink",
+    "indianred",
+    "indigo",
+    "ivory",
+    "khaki",
+    "lavender",
+    "lavenderblush",
+    "lawngreen",
+    "lemonchiffon",
+    "lightblue",
+    "lightcoral",
+    "lightcyan",
+    "lightgoldenrodyellow",
+    "lightgray",
+    "lightgreen",
+    "lightgrey",
+    "lightpink",
+    "lightsalmon",
+    "lightseagreen",
+    "lightskyblue",
+    "lightslategray",
+    "lightslategrey",
+    "lightsteelblue",
+    "lightyellow",
+    "lime",
+    "limegreen",
+    "linen",
+    "magenta",
+    "maroon",
+    "mediumaquamarine",
+    "mediumblue",
+    "mediumorchid",
+    "mediumpurple",
+    "mediumseagreen",
+    "mediumslateblue",
+    "mediumspringgreen",
+    "mediumturquoise",
+    "mediumvioletred",
+    "midnightblue",
+    "mintcream",
+    "mistyrose",
+    "moccasin",
+    "navajowhite",
+    "navy",
+    "oldlace",
+    "olive",
+    "olivedrab",
+    "orange",
+    "orangered",
+    "orchid",
+    "palegoldenrod",
+    "palegreen",
+    "paleturquoise",
+    "palevioletred",
+    "papayawhip",
+    "peachpuff",
+    "peru",
+    "pink",
+    "plum",
+    "powderblue",
+    "purple",
+    "rebeccapurple",
+    "red",
+    "rosybrown",
+    "royalblue",
+    "saddlebrown",
+    "salmon",
+    "sandybrown",
+    "seagreen",
+    "seashell",
+    "sienna",
+    "silver",
+    "skyblue",
+    "slateblue",
+    "slategray",
+    "slategrey",
+    "snow",
+    "springgreen",
+    "steelblue",
+    "tan",
+    "teal",
+    "thistle",
+    "tomato",
+    "transparent",
+    "turquoise",
+    "violet",
+    "wheat",
+    "white",
+    "whitesmoke",
+    "yellow",
+    "yellowgreen",
+];
+
+fn is_hex_color(s: &str) -> bool {
+    if !s.starts_with('#') {
+        return false;
+    }
+
+    let digits = &s[1..];
+    [3, 4, 6, 8].contains(&digits.len()) && digits.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn is_channel(s: &str) -> bool {
+    let s = s.trim();
+
+    if s.ends_with('%') {
+        s.trim_end_matches('%')
+            .parse::<f64>()
+            .map(|p| p >= 0.0 && p <= 100.0)
+            .unwrap_or(false)
+    } else {
+        s.parse::<u32>().map(|c| c <= 255).unwrap_or(false)
+    }
+}
+
+fn is_alpha(s: &str) -> bool {
+    let s = s.trim();
+
+    if s.ends_with('%') {
+        is_channel(s)
+    } else {
+        s.parse::<f64>()
+            .map(|a| a >= 0.0 && a <= 1.0)
+            .unwrap_or(false)
+    }
+}
+
+fn is_rgb_color(s: &str) -> bool {
+    let (args, with_alpha) = if s.starts_with("rgba(") && s.ends_with(')') {
+        (&s[5..s.len() - 1], true)
+    } else if s.starts_with("rgb(") && s.ends_with(')') {
+        (&s[4..s.len() - 1], false)
+    } else {
+        return false;
+    };
+
+    let parts: Vec<&str> = args.split(',').collect();
+
+    match (parts.len(), with_alpha) {
+        (3, false) => parts.iter().all(|p| is_channel(p)),
+        (4, true) => parts[..3].iter().all(|p| is_channel(p)) && is_alpha(parts[3]),
+        _ => false,
+    }
+}
+
+pub fn is_valid_color(color: &str) -> bool {
+    let normalized = color.trim().to_ascii_lowercase();
+
+    is_hex_color(&normalized)
+        || is_rgb_color(&normalized)
+        || NAMED_COLORS.contains(&normalized.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_hex_colors() {
+        for color in ["#fff", "#FFFA", "#1a2b3c", "#1a2b3c80"].iter() {
+            assert!(is_valid_color(color), "{} should be valid", color);
+        }
+
+        for color in ["fff", "#", "#ff", "#12345", "#1234567", "#ggg"].iter() {
+            assert!(!is_valid_color(color), "{} should be invalid", color);
+        }
+    }
+
+    #[test]
+    fn accepts_rgb_colors() {
+        let valid = [
+            "rgb(255, 0, 0)",
+            "RGB(1,2,3)",
+            "rgb(100%, 0%, 50.5%)",
+            "rgba(0, 128, 255, 0.5)",
+            "rgba(0, 0, 0, 50%)",
+        ];
+        for color in valid.iter() {
+            assert!(is_valid_color(color), "{} should be valid", color);
+        }
+
+        let invalid = [
+            "rgb(256, 0, 0)",
+            "rgb(-1, 0, 0)",
+            "rgb(101%, 0, 0)",
+            "rgb(0, 0)",
+            "rgb(0, 0, 0, 1)",
+            "rgba(0, 0, 0)",
+            "rgba(0, 0, 0, 1.5)",
+            "rgb(0, 0, 0",
+        ];
+        for color in invalid.iter() {
+            assert!(!is_valid_color(color), "{} should be invalid", color);
+        }
+    }
+
+    #[test]
+    fn accepts_named_colors() {
+        for color in ["rebeccapurple", " Red ", "transparent"].iter() {
+            assert!(is_valid_color(color), "{} should be valid", color);
+        }
+
+        for color in ["", "notacolor", "light blue"].iter() {
+            assert!(!is_valid_color(color), "{} should be invalid", color);
+        }
+    }
+}
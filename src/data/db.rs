@@ -7,7 +7,7 @@ use rocket::http::Status;
 use rocket::request::{self, FromRequest};
 use rocket::{Outcome, Request, State};
 
-type PgPool = Pool<ConnectionManager<PgConnection>>;
+pub type PgPool = Pool<ConnectionManager<PgConnection>>;
 
 pub fn init_pool(cfg: &SeriatimConfig) -> PgPool {
     let manager = ConnectionManager::<PgConnection>::new(cfg.database_url.clone());
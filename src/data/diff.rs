@@ -0,0 +1,875 @@
+use data::item::{Item, ItemID};
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+// fuzzy matching compares every unmatched item against every other, so it is
+// skipped for outlines that have been rewritten wholesale
+const MAX_FUZZY_COMPARISONS: usize = 250_000;
+const MIN_SIMILARITY: f64 = 0.5;
+const MAX_WORD_DIFF_CELLS: usize = 4_000_000;
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WordOp {
+    Equal,
+    Insert,
+    Delete,
+}
+
+#[derive(Serialize)]
+pub struct WordChange {
+    pub op: WordOp,
+    pub text: String,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum ItemChange {
+    Inserted {
+        item_id: ItemID,
+        parent_id: Option<ItemID>,
+        text: String,
+    },
+    Deleted {
+        item_id: ItemID,
+        parent_id: Option<ItemID>,
+        text: String,
+    },
+    Moved {
+        old_item_id: ItemID,
+        new_item_id: ItemID,
+        old_parent_id: Option<ItemID>,
+        new_parent_id: Option<ItemID>,
+    },
+    TextChanged {
+        old_item_id: ItemID,
+        new_item_id: ItemID,
+        old_text: String,
+        new_text: String,
+        words: Vec<WordChange>,
+    },
+}
+
+// an item's place in an outline, detached from the database so that stored
+// snapshots can be diffed in the same way as live documents
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OutlineItem {
+    pub item_id: ItemID,
+    pub parent_id: Option<ItemID>,
+    pub child_order: i32,
+    pub item_text: String,
+}
+
+impl<'a, 'b> From<&'b Item<'a>> for OutlineItem {
+    fn from(item: &'b Item<'a>) -> Self {
+        OutlineItem {
+            item_id: item.get_id(),
+            parent_id: item.get_parent_id(),
+            child_order: item.data.child_order,
+            item_text: item.data.item_text.clone(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictReason {
+    BothEdited,
+    EditedUpstreamDeletedInCopy,
+    DeletedUpstreamEditedInCopy,
+    BothMoved,
+}
+
+#[derive(Serialize)]
+pub struct MergeConflict {
+    pub item_id: ItemID,
+    pub reason: ConflictReason,
+    pub base_text: String,
+    pub upstream_text: Option<String>,
+    pub copy_text: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct MergePreview {
+    pub changes: Vec<ItemChange>,
+    pub conflicts: Vec<MergeConflict>,
+}
+
+struct Node<'b> {
+    id: ItemID,
+    parent: Option<usize>,
+    order: i32,
+    text: &'b str,
+}
+
+// flattens an outline below its root in depth-first order, so that matching
+// prefers items in the same relative position
+fn flatten<'b>(items: &'b [OutlineItem], root_id: &ItemID) -> Vec<Node<'b>> {
+    let by_id: HashMap<&ItemID, &'b OutlineItem> = items.iter().map(|i| (&i.item_id, i)).collect();
+
+    let mut children = HashMap::<&ItemID, Vec<&'b OutlineItem>>::new();
+    for item in items.iter() {
+        if let Some(ref pid) = item.parent_id {
+            children.entry(pid).or_insert(Vec::new()).push(item);
+        }
+    }
+
+    for siblings in children.values_mut() {
+        siblings.sort_by_key(|i| i.child_order);
+    }
+
+    let mut nodes = Vec::new();
+    let mut stack: Vec<(ItemID, Option<usize>)> = vec![(root_id.clone(), None)];
+
+    while let Some((item_id, parent)) = stack.pop() {
+        let index = nodes.len();
+        let item = by_id.get(&item_id);
+
+        nodes.push(Node {
+            id: item_id.clone(),
+            parent,
+            order: item.map(|i| i.child_order).unwrap_or(0),
+            text: item.map(|i| i.item_text.as_str()).unwrap_or(""),
+        });
+
+        if let Some(siblings) = children.get(&item_id) {
+            for child in siblings.iter().rev() {
+                stack.push((child.item_id.clone(), Some(index)));
+            }
+        }
+    }
+
+    nodes
+}
+
+fn word_counts(text: &str) -> HashMap<&str, usize> {
+    let mut counts = HashMap::new();
+    for word in text.split_whitespace() {
+        *counts.entry(word).or_insert(0) += 1;
+    }
+
+    counts
+}
+
+// the Dice coefficient of the two texts' words
+fn similarity(lhs: &HashMap<&str, usize>, rhs: &HashMap<&str, usize>) -> f64 {
+    let lhs_total: usize = lhs.values().sum();
+    let rhs_total: usize = rhs.values().sum();
+
+    if lhs_total + rhs_total == 0 {
+        return 0.0;
+    }
+
+    let shared: usize = lhs
+        .iter()
+        .map(|(word, count)| (*count).min(*rhs.get(word).unwrap_or(&0)))
+        .sum();
+
+    2.0 * shared as f64 / (lhs_total + rhs_total) as f64
+}
+
+// pairs each old node with at most one new node: identical texts first, then
+// the most similar remaining texts
+fn match_nodes(old: &[Node], new: &[Node]) -> HashMap<usize, usize> {
+    let mut matches = HashMap::<usize, usize>::new();
+    matches.insert(0, 0);
+
+    let mut by_text = HashMap::<&str, VecDeque<usize>>::new();
+    for (index, node) in new.iter().enumerate().skip(1) {
+        by_text
+            .entry(node.text)
+            .or_insert(VecDeque::new())
+            .push_back(index);
+    }
+
+    for (index, node) in old.iter().enumerate().skip(1) {
+        if let Some(candidates) = by_text.get_mut(node.text) {
+            if let Some(new_index) = candidates.pop_front() {
+                matches.insert(index, new_index);
+            }
+        }
+    }
+
+    let mut matched_new: Vec<bool> = vec![false; new.len()];
+    for new_index in matches.values() {
+        matched_new[*new_index] = true;
+    }
+
+    let unmatched_old: Vec<usize> = (1..old.len())
+        .filter(|i| !matches.contains_key(i))
+        .collect();
+    let unmatched_new: Vec<usize> = (1..new.len()).filter(|i| !matched_new[*i]).collect();
+
+    if unmatched_old.len() * unmatched_new.len() > MAX_FUZZY_COMPARISONS {
+        return matches;
+    }
+
+    let new_words: HashMap<usize, HashMap<&str, usize>> = unmatched_new
+        .iter()
+        .map(|i| (*i, word_counts(new[*i].text)))
+        .collect();
+
+    for old_index in unmatched_old {
+        let old_words = word_counts(old[old_index].text);
+
+        let best = unmatched_new
+            .iter()
+            .filter(|i| !matched_new[**i])
+            .map(|i| (*i, similarity(&old_words, &new_words[i])))
+            .filter(|&(_, score)| score >= MIN_SIMILARITY)
+            .fold(None, |best: Option<(usize, f64)>, candidate| match best {
+                Some(b) if b.1 >= candidate.1 => Some(b),
+                _ => Some(candidate),
+            });
+
+        if let Some((new_index, _)) = best {
+            matches.insert(old_index, new_index);
+            matched_new[new_index] = true;
+        }
+    }
+
+    matches
+}
+
+// indices into values of a longest strictly increasing subsequence
+fn longest_increasing(values: &[i32]) -> Vec<usize> {
+    let mut tails: Vec<usize> = Vec::new();
+    let mut previous: Vec<Option<usize>> = vec![None; values.len()];
+
+    for (i, value) in values.iter().enumerate() {
+        let position = match tails.binary_search_by(|t| values[*t].cmp(value)) {
+            Ok(p) | Err(p) => p,
+        };
+
+        if position > 0 {
+            previous[i] = Some(tails[position - 1]);
+        }
+
+        if position == tails.len() {
+            tails.push(i);
+        } else {
+            tails[position] = i;
+        }
+    }
+
+    let mut sequence = Vec::new();
+    let mut current = tails.last().cloned();
+    while let Some(i) = current {
+        sequence.push(i);
+        current = previous[i];
+    }
+
+    sequence.reverse();
+    sequence
+}
+
+fn push_word(changes: &mut Vec<WordChange>, op: WordOp, word: &str) {
+    if let Some(last) = changes.last_mut() {
+        if last.op == op {
+            last.text.push(' ');
+            last.text.push_str(word);
+            return;
+        }
+    }
+
+    changes.push(WordChange {
+        op,
+        text: word.to_string(),
+    });
+}
+
+pub fn diff_words(old_text: &str, new_text: &str) -> Vec<WordChange> {
+    let old_words: Vec<&str> = old_text.split_whitespace().collect();
+    let new_words: Vec<&str> = new_text.split_whitespace().collect();
+
+    let prefix = old_words
+        .iter()
+        .zip(new_words.iter())
+        .take_while(|(o, n)| o == n)
+        .count();
+    let suffix = old_words[prefix..]
+        .iter()
+        .rev()
+        .zip(new_words[prefix..].iter().rev())
+        .take_while(|(o, n)| o == n)
+        .count();
+
+    let old_middle = &old_words[prefix..old_words.len() - suffix];
+    let new_middle = &new_words[prefix..new_words.len() - suffix];
+
+    let mut changes = Vec::new();
+    for word in old_words[..prefix].iter() {
+        push_word(&mut changes, WordOp::Equal, word);
+    }
+
+    let (rows, cols) = (old_middle.len() + 1, new_middle.len() + 1);
+
+    if rows * cols > MAX_WORD_DIFF_CELLS {
+        for word in old_middle.iter() {
+            push_word(&mut changes, WordOp::Delete, word);
+        }
+        for word in new_middle.iter() {
+            push_word(&mut changes, WordOp::Insert, word);
+        }
+    } else {
+        // lengths of the longest common subsequences of every pair of suffixes
+        let mut lcs = vec![0u32; rows * cols];
+        for i in (0..old_middle.len()).rev() {
+            for j in (0..new_middle.len()).rev() {
+                lcs[i * cols + j] = if old_middle[i] == new_middle[j] {
+                    lcs[(i + 1) * cols + j + 1] + 1
+                } else {
+                    lcs[(i + 1) * cols + j].max(lcs[i * cols + j + 1])
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < old_middle.len() || j < new_middle.len() {
+            if i < old_middle.len() && j < new_middle.len() && old_middle[i] == new_middle[j] {
+                push_word(&mut changes, WordOp::Equal, old_middle[i]);
+                i += 1;
+                j += 1;
+            } else if j < new_middle.len()
+                && (i == old_middle.len() || lcs[i * cols + j + 1] >= lcs[(i + 1) * cols + j])
+            {
+                push_word(&mut changes, WordOp::Insert, new_middle[j]);
+                j += 1;
+            } else {
+                push_word(&mut changes, WordOp::Delete, old_middle[i]);
+                i += 1;
+            }
+        }
+    }
+
+    for word in old_words[old_words.len() - suffix..].iter() {
+        push_word(&mut changes, WordOp::Equal, word);
+    }
+
+    changes
+}
+
+fn parent_id(nodes: &[Node], node: &Node) -> Option<ItemID> {
+    node.parent.map(|p| nodes[p].id.clone())
+}
+
+// item IDs aren't stable across edits or copies, so items are matched up by
+// their text; the roots of the two outlines always match
+pub fn diff_outlines(
+    old_items: &[OutlineItem],
+    old_root_id: &ItemID,
+    new_items: &[OutlineItem],
+    new_root_id: &ItemID,
+) -> Vec<ItemChange> {
+    let old = flatten(old_items, old_root_id);
+    let new = flatten(new_items, new_root_id);
+    let matches = match_nodes(&old, &new);
+
+    let reverse_matches: HashMap<usize, usize> = matches.iter().map(|(o, n)| (*n, *o)).collect();
+
+    let mut changes = Vec::new();
+
+    for (index, node) in old.iter().enumerate() {
+        if !matches.contains_key(&index) {
+            changes.push(ItemChange::Deleted {
+                item_id: node.id.clone(),
+                parent_id: parent_id(&old, node),
+                text: node.text.to_string(),
+            });
+        }
+    }
+
+    for (index, node) in new.iter().enumerate() {
+        if !reverse_matches.contains_key(&index) {
+            changes.push(ItemChange::Inserted {
+                item_id: node.id.clone(),
+                parent_id: parent_id(&new, node),
+                text: node.text.to_string(),
+            });
+        }
+    }
+
+    // items that kept their parent only count as moved if they left the
+    // longest run of siblings that are still in their original order
+    let mut siblings = HashMap::<usize, Vec<(i32, i32, usize)>>::new();
+    let mut moved = vec![false; old.len()];
+
+    for (old_index, new_index) in matches.iter() {
+        let (old_node, new_node) = (&old[*old_index], &new[*new_index]);
+
+        if let (Some(old_parent), Some(new_parent)) = (old_node.parent, new_node.parent) {
+            if matches.get(&old_parent) == Some(&new_parent) {
+                siblings.entry(new_parent).or_insert(Vec::new()).push((
+                    new_node.order,
+                    old_node.order,
+                    *old_index,
+                ));
+            } else {
+                moved[*old_index] = true;
+            }
+        }
+    }
+
+    for children in siblings.values_mut() {
+        children.sort();
+
+        let old_orders: Vec<i32> = children.iter().map(|c| c.1).collect();
+        let in_order: HashSet<usize> = longest_increasing(&old_orders).into_iter().collect();
+
+        for (position, child) in children.iter().enumerate() {
+            if !in_order.contains(&position) {
+                moved[child.2] = true;
+            }
+        }
+    }
+
+    let mut matched: Vec<(&usize, &usize)> = matches.iter().collect();
+    matched.sort();
+
+    for (old_index, new_index) in matched {
+        let (old_node, new_node) = (&old[*old_index], &new[*new_index]);
+
+        if moved[*old_index] {
+            changes.push(ItemChange::Moved {
+                old_item_id: old_node.id.clone(),
+                new_item_id: new_node.id.clone(),
+                old_parent_id: parent_id(&old, old_node),
+                new_parent_id: parent_id(&new, new_node),
+            });
+        }
+
+        if old_node.text != new_node.text {
+            changes.push(ItemChange::TextChanged {
+                old_item_id: old_node.id.clone(),
+                new_item_id: new_node.id.clone(),
+                old_text: old_node.text.to_string(),
+                new_text: new_node.text.to_string(),
+                words: diff_words(old_node.text, new_node.text),
+            });
+        }
+    }
+
+    changes
+}
+
+// the item from the common base that a change applies to
+fn base_item_id(change: &ItemChange) -> Option<&ItemID> {
+    match change {
+        ItemChange::Inserted { .. } => None,
+        ItemChange::Deleted { item_id, .. } => Some(item_id),
+        ItemChange::Moved { old_item_id, .. } => Some(old_item_id),
+        ItemChange::TextChanged { old_item_id, .. } => Some(old_item_id),
+    }
+}
+
+fn new_text(change: &ItemChange) -> Option<&String> {
+    match change {
+        ItemChange::TextChanged { new_text, .. } => Some(new_text),
+        _ => None,
+    }
+}
+
+enum Resolution {
+    Apply,
+    AlreadyApplied,
+    Conflict(ConflictReason),
+}
+
+fn resolve(upstream: &ItemChange, copy_changes: &[&ItemChange]) -> Resolution {
+    let copy_edit = copy_changes.iter().filter_map(|c| new_text(c)).next();
+    let copy_deleted = copy_changes.iter().any(|c| match c {
+        ItemChange::Deleted { .. } => true,
+        _ => false,
+    });
+    let copy_moved = copy_changes.iter().any(|c| match c {
+        ItemChange::Moved { .. } => true,
+        _ => false,
+    });
+
+    match upstream {
+        ItemChange::TextChanged { new_text, .. } => match copy_edit {
+            Some(copy_text) if copy_text == new_text => Resolution::AlreadyApplied,
+            Some(_) => Resolution::Conflict(ConflictReason::BothEdited),
+            None if copy_deleted => {
+                Resolution::Conflict(ConflictReason::EditedUpstreamDeletedInCopy)
+            }
+            None => Resolution::Apply,
+        },
+        ItemChange::Deleted { .. } if copy_deleted => Resolution::AlreadyApplied,
+        ItemChange::Deleted { .. } if copy_edit.is_some() => {
+            Resolution::Conflict(ConflictReason::DeletedUpstreamEditedInCopy)
+        }
+        ItemChange::Moved { .. } if copy_deleted => Resolution::AlreadyApplied,
+        ItemChange::Moved { .. } if copy_moved => Resolution::Conflict(ConflictReason::BothMoved),
+        _ => Resolution::Apply,
+    }
+}
+
+// a three-way comparison: upstream changes since the base that the copy
+// hasn't also made, and the items both sides changed in incompatible ways
+pub fn preview_merge(
+    base_items: &[OutlineItem],
+    base_root_id: &ItemID,
+    upstream_items: &[OutlineItem],
+    upstream_root_id: &ItemID,
+    copy_items: &[OutlineItem],
+    copy_root_id: &ItemID,
+) -> MergePreview {
+    let upstream_changes =
+        diff_outlines(base_items, base_root_id, upstream_items, upstream_root_id);
+    let copy_changes = diff_outlines(base_items, base_root_id, copy_items, copy_root_id);
+
+    let mut copy_by_base = HashMap::<&ItemID, Vec<&ItemChange>>::new();
+    for change in copy_changes.iter() {
+        if let Some(item_id) = base_item_id(change) {
+            copy_by_base
+                .entry(item_id)
+                .or_insert(Vec::new())
+                .push(change);
+        }
+    }
+
+    let base_texts: HashMap<&ItemID, &String> = base_items
+        .iter()
+        .map(|i| (&i.item_id, &i.item_text))
+        .collect();
+
+    let mut changes = Vec::new();
+    let mut conflicts = Vec::new();
+
+    for change in upstream_changes {
+        let resolution = match base_item_id(&change) {
+            Some(item_id) => resolve(
+                &change,
+                copy_by_base.get(item_id).map(|c| &c[..]).unwrap_or(&[]),
+            ),
+            None => Resolution::Apply,
+        };
+
+        match resolution {
+            Resolution::Apply => changes.push(change),
+            Resolution::AlreadyApplied => {}
+            Resolution::Conflict(reason) => {
+                let item_id = base_item_id(&change)
+                    .cloned()
+                    .unwrap_or_else(|| base_root_id.clone());
+                let copy_text = copy_by_base
+                    .get(&item_id)
+                    .and_then(|c| c.iter().filter_map(|c| new_text(c)).next())
+                    .cloned();
+
+                conflicts.push(MergeConflict {
+                    base_text: base_texts
+                        .get(&item_id)
+                        .map(|t| t.to_string())
+                        .unwrap_or_default(),
+                    upstream_text: new_text(&change).cloned(),
+                    copy_text,
+                    item_id,
+                    reason,
+                });
+            }
+        }
+    }
+
+    MergePreview { changes, conflicts }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXTS: [&str; 3] = ["alpha one", "bravo two", "charlie three"];
+
+    fn outline_item(
+        item_id: &ItemID,
+        parent_id: Option<&ItemID>,
+        child_order: i32,
+        item_text: &str,
+    ) -> OutlineItem {
+        OutlineItem {
+            item_id: item_id.clone(),
+            parent_id: parent_id.cloned(),
+            child_order,
+            item_text: item_text.to_string(),
+        }
+    }
+
+    // a title with a paragraph below it for each of TEXTS
+    fn base() -> (ItemID, Vec<ItemID>, Vec<OutlineItem>) {
+        let root_id = ItemID::generate();
+        let item_ids: Vec<ItemID> = TEXTS.iter().map(|_| ItemID::generate()).collect();
+
+        let mut items = vec![outline_item(&root_id, None, 0, "Title")];
+        for (order, (item_id, text)) in item_ids.iter().zip(TEXTS.iter()).enumerate() {
+            items.push(outline_item(item_id, Some(&root_id), order as i32, text));
+        }
+
+        (root_id, item_ids, items)
+    }
+
+    fn edited(items: &[OutlineItem], item_id: &ItemID, text: &str) -> Vec<OutlineItem> {
+        items
+            .iter()
+            .cloned()
+            .map(|mut i| {
+                if i.item_id == *item_id {
+                    i.item_text = text.to_string();
+                }
+                i
+            })
+            .collect()
+    }
+
+    fn without(items: &[OutlineItem], item_id: &ItemID) -> Vec<OutlineItem> {
+        items
+            .iter()
+            .filter(|i| i.item_id != *item_id)
+            .cloned()
+            .collect()
+    }
+
+    fn moved(items: &[OutlineItem], item_id: &ItemID, parent_id: &ItemID) -> Vec<OutlineItem> {
+        items
+            .iter()
+            .cloned()
+            .map(|mut i| {
+                if i.item_id == *item_id {
+                    i.parent_id = Some(parent_id.clone());
+                    i.child_order = 0;
+                }
+                i
+            })
+            .collect()
+    }
+
+    fn reasons(preview: &MergePreview) -> Vec<ConflictReason> {
+        preview.conflicts.iter().map(|c| c.reason).collect()
+    }
+
+    #[test]
+    fn reports_inserted_items() {
+        let (root_id, _, items) = base();
+        let new_id = ItemID::generate();
+        let mut new_items = items.clone();
+        new_items.push(outline_item(&new_id, Some(&root_id), 3, "delta four"));
+
+        let changes = diff_outlines(&items, &root_id, &new_items, &root_id);
+
+        assert_eq!(changes.len(), 1);
+        match changes[0] {
+            ItemChange::Inserted {
+                ref item_id,
+                ref parent_id,
+                ref text,
+            } => {
+                assert_eq!(*item_id, new_id);
+                assert_eq!(*parent_id, Some(root_id.clone()));
+                assert_eq!(text, "delta four");
+            }
+            _ => panic!("expected an insertion"),
+        }
+    }
+
+    #[test]
+    fn reports_deleted_items_without_moving_their_siblings() {
+        let (root_id, item_ids, items) = base();
+
+        let changes = diff_outlines(&items, &root_id, &without(&items, &item_ids[1]), &root_id);
+
+        assert_eq!(changes.len(), 1);
+        match changes[0] {
+            ItemChange::Deleted {
+                ref item_id,
+                ref parent_id,
+                ref text,
+            } => {
+                assert_eq!(*item_id, item_ids[1]);
+                assert_eq!(*parent_id, Some(root_id.clone()));
+                assert_eq!(text, "bravo two");
+            }
+            _ => panic!("expected a deletion"),
+        }
+    }
+
+    #[test]
+    fn reports_items_moved_to_another_parent() {
+        let (root_id, item_ids, items) = base();
+        let new_items = moved(&items, &item_ids[2], &item_ids[0]);
+
+        let changes = diff_outlines(&items, &root_id, &new_items, &root_id);
+
+        assert_eq!(changes.len(), 1);
+        match changes[0] {
+            ItemChange::Moved {
+                ref old_item_id,
+                ref old_parent_id,
+                ref new_parent_id,
+                ..
+            } => {
+                assert_eq!(*old_item_id, item_ids[2]);
+                assert_eq!(*old_parent_id, Some(root_id.clone()));
+                assert_eq!(*new_parent_id, Some(item_ids[0].clone()));
+            }
+            _ => panic!("expected a move"),
+        }
+    }
+
+    #[test]
+    fn matches_reworded_items_across_new_ids() {
+        let (root_id, item_ids, items) = base();
+
+        // a copy gives every item a new ID, so only the text ties them together
+        let copy_root_id = ItemID::generate();
+        let mut copy_items = vec![outline_item(&copy_root_id, None, 0, "Title")];
+        for (order, text) in ["alpha one", "bravo number two", "charlie three"]
+            .iter()
+            .enumerate()
+        {
+            copy_items.push(outline_item(
+                &ItemID::generate(),
+                Some(&copy_root_id),
+                order as i32,
+                text,
+            ));
+        }
+
+        let changes = diff_outlines(&items, &root_id, &copy_items, &copy_root_id);
+
+        assert_eq!(changes.len(), 1);
+        match changes[0] {
+            ItemChange::TextChanged {
+                ref old_item_id,
+                ref new_item_id,
+                ref words,
+                ..
+            } => {
+                assert_eq!(*old_item_id, item_ids[1]);
+                assert_eq!(*new_item_id, copy_items[2].item_id);
+
+                let words: Vec<(WordOp, &str)> =
+                    words.iter().map(|w| (w.op, w.text.as_str())).collect();
+                assert_eq!(
+                    words,
+                    vec![
+                        (WordOp::Equal, "bravo"),
+                        (WordOp::Insert, "number"),
+                        (WordOp::Equal, "two"),
+                    ]
+                );
+            }
+            _ => panic!("expected a text change"),
+        }
+    }
+
+    #[test]
+    fn diffs_words_within_a_sentence() {
+        let words: Vec<(WordOp, String)> = diff_words("the quick brown fox", "the slow brown dog")
+            .into_iter()
+            .map(|w| (w.op, w.text))
+            .collect();
+
+        assert_eq!(
+            words,
+            vec![
+                (WordOp::Equal, "the".to_string()),
+                (WordOp::Insert, "slow".to_string()),
+                (WordOp::Delete, "quick".to_string()),
+                (WordOp::Equal, "brown".to_string()),
+                (WordOp::Insert, "dog".to_string()),
+                (WordOp::Delete, "fox".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn merges_upstream_changes_the_copy_has_not_made() {
+        let (root_id, item_ids, items) = base();
+        let upstream = edited(&items, &item_ids[0], "alpha one revised");
+        let copy = edited(&items, &item_ids[2], "charlie three revised");
+
+        let preview = preview_merge(&items, &root_id, &upstream, &root_id, &copy, &root_id);
+
+        assert!(preview.conflicts.is_empty());
+        assert_eq!(preview.changes.len(), 1);
+        assert_eq!(base_item_id(&preview.changes[0]), Some(&item_ids[0]));
+    }
+
+    #[test]
+    fn skips_changes_already_made_in_the_copy() {
+        let (root_id, item_ids, items) = base();
+        let upstream = edited(&items, &item_ids[1], "bravo two revised");
+
+        let preview = preview_merge(&items, &root_id, &upstream, &root_id, &upstream, &root_id);
+
+        assert!(preview.changes.is_empty());
+        assert!(preview.conflicts.is_empty());
+    }
+
+    #[test]
+    fn conflicts_when_both_sides_edit_an_item() {
+        let (root_id, item_ids, items) = base();
+        let upstream = edited(&items, &item_ids[1], "bravo two upstream");
+        let copy = edited(&items, &item_ids[1], "bravo two copy");
+
+        let preview = preview_merge(&items, &root_id, &upstream, &root_id, &copy, &root_id);
+
+        assert!(preview.changes.is_empty());
+        assert_eq!(reasons(&preview), vec![ConflictReason::BothEdited]);
+
+        let conflict = &preview.conflicts[0];
+        assert_eq!(conflict.item_id, item_ids[1]);
+        assert_eq!(conflict.base_text, "bravo two");
+        assert_eq!(
+            conflict.upstream_text,
+            Some("bravo two upstream".to_string())
+        );
+        assert_eq!(conflict.copy_text, Some("bravo two copy".to_string()));
+    }
+
+    #[test]
+    fn conflicts_when_an_item_edited_upstream_is_deleted_in_the_copy() {
+        let (root_id, item_ids, items) = base();
+        let upstream = edited(&items, &item_ids[1], "bravo two upstream");
+        let copy = without(&items, &item_ids[1]);
+
+        let preview = preview_merge(&items, &root_id, &upstream, &root_id, &copy, &root_id);
+
+        assert_eq!(
+            reasons(&preview),
+            vec![ConflictReason::EditedUpstreamDeletedInCopy]
+        );
+        assert_eq!(preview.conflicts[0].copy_text, None);
+    }
+
+    #[test]
+    fn conflicts_when_an_item_deleted_upstream_is_edited_in_the_copy() {
+        let (root_id, item_ids, items) = base();
+        let upstream = without(&items, &item_ids[1]);
+        let copy = edited(&items, &item_ids[1], "bravo two copy");
+
+        let preview = preview_merge(&items, &root_id, &upstream, &root_id, &copy, &root_id);
+
+        assert_eq!(
+            reasons(&preview),
+            vec![ConflictReason::DeletedUpstreamEditedInCopy]
+        );
+        assert_eq!(preview.conflicts[0].upstream_text, None);
+        assert_eq!(
+            preview.conflicts[0].copy_text,
+            Some("bravo two copy".to_string())
+        );
+    }
+
+    #[test]
+    fn conflicts_when_both_sides_move_an_item() {
+        let (root_id, item_ids, items) = base();
+        let upstream = moved(&items, &item_ids[2], &item_ids[0]);
+        let copy = moved(&items, &item_ids[2], &item_ids[1]);
+
+        let preview = preview_merge(&items, &root_id, &upstream, &root_id, &copy, &root_id);
+
+        assert!(preview.changes.is_empty());
+        assert_eq!(reasons(&preview), vec![ConflictReason::BothMoved]);
+    }
+}
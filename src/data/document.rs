@@ -1,21 +1,34 @@
 use diesel;
+use diesel::pg::Pg;
 use diesel::prelude::*;
 
 use data;
+use data::attachment::Attachment;
 use data::category::Category;
 use data::db::Connection;
-use data::item::{Item, ItemID, ItemWithStyles};
+use data::diff::{diff_outlines, ItemChange, OutlineItem};
+use data::document_style::DocumentStyle;
+use data::folder::FolderID;
+use data::fork::Fork;
+use data::item::{Item, ItemID, ItemWithStyles, TaskRollup};
+use data::numbering::number_outline;
+use data::rich_text::ItemText;
+use data::sanitize::sanitize_text;
 use data::schema::documents;
 use data::schema::documents::dsl::*;
 use data::schema::items;
 use data::schema::items::dsl::*;
+use data::schema::{ItemKind, NumberingScheme, StyleProperty, TocGeneration};
+use data::stats::{compute_stats, DocumentStats};
+use data::style::{ComputedStyle, Style};
+use data::style_preset::StylePreset;
 use data::user::UserID;
 
 use uuid;
 
 use serde::ser::{Serialize, SerializeStruct, Serializer};
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::time::SystemTime;
 
 #[derive(TaggedID, Serialize, Deserialize)]
@@ -34,6 +47,19 @@ pub struct SerializableDocument<'a> {
 pub struct DocumentWithItems<'a> {
     document: SerializableDocument<'a>,
     items_hashmap: HashMap<ItemID, ItemWithStyles<'a>>,
+    default_styles: Vec<DocumentStyle<'a>>,
+    presets: Vec<StylePreset<'a>>,
+    attachments: Vec<Attachment<'a>>,
+}
+
+#[derive(Serialize)]
+pub struct SubtreeWithItems<'a> {
+    root_item_id: ItemID,
+    title: String,
+    numbering_scheme: Option<NumberingScheme>,
+    items: HashMap<ItemID, ItemWithStyles<'a>>,
+    default_styles: Vec<DocumentStyle<'a>>,
+    presets: Vec<StylePreset<'a>>,
 }
 
 #[derive(Debug, Queryable, Identifiable)]
@@ -46,6 +72,10 @@ pub struct Data {
     pub modified_at: Option<SystemTime>,
     pub publicly_viewable: bool,
     toc_item_id: Option<uuid::Uuid>,
+    pub toc_generation: Option<TocGeneration>,
+    pub toc_depth: i32,
+    pub numbering_scheme: Option<NumberingScheme>,
+    folder_id: Option<uuid::Uuid>,
 }
 
 #[derive(Insertable)]
@@ -88,13 +118,16 @@ impl<'a> Document<'a> {
         p_order: i32,
         text: Option<String>,
     ) -> QueryResult<Item> {
+        let text = sanitize_text(&text.unwrap_or("".to_string()))
+            .map_err(|e| diesel::result::Error::QueryBuilderError(Box::new(e)))?;
+
         let insert_item = NewItem {
             document_id: *self.get_id(),
             parent_id: match p_parent_id {
                 Some(pid) => Some(*pid),
                 None => None,
             },
-            item_text: &text.unwrap_or("".to_string()),
+            item_text: &text,
             child_order: p_order,
             collapsed: false,
         };
@@ -149,6 +182,130 @@ impl<'a> Document<'a> {
             .collect())
     }
 
+    pub fn get_public(connection: &'a Connection, p_limit: i64) -> QueryResult<Vec<Document<'a>>> {
+        let docs = documents
+            .filter(publicly_viewable.eq(true))
+            .order(data::schema::documents::dsl::created_at.desc())
+            .limit(p_limit)
+            .load::<Data>(&connection.pg_connection)?;
+
+        Ok(docs
+            .into_iter()
+            .map(|data| Document { connection, data })
+            .collect())
+    }
+
+    // search terms are matched literally against the text of every item
+    fn public_query(p_search: Option<&str>) -> documents::BoxedQuery<'static, Pg> {
+        let query = documents.filter(publicly_viewable.eq(true)).into_boxed();
+
+        match p_search.map(|s| s.trim()).filter(|s| *s != "") {
+            Some(search) => {
+                let pattern = format!(
+                    "%{}%",
+                    search
+                        .replace('\\', "\\\\")
+                        .replace('%', "\\%")
+                        .replace('_', "\\_")
+                );
+
+                let matching_documents = items::table
+                    .select(items::document_id)
+                    .filter(items::item_text.ilike(pattern));
+
+                query.filter(data::schema::documents::dsl::id.eq_any(matching_documents))
+            }
+            None => query,
+        }
+    }
+
+    pub fn search_public(
+        connection: &'a Connection,
+        p_search: Option<&str>,
+        page: i64,
+        per_page: i64,
+    ) -> QueryResult<Vec<Document<'a>>> {
+        // pages too far out to address are past the end of the directory anyway
+        let offset = match page.checked_mul(per_page) {
+            Some(o) if o >= 0 => o,
+            _ => return Ok(Vec::new()),
+        };
+
+        let docs = Self::public_query(p_search)
+            .order((
+                modified_at.desc().nulls_last(),
+                data::schema::documents::dsl::created_at.desc(),
+            ))
+            .offset(offset)
+            .limit(per_page)
+            .load::<Data>(&connection.pg_connection)?;
+
+        Ok(docs
+            .into_iter()
+            .map(|data| Document { connection, data })
+            .collect())
+    }
+
+    pub fn count_public(connection: &'a Connection, p_search: Option<&str>) -> QueryResult<i64> {
+        Self::public_query(p_search)
+            .count()
+            .get_result(&connection.pg_connection)
+    }
+
+    pub fn get_public_by_user(
+        connection: &'a Connection,
+        p_user_id: &UserID,
+    ) -> QueryResult<Vec<Document<'a>>> {
+        let docs = Self::public_query(None)
+            .filter(user_id.eq(&**p_user_id))
+            .order((
+                modified_at.desc().nulls_last(),
+                data::schema::documents::dsl::created_at.desc(),
+            ))
+            .load::<Data>(&connection.pg_connection)?;
+
+        Ok(docs
+            .into_iter()
+            .map(|data| Document { connection, data })
+            .collect())
+    }
+
+    // None lists the documents that are not in any folder
+    pub fn get_by_user_in_folder(
+        connection: &'a Connection,
+        p_user_id: &UserID,
+        p_folder_id: Option<&FolderID>,
+    ) -> QueryResult<Vec<Document<'a>>> {
+        let query = documents.filter(user_id.eq(&**p_user_id)).into_boxed();
+
+        let docs = match p_folder_id {
+            Some(f) => query.filter(folder_id.eq(&**f)),
+            None => query.filter(folder_id.is_null()),
+        }
+        .load::<Data>(&connection.pg_connection)?;
+
+        Ok(docs
+            .into_iter()
+            .map(|data| Document { connection, data })
+            .collect())
+    }
+
+    pub fn get_folder_id(&self) -> Option<FolderID> {
+        self.data.folder_id.map(FolderID::from_uuid)
+    }
+
+    pub fn move_to_folder(
+        &mut self,
+        p_folder_id: Option<&FolderID>,
+    ) -> QueryResult<&mut Document<'a>> {
+        self.data = diesel::update(documents)
+            .filter(data::schema::documents::dsl::id.eq(&self.data.id))
+            .set(folder_id.eq(p_folder_id.map(|f| **f)))
+            .get_result(&self.connection.pg_connection)?;
+
+        Ok(self)
+    }
+
     pub fn touch(&mut self) -> QueryResult<&mut Document<'a>> {
         let updated = diesel::update(documents)
             .filter(data::schema::documents::dsl::id.eq(&self.data.id))
@@ -172,6 +329,10 @@ impl<'a> Document<'a> {
         self.data.user_id.eq(&**p_user_id)
     }
 
+    pub fn get_owner_id(&self) -> UserID {
+        UserID::from_uuid(self.data.user_id.clone())
+    }
+
     pub fn is_owned_by(&self, p_user_id: &UserID) -> bool {
         self.data.user_id.eq(&**p_user_id)
     }
@@ -180,6 +341,45 @@ impl<'a> Document<'a> {
         super::item::Item::get_by_document(self.connection, &self.get_id())
     }
 
+    pub fn get_stats(&self) -> QueryResult<DocumentStats> {
+        let root_id = self
+            .get_serialized_root_id()
+            .ok_or(diesel::result::Error::NotFound)?;
+
+        Ok(compute_stats(
+            &self.get_items()?,
+            &root_id,
+            self.get_serialized_toc_id().as_ref(),
+            self.last_modified(),
+        ))
+    }
+
+    // changes that turn this document's outline into the other's
+    pub fn diff_against(&self, other: &Document) -> QueryResult<Vec<ItemChange>> {
+        let root_id = self
+            .get_serialized_root_id()
+            .ok_or(diesel::result::Error::NotFound)?;
+        let other_root_id = other
+            .get_serialized_root_id()
+            .ok_or(diesel::result::Error::NotFound)?;
+
+        Ok(diff_outlines(
+            &self.get_outline()?,
+            &root_id,
+            &other.get_outline()?,
+            &other_root_id,
+        ))
+    }
+
+    pub fn get_outline(&self) -> QueryResult<Vec<OutlineItem>> {
+        Ok(self.get_items()?.iter().map(OutlineItem::from).collect())
+    }
+
+    // documents have no revision numbers, so their last change stands in
+    pub fn last_modified(&self) -> SystemTime {
+        self.data.modified_at.unwrap_or(self.data.created_at)
+    }
+
     pub fn get_item(&self, p_item_id: &ItemID) -> QueryResult<Item> {
         let data = items
             .filter(document_id.eq(&*self.get_id()))
@@ -189,6 +389,60 @@ impl<'a> Document<'a> {
         Ok(Item::new(&self.connection, data))
     }
 
+    // an item is protected by its own lock or one on any of its ancestors, and
+    // replacing an item's children also touches every lock below it
+    pub fn find_lock(
+        &self,
+        p_item_ids: &[ItemID],
+        include_descendants: bool,
+    ) -> QueryResult<Option<ItemID>> {
+        let doc_items = self.get_items()?;
+
+        let mut parents = HashMap::<ItemID, Option<ItemID>>::new();
+        let mut children = HashMap::<ItemID, Vec<ItemID>>::new();
+        let mut locked = HashSet::<ItemID>::new();
+        for i in doc_items.iter() {
+            let item_id = i.get_id();
+
+            if let Some(pid) = i.get_parent_id() {
+                children
+                    .entry(pid.clone())
+                    .or_insert(Vec::new())
+                    .push(item_id.clone());
+            }
+            if i.is_locked() {
+                locked.insert(item_id.clone());
+            }
+            parents.insert(item_id, i.get_parent_id());
+        }
+
+        if locked.is_empty() {
+            return Ok(None);
+        }
+
+        for item_id in p_item_ids.iter() {
+            let mut curr = Some(item_id.clone());
+            while let Some(c) = curr {
+                if locked.contains(&c) {
+                    return Ok(Some(c));
+                }
+                curr = parents.get(&c).cloned().unwrap_or(None);
+            }
+
+            if include_descendants {
+                let mut pending = children.get(item_id).cloned().unwrap_or_default();
+                while let Some(c) = pending.pop() {
+                    if locked.contains(&c) {
+                        return Ok(Some(c));
+                    }
+                    pending.extend(children.get(&c).cloned().unwrap_or_default());
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
     fn copy_item_children(
         self: &mut Document<'a>,
         p_items: &Vec<super::item::Item<'a>>,
@@ -225,12 +479,29 @@ impl<'a> Document<'a> {
         new_parent_id: Option<ItemID>,
     ) -> QueryResult<()> {
         let new_item_id = {
-            self.add_item(
+            let mut new_item = self.add_item(
                 new_parent_id,
                 curr_item.data.child_order,
                 Some(curr_item.data.item_text.clone()),
-            )?
-            .get_id()
+            )?;
+
+            let text = curr_item.get_text();
+            if text.is_rich() {
+                new_item.update_text(&text)?;
+            }
+
+            if curr_item.data.task_state.is_some() || curr_item.data.due_at.is_some() {
+                new_item.update_task(curr_item.data.task_state, curr_item.data.due_at)?;
+            }
+
+            if curr_item.data.kind != ItemKind::Paragraph {
+                new_item.set_kind(
+                    curr_item.data.kind,
+                    curr_item.data.code_language.as_ref().map(|l| l.as_str()),
+                )?;
+            }
+
+            new_item.get_id()
         };
 
         self.copy_item_children(&p_items, curr_item.get_id(), Some(new_item_id))?;
@@ -256,6 +527,8 @@ impl<'a> Document<'a> {
             None => Ok(()),
         }?;
 
+        Fork::create(self.connection, &new_document.get_id(), self)?;
+
         Ok(new_document)
     }
 
@@ -274,7 +547,7 @@ impl<'a> Document<'a> {
 
     pub fn rename(&mut self, update_text: &str) -> QueryResult<()> {
         let mut root_item = self.get_root()?;
-        root_item.update_text(update_text)?;
+        root_item.update_text(&ItemText::Plain(update_text.to_string()))?;
 
         Ok(())
     }
@@ -306,7 +579,7 @@ impl<'a> Document<'a> {
         }
     }
 
-    fn get_serialized_root_id(&self) -> Option<ItemID> {
+    pub fn get_serialized_root_id(&self) -> Option<ItemID> {
         if let Some(root_id) = self.data.root_item_id {
             Some(ItemID::from_uuid(root_id))
         } else {
@@ -350,6 +623,36 @@ impl<'a> Document<'a> {
         Ok(self)
     }
 
+    pub fn set_toc_generation(
+        &mut self,
+        p_toc_generation: Option<TocGeneration>,
+        p_toc_depth: i32,
+    ) -> QueryResult<&mut Self> {
+        let new_data = diesel::update(documents)
+            .filter(data::schema::documents::dsl::id.eq(&self.data.id))
+            .set((
+                toc_generation.eq(p_toc_generation),
+                toc_depth.eq(p_toc_depth),
+            ))
+            .get_result(&self.connection.pg_connection)?;
+
+        self.data = new_data;
+        Ok(self)
+    }
+
+    pub fn set_numbering_scheme(
+        &mut self,
+        p_numbering_scheme: Option<NumberingScheme>,
+    ) -> QueryResult<&mut Self> {
+        let new_data = diesel::update(documents)
+            .filter(data::schema::documents::dsl::id.eq(&self.data.id))
+            .set(numbering_scheme.eq(p_numbering_scheme))
+            .get_result(&self.connection.pg_connection)?;
+
+        self.data = new_data;
+        Ok(self)
+    }
+
     pub fn serializable(
         &'a self,
         p_user_id: Option<&UserID>,
@@ -365,45 +668,333 @@ impl<'a> Document<'a> {
         })
     }
 
-    pub fn serialize_with_items(
-        &'a self,
-        p_user_id: Option<&UserID>,
-    ) -> QueryResult<DocumentWithItems<'a>> {
-        let items_hashmap = self
+    fn get_items_with_styles(&self) -> QueryResult<HashMap<ItemID, ItemWithStyles<'a>>> {
+        let mut styles_by_item = HashMap::<ItemID, Vec<Style>>::new();
+        for s in Style::get_by_document(self.connection, &self.get_id())?.into_iter() {
+            styles_by_item
+                .entry(s.get_item_id())
+                .or_insert(Vec::new())
+                .push(s);
+        }
+
+        Ok(self
             .get_items()?
             .into_iter()
-            .map(|i| ItemWithStyles::from(i))
-            .collect::<QueryResult<Vec<ItemWithStyles>>>()?
-            .into_iter()
+            .map(|i| {
+                let item_styles = styles_by_item.remove(&i.get_id()).unwrap_or(Vec::new());
+                ItemWithStyles::new(i, item_styles)
+            })
             .fold(std::collections::HashMap::new(), |mut acc, i| {
                 acc.entry(i.item.get_id()).or_insert(i);
                 acc
-            });
+            }))
+    }
+
+    pub fn serialize_with_items(
+        &'a self,
+        p_user_id: Option<&UserID>,
+    ) -> QueryResult<DocumentWithItems<'a>> {
+        let items_hashmap = self.get_items_with_styles()?;
+
+        let default_styles = DocumentStyle::get_by_document(self.connection, &self.get_id())?;
+
+        let presets =
+            StylePreset::get_available(self.connection, &self.get_id(), &self.get_owner_id())?;
+
+        let mut items_hashmap =
+            cascade_styles(roll_up_tasks(items_hashmap), &default_styles, &presets);
+
+        if let Some(root_id) = self.get_serialized_root_id() {
+            let positions = number_outline(
+                items_hashmap.values().map(|i| &i.item),
+                &root_id,
+                self.get_serialized_toc_id().as_ref(),
+                self.data.numbering_scheme,
+            );
+
+            for (item_id, i) in items_hashmap.iter_mut() {
+                if let Some(position) = positions.get(item_id) {
+                    i.depth = position.depth;
+                    i.number = position.number.clone();
+                }
+            }
+        }
 
         Ok(DocumentWithItems::<'a> {
             document: self.serializable(p_user_id)?,
             items_hashmap,
+            default_styles,
+            presets,
+            attachments: Attachment::get_by_document(self.connection, &self.get_id())?,
+        })
+    }
+
+    // each item's styles once presets, inheritance and the defaults are applied
+    pub fn get_effective_styles(
+        &self,
+    ) -> QueryResult<HashMap<ItemID, HashMap<StyleProperty, ComputedStyle>>> {
+        let default_styles = DocumentStyle::get_by_document(self.connection, &self.get_id())?;
+        let presets =
+            StylePreset::get_available(self.connection, &self.get_id(), &self.get_owner_id())?;
+
+        Ok(
+            cascade_styles(self.get_items_with_styles()?, &default_styles, &presets)
+                .into_iter()
+                .map(|(item_id, i)| (item_id, i.effective_styles))
+                .collect(),
+        )
+    }
+
+    // the scoped item stands in for the root, and nothing above or beside it
+    // is loaded into the result, not even through references
+    pub fn serialize_subtree(&'a self, p_item_id: &ItemID) -> QueryResult<SubtreeWithItems<'a>> {
+        let items_hashmap = self.get_items_with_styles()?;
+        let toc_id = self.get_serialized_toc_id();
+
+        // the table of contents lists headings from the whole document
+        if !items_hashmap.contains_key(p_item_id) || toc_id.as_ref() == Some(p_item_id) {
+            return Err(diesel::result::Error::NotFound);
+        }
+
+        let mut children = HashMap::<ItemID, Vec<ItemID>>::new();
+        for (item_id, i) in items_hashmap.iter() {
+            if let Some(pid) = i.item.get_parent_id() {
+                children
+                    .entry(pid)
+                    .or_insert(Vec::new())
+                    .push(item_id.clone());
+            }
+        }
+
+        let mut included = HashSet::<ItemID>::new();
+        let mut pending = vec![p_item_id.clone()];
+        while let Some(curr) = pending.pop() {
+            if toc_id.as_ref() == Some(&curr) {
+                continue;
+            }
+
+            if let Some(child_ids) = children.get(&curr) {
+                pending.extend(child_ids.iter().cloned());
+            }
+            included.insert(curr);
+        }
+
+        // styles are cascaded before anything is dropped, so the subtree still
+        // inherits from the ancestors that are left out of it
+        let default_styles = DocumentStyle::get_by_document(self.connection, &self.get_id())?;
+        let available_presets =
+            StylePreset::get_available(self.connection, &self.get_id(), &self.get_owner_id())?;
+
+        let mut items_hashmap = cascade_styles(items_hashmap, &default_styles, &available_presets);
+        items_hashmap.retain(|item_id, _| included.contains(item_id));
+
+        for (item_id, i) in items_hashmap.iter_mut() {
+            if item_id == p_item_id {
+                i.item.data.parent_id = None;
+            }
+
+            let outside = i
+                .item
+                .data
+                .toc_target_id
+                .map(|t| !included.contains(&ItemID::from_uuid(t)))
+                .unwrap_or(false);
+            if outside {
+                i.item.data.toc_target_id = None;
+            }
+        }
+
+        let used_presets: HashSet<String> = items_hashmap
+            .values()
+            .filter_map(|i| i.item.data.style_preset.clone())
+            .collect();
+        let presets: Vec<StylePreset> = available_presets
+            .into_iter()
+            .filter(|p| used_presets.contains(&p.data.name))
+            .collect();
+
+        let mut items_hashmap = roll_up_tasks(items_hashmap);
+
+        let positions = number_outline(
+            items_hashmap.values().map(|i| &i.item),
+            p_item_id,
+            None,
+            self.data.numbering_scheme,
+        );
+
+        for (item_id, i) in items_hashmap.iter_mut() {
+            if let Some(position) = positions.get(item_id) {
+                i.depth = position.depth;
+                i.number = position.number.clone();
+            }
+        }
+
+        Ok(SubtreeWithItems::<'a> {
+            root_item_id: p_item_id.clone(),
+            title: items_hashmap
+                .get(p_item_id)
+                .map(|i| i.item.data.item_text.clone())
+                .unwrap_or_default(),
+            numbering_scheme: self.data.numbering_scheme,
+            items: items_hashmap,
+            default_styles,
+            presets,
         })
     }
 }
 
+fn roll_up_subtree(
+    curr: &ItemID,
+    children: &HashMap<ItemID, Vec<ItemID>>,
+    own: &HashMap<ItemID, TaskRollup>,
+    rollups: &mut HashMap<ItemID, TaskRollup>,
+) -> TaskRollup {
+    let mut rollup = own.get(curr).cloned().unwrap_or_default();
+
+    if let Some(child_ids) = children.get(curr) {
+        for child_id in child_ids {
+            rollup.add(&roll_up_subtree(child_id, children, own, rollups));
+        }
+    }
+
+    rollups.insert(curr.clone(), rollup);
+    rollup
+}
+
+fn roll_up_tasks<'a>(
+    mut items_hashmap: HashMap<ItemID, ItemWithStyles<'a>>,
+) -> HashMap<ItemID, ItemWithStyles<'a>> {
+    let mut children = HashMap::<ItemID, Vec<ItemID>>::new();
+    let mut own = HashMap::<ItemID, TaskRollup>::new();
+
+    for (item_id, i) in items_hashmap.iter() {
+        own.insert(item_id.clone(), i.task_rollup);
+        if let Some(pid) = i.item.get_parent_id() {
+            children
+                .entry(pid)
+                .or_insert(Vec::new())
+                .push(item_id.clone());
+        }
+    }
+
+    let mut rollups = HashMap::<ItemID, TaskRollup>::new();
+    for (item_id, i) in items_hashmap.iter() {
+        if i.item.get_parent_id().is_none() {
+            roll_up_subtree(item_id, &children, &own, &mut rollups);
+        }
+    }
+
+    for (item_id, i) in items_hashmap.iter_mut() {
+        if let Some(rollup) = rollups.get(item_id) {
+            i.task_rollup = *rollup;
+        }
+    }
+
+    items_hashmap
+}
+
+fn cascade_subtree(
+    curr: &ItemID,
+    inherited: &HashMap<StyleProperty, ComputedStyle>,
+    children: &HashMap<ItemID, Vec<ItemID>>,
+    presets: &HashMap<&str, &StylePreset>,
+    items_hashmap: &mut HashMap<ItemID, ItemWithStyles>,
+) {
+    let passed_down = match items_hashmap.get_mut(curr) {
+        Some(i) => {
+            let mut passed_down = inherited.clone();
+            for (prop, s) in i.styles.iter() {
+                if s.data.inherit {
+                    passed_down.insert(*prop, s.to_computed(true));
+                }
+            }
+
+            // an item's own styles always win over its preset, which in turn
+            // wins over the styles inherited from its ancestors
+            let mut effective_styles = inherited.clone();
+            if let Some(preset) = i
+                .item
+                .data
+                .style_preset
+                .as_ref()
+                .and_then(|p| presets.get(p.as_str()))
+            {
+                for p in preset.properties.iter() {
+                    effective_styles.insert(p.property, p.to_computed());
+                }
+            }
+            effective_styles.extend(i.effective_styles.drain());
+            i.effective_styles = effective_styles;
+
+            passed_down
+        }
+        None => return,
+    };
+
+    if let Some(child_ids) = children.get(curr) {
+        for child_id in child_ids {
+            cascade_subtree(child_id, &passed_down, children, presets, items_hashmap);
+        }
+    }
+}
+
+// resolves the effective styles of every item from its ancestors' inheritable
+// styles and the document defaults, visiting each item once
+fn cascade_styles<'a>(
+    mut items_hashmap: HashMap<ItemID, ItemWithStyles<'a>>,
+    default_styles: &Vec<DocumentStyle>,
+    presets: &Vec<StylePreset>,
+) -> HashMap<ItemID, ItemWithStyles<'a>> {
+    let mut children = HashMap::<ItemID, Vec<ItemID>>::new();
+    let mut roots = Vec::new();
+
+    for (item_id, i) in items_hashmap.iter() {
+        match i.item.get_parent_id() {
+            Some(pid) => children
+                .entry(pid)
+                .or_insert(Vec::new())
+                .push(item_id.clone()),
+            None => roots.push(item_id.clone()),
+        }
+    }
+
+    let defaults = default_styles
+        .iter()
+        .map(|s| (s.data.property, s.to_computed()))
+        .collect();
+
+    let presets_by_name = presets.iter().map(|p| (p.data.name.as_str(), p)).collect();
+
+    for root_id in roots.iter() {
+        cascade_subtree(
+            root_id,
+            &defaults,
+            &children,
+            &presets_by_name,
+            &mut items_hashmap,
+        );
+    }
+
+    items_hashmap
+}
+
 fn serialize_document<'a, S>(
     ser_document: &SerializableDocument<'a>,
     serializer: S,
-    items_hashmap: Option<&HashMap<ItemID, ItemWithStyles>>,
+    with_items: Option<&DocumentWithItems<'a>>,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
-    let count_fields = 9;
+    let count_fields = 16;
     let ref document = ser_document.document;
 
     let mut serialized = serializer.serialize_struct(
         "Document",
-        if items_hashmap.is_some() {
+        if with_items.is_some() {
             count_fields
         } else {
-            count_fields - 1
+            count_fields - 4
         },
     )?;
 
@@ -414,9 +1005,16 @@ where
     serialized.serialize_field("modified_at", &document.data.modified_at)?;
     serialized.serialize_field("publicly_viewable", &document.data.publicly_viewable)?;
     serialized.serialize_field("toc_item_id", &document.get_serialized_toc_id())?;
-
-    if let Some(ser_items) = items_hashmap {
-        serialized.serialize_field("items", ser_items)?;
+    serialized.serialize_field("toc_generation", &document.data.toc_generation)?;
+    serialized.serialize_field("toc_depth", &document.data.toc_depth)?;
+    serialized.serialize_field("numbering_scheme", &document.data.numbering_scheme)?;
+    serialized.serialize_field("folder_id", &document.get_folder_id())?;
+
+    if let Some(w) = with_items {
+        serialized.serialize_field("items", &w.items_hashmap)?;
+        serialized.serialize_field("default_styles", &w.default_styles)?;
+        serialized.serialize_field("presets", &w.presets)?;
+        serialized.serialize_field("attachments", &w.attachments)?;
     }
 
     serialized.serialize_field("categories", &ser_document.categories)?;
@@ -438,6 +1036,6 @@ impl<'a> Serialize for DocumentWithItems<'a> {
     where
         S: Serializer,
     {
-        serialize_document(&self.document, serializer, Some(&self.items_hashmap))
+        serialize_document(&self.document, serializer, Some(self))
     }
 }
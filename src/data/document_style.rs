@@ -0,0 +1,139 @@
+use diesel;
+use diesel::prelude::*;
+use diesel::Connection as DieselConnection;
+
+use data::db::Connection;
+use data::document::DocumentID;
+use data::schema::document_styles;
+use data::schema::document_styles::dsl::*;
+use data::schema::{StyleProperty, StyleUnit};
+use data::style::{validate_style, ComputedStyle, StyleError};
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use uuid;
+
+pub struct DocumentStyle<'a> {
+    connection: &'a Connection,
+    pub data: Data,
+}
+
+#[derive(Insertable, Queryable)]
+#[table_name = "document_styles"]
+pub struct Data {
+    document_id: uuid::Uuid,
+    pub property: StyleProperty,
+    pub value_number: Option<i32>,
+    pub value_string: Option<String>,
+    pub unit: Option<StyleUnit>,
+}
+
+impl<'a> DocumentStyle<'a> {
+    pub fn create(
+        connection: &'a Connection,
+        p_document_id: &DocumentID,
+        p_property: StyleProperty,
+        p_value_string: Option<String>,
+        p_value_number: Option<i32>,
+        p_unit: Option<StyleUnit>,
+    ) -> DocumentStyle<'a> {
+        DocumentStyle {
+            connection,
+            data: Data {
+                document_id: **p_document_id,
+                property: p_property,
+                value_string: p_value_string,
+                value_number: p_value_number,
+                unit: p_unit,
+            },
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), StyleError> {
+        validate_style(
+            self.data.property,
+            self.data.value_string.as_ref().map(|s| s.as_str()),
+            self.data.value_number,
+            self.data.unit,
+        )
+    }
+
+    pub fn get_by_document(
+        connection: &'a Connection,
+        p_document_id: &DocumentID,
+    ) -> QueryResult<Vec<DocumentStyle<'a>>> {
+        let p_document_uuid = **p_document_id;
+
+        let styles_list = document_styles
+            .filter(document_id.eq(&p_document_uuid))
+            .load::<Data>(&connection.pg_connection)?;
+
+        Ok(styles_list
+            .into_iter()
+            .map(|data| DocumentStyle { connection, data })
+            .collect())
+    }
+
+    // replaces all of a document's default styles with the given ones
+    pub fn replace_for_document(
+        connection: &'a Connection,
+        p_document_id: &DocumentID,
+        new_styles: Vec<DocumentStyle<'a>>,
+    ) -> QueryResult<Vec<DocumentStyle<'a>>> {
+        new_styles
+            .iter()
+            .map(|s| s.validate())
+            .collect::<Result<Vec<()>, StyleError>>()
+            .map_err(|e| diesel::result::Error::QueryBuilderError(Box::new(e)))?;
+
+        let p_document_uuid = **p_document_id;
+        let new_data: Vec<Data> = new_styles.into_iter().map(|s| s.data).collect();
+
+        let styles_list = connection
+            .pg_connection
+            .transaction::<_, diesel::result::Error, _>(|| {
+                diesel::delete(document_styles)
+                    .filter(document_id.eq(&p_document_uuid))
+                    .execute(&connection.pg_connection)?;
+
+                if new_data.is_empty() {
+                    return Ok(Vec::new());
+                }
+
+                diesel::insert_into(document_styles)
+                    .values(&new_data)
+                    .get_results::<Data>(&connection.pg_connection)
+            })?;
+
+        Ok(styles_list
+            .into_iter()
+            .map(|data| DocumentStyle { connection, data })
+            .collect())
+    }
+
+    pub fn to_computed(&self) -> ComputedStyle {
+        ComputedStyle {
+            property: self.data.property,
+            value_string: self.data.value_string.clone(),
+            value_number: self.data.value_number,
+            unit: self.data.unit,
+            inherited: true,
+        }
+    }
+}
+
+impl<'a> Serialize for DocumentStyle<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut serialized = serializer.serialize_struct("DocumentStyle", 4)?;
+
+        serialized.serialize_field("property", &self.data.property.to_string())?;
+        serialized.serialize_field("value_string", &self.data.value_string)?;
+        serialized.serialize_field("value_number", &self.data.value_number)?;
+        serialized.serialize_field("unit", &self.data.unit)?;
+
+        serialized.end()
+    }
+}
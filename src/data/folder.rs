@@ -0,0 +1,192 @@
+use diesel;
+use diesel::prelude::*;
+
+use data::db::Connection;
+use data::schema::documents;
+use data::schema::folders;
+use data::schema::folders::dsl::*;
+use data::user::UserID;
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+use uuid;
+
+#[derive(TaggedID, Serialize, Deserialize)]
+pub struct FolderID(uuid::Uuid);
+
+pub struct Folder<'a> {
+    connection: &'a Connection,
+    pub data: Data,
+}
+
+#[derive(Debug, Queryable, Identifiable)]
+#[table_name = "folders"]
+pub struct Data {
+    id: uuid::Uuid,
+    user_id: uuid::Uuid,
+    parent_id: Option<uuid::Uuid>,
+    pub name: String,
+    pub created_at: SystemTime,
+}
+
+#[derive(Insertable)]
+#[table_name = "folders"]
+struct NewFolder<'a> {
+    user_id: uuid::Uuid,
+    parent_id: Option<uuid::Uuid>,
+    name: &'a str,
+}
+
+impl<'a> Folder<'a> {
+    pub const MAX_NAME_LENGTH: usize = 100;
+
+    fn results_list(connection: &'a Connection, folders_list: Vec<Data>) -> Vec<Self> {
+        folders_list
+            .into_iter()
+            .map(|data| Folder { connection, data })
+            .collect()
+    }
+
+    pub fn get_id(&self) -> FolderID {
+        FolderID::from_uuid(self.data.id.clone())
+    }
+
+    pub fn get_user_id(&self) -> UserID {
+        UserID::from_uuid(self.data.user_id.clone())
+    }
+
+    pub fn get_parent_id(&self) -> Option<FolderID> {
+        self.data.parent_id.map(FolderID::from_uuid)
+    }
+
+    pub fn create(
+        connection: &'a Connection,
+        p_user_id: &UserID,
+        p_parent_id: Option<&FolderID>,
+        p_name: &str,
+    ) -> QueryResult<Self> {
+        let data = diesel::insert_into(folders)
+            .values(NewFolder {
+                user_id: **p_user_id,
+                parent_id: p_parent_id.map(|p| **p),
+                name: p_name,
+            })
+            .get_result(&connection.pg_connection)?;
+
+        Ok(Folder { connection, data })
+    }
+
+    // folders are private, so lookups are always scoped to their owner
+    pub fn get_by_id(
+        connection: &'a Connection,
+        p_user_id: &UserID,
+        p_folder_id: &FolderID,
+    ) -> QueryResult<Self> {
+        let data = folders
+            .filter(id.eq(&**p_folder_id))
+            .filter(user_id.eq(&**p_user_id))
+            .first::<Data>(&connection.pg_connection)?;
+
+        Ok(Folder { connection, data })
+    }
+
+    pub fn get_by_user(connection: &'a Connection, p_user_id: &UserID) -> QueryResult<Vec<Self>> {
+        let folders_list = folders
+            .filter(user_id.eq(&**p_user_id))
+            .order(name.asc())
+            .load::<Data>(&connection.pg_connection)?;
+
+        Ok(Self::results_list(connection, folders_list))
+    }
+
+    pub fn rename(&mut self, p_name: &str) -> QueryResult<&mut Self> {
+        self.data = diesel::update(folders)
+            .filter(id.eq(self.data.id))
+            .set(name.eq(p_name))
+            .get_result(&self.connection.pg_connection)?;
+
+        Ok(self)
+    }
+
+    // true if the given folder is this one or nested anywhere beneath it
+    pub fn contains(&self, p_folder_id: &FolderID) -> QueryResult<bool> {
+        let parents: HashMap<uuid::Uuid, Option<uuid::Uuid>> = folders
+            .filter(user_id.eq(self.data.user_id))
+            .select((id, parent_id))
+            .load::<(uuid::Uuid, Option<uuid::Uuid>)>(&self.connection.pg_connection)?
+            .into_iter()
+            .collect();
+
+        let mut current = Some(**p_folder_id);
+        let mut steps = 0;
+
+        while let Some(folder_uuid) = current {
+            if folder_uuid == self.data.id {
+                return Ok(true);
+            }
+
+            // guards against cycles that should never exist
+            steps += 1;
+            if steps > parents.len() {
+                break;
+            }
+
+            current = parents.get(&folder_uuid).cloned().unwrap_or(None);
+        }
+
+        Ok(false)
+    }
+
+    pub fn move_to(&mut self, p_parent_id: Option<&FolderID>) -> QueryResult<&mut Self> {
+        self.data = diesel::update(folders)
+            .filter(id.eq(self.data.id))
+            .set(parent_id.eq(p_parent_id.map(|p| **p)))
+            .get_result(&self.connection.pg_connection)?;
+
+        Ok(self)
+    }
+
+    // subfolders and documents are handed to the parent folder rather than
+    // being deleted, so nothing is lost by removing a folder
+    pub fn delete(&mut self) -> QueryResult<usize> {
+        let self_id = self.data.id;
+        let new_parent_id = self.data.parent_id;
+
+        self.connection
+            .pg_connection
+            .transaction::<_, diesel::result::Error, _>(|| {
+                diesel::update(folders)
+                    .filter(parent_id.eq(self_id))
+                    .set(parent_id.eq(new_parent_id))
+                    .execute(&self.connection.pg_connection)?;
+
+                diesel::update(documents::table)
+                    .filter(documents::folder_id.eq(self_id))
+                    .set(documents::folder_id.eq(new_parent_id))
+                    .execute(&self.connection.pg_connection)?;
+
+                diesel::delete(folders)
+                    .filter(id.eq(self_id))
+                    .execute(&self.connection.pg_connection)
+            })
+    }
+}
+
+impl<'a> Serialize for Folder<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut serialized = serializer.serialize_struct("Folder", 4)?;
+
+        serialized.serialize_field("folder_id", &self.get_id())?;
+        serialized.serialize_field("parent_id", &self.get_parent_id())?;
+        serialized.serialize_field("name", &self.data.name)?;
+        serialized.serialize_field("created_at", &self.data.created_at)?;
+
+        serialized.end()
+    }
+}
@@ -0,0 +1,164 @@
+use diesel;
+use diesel::prelude::*;
+
+use data::db::Connection;
+use data::diff::{preview_merge, MergePreview, OutlineItem};
+use data::document::{Document, DocumentID};
+use data::item::ItemID;
+use data::schema::document_forks;
+use data::schema::document_forks::dsl::*;
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde_json;
+
+use std::time::SystemTime;
+
+use uuid;
+
+// records where a copied document came from, along with the source's outline
+// at the time it was copied or last synced, which serves as the merge base
+pub struct Fork<'a> {
+    connection: &'a Connection,
+    pub data: Data,
+}
+
+#[derive(Debug, Queryable, Identifiable)]
+#[table_name = "document_forks"]
+#[primary_key(document_id)]
+pub struct Data {
+    document_id: uuid::Uuid,
+    source_document_id: Option<uuid::Uuid>,
+    pub source_modified_at: SystemTime,
+    base_root_id: uuid::Uuid,
+    base_items: serde_json::Value,
+    pub created_at: SystemTime,
+    pub synced_at: SystemTime,
+}
+
+#[derive(Insertable)]
+#[table_name = "document_forks"]
+struct NewFork {
+    document_id: uuid::Uuid,
+    source_document_id: Option<uuid::Uuid>,
+    source_modified_at: SystemTime,
+    base_root_id: uuid::Uuid,
+    base_items: serde_json::Value,
+}
+
+fn snapshot(source: &Document) -> QueryResult<(uuid::Uuid, serde_json::Value)> {
+    let root_id = source
+        .get_serialized_root_id()
+        .ok_or(diesel::result::Error::NotFound)?;
+
+    let outline = serde_json::to_value(source.get_outline()?)
+        .map_err(|e| diesel::result::Error::SerializationError(Box::new(e)))?;
+
+    Ok((*root_id, outline))
+}
+
+impl<'a> Fork<'a> {
+    pub fn get_document_id(&self) -> DocumentID {
+        DocumentID::from_uuid(self.data.document_id.clone())
+    }
+
+    pub fn get_source_document_id(&self) -> Option<DocumentID> {
+        self.data.source_document_id.map(DocumentID::from_uuid)
+    }
+
+    pub fn create(
+        connection: &'a Connection,
+        p_document_id: &DocumentID,
+        p_source: &Document,
+    ) -> QueryResult<Self> {
+        let (p_base_root_id, p_base_items) = snapshot(p_source)?;
+
+        let data = diesel::insert_into(document_forks)
+            .values(NewFork {
+                document_id: **p_document_id,
+                source_document_id: Some(*p_source.get_id()),
+                source_modified_at: p_source.last_modified(),
+                base_root_id: p_base_root_id,
+                base_items: p_base_items,
+            })
+            .get_result(&connection.pg_connection)?;
+
+        Ok(Fork { connection, data })
+    }
+
+    pub fn get_by_document(
+        connection: &'a Connection,
+        p_document_id: &DocumentID,
+    ) -> QueryResult<Self> {
+        let data = document_forks
+            .filter(document_id.eq(&**p_document_id))
+            .first::<Data>(&connection.pg_connection)?;
+
+        Ok(Fork { connection, data })
+    }
+
+    pub fn get_base_outline(&self) -> QueryResult<Vec<OutlineItem>> {
+        serde_json::from_value(self.data.base_items.clone())
+            .map_err(|e| diesel::result::Error::DeserializationError(Box::new(e)))
+    }
+
+    pub fn upstream_changed(&self, p_source: &Document) -> bool {
+        p_source.last_modified() > self.data.source_modified_at
+    }
+
+    pub fn preview_merge(
+        &self,
+        p_source: &Document,
+        p_copy: &Document,
+    ) -> QueryResult<MergePreview> {
+        let source_root_id = p_source
+            .get_serialized_root_id()
+            .ok_or(diesel::result::Error::NotFound)?;
+        let copy_root_id = p_copy
+            .get_serialized_root_id()
+            .ok_or(diesel::result::Error::NotFound)?;
+
+        Ok(preview_merge(
+            &self.get_base_outline()?,
+            &ItemID::from_uuid(self.data.base_root_id),
+            &p_source.get_outline()?,
+            &source_root_id,
+            &p_copy.get_outline()?,
+            &copy_root_id,
+        ))
+    }
+
+    // once the owner has brought the copy up to date, the source's current
+    // outline becomes the base for future merges
+    pub fn sync(&mut self, p_source: &Document) -> QueryResult<&mut Self> {
+        let (p_base_root_id, p_base_items) = snapshot(p_source)?;
+
+        self.data = diesel::update(document_forks)
+            .filter(document_id.eq(self.data.document_id))
+            .set((
+                source_modified_at.eq(p_source.last_modified()),
+                base_root_id.eq(p_base_root_id),
+                base_items.eq(p_base_items),
+                synced_at.eq(SystemTime::now()),
+            ))
+            .get_result(&self.connection.pg_connection)?;
+
+        Ok(self)
+    }
+}
+
+impl<'a> Serialize for Fork<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut serialized = serializer.serialize_struct("Fork", 5)?;
+
+        serialized.serialize_field("document_id", &self.get_document_id())?;
+        serialized.serialize_field("source_document_id", &self.get_source_document_id())?;
+        serialized.serialize_field("source_version", &self.data.source_modified_at)?;
+        serialized.serialize_field("forked_at", &self.data.created_at)?;
+        serialized.serialize_field("synced_at", &self.data.synced_at)?;
+
+        serialized.end()
+    }
+}
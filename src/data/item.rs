@@ -1,18 +1,26 @@
 use diesel;
+use diesel::pg::Pg;
 use diesel::prelude::*;
 
 use data;
+use data::attachment::{Attachment, AttachmentID};
 use data::db::Connection;
 use data::document::DocumentID;
+use data::rich_text::ItemText;
+use data::schema::categories;
+use data::schema::documents;
 use data::schema::items;
 use data::schema::items::dsl::*;
 use data::schema::styles::dsl::*;
-use data::schema::{StyleProperty, StyleUnit};
-use data::style::{style_vec_to_map, Style};
+use data::schema::{ItemKind, StyleProperty, StyleUnit, TaskState};
+use data::style::{style_vec_to_map, ComputedStyle, Style, StyleError};
+use data::user::UserID;
 
 use serde::ser::{Serialize, SerializeStruct, Serializer};
+use serde_json;
 
 use std::collections::HashMap;
+use std::time::SystemTime;
 
 use uuid;
 
@@ -33,6 +41,53 @@ pub struct Data {
     pub item_text: String,
     pub child_order: i32,
     pub collapsed: bool,
+    pub task_state: Option<TaskState>,
+    pub due_at: Option<SystemTime>,
+    pub toc_target_id: Option<uuid::Uuid>,
+    pub style_preset: Option<String>,
+    pub kind: ItemKind,
+    pub code_language: Option<String>,
+    pub rich_text: Option<serde_json::Value>,
+    pub locked_by: Option<uuid::Uuid>,
+    pub locked_at: Option<SystemTime>,
+}
+
+const MAX_CODE_LANGUAGE_LENGTH: usize = 32;
+
+// language tags are identifiers such as "rust", "c++" or "objective-c"
+pub fn is_valid_code_language(language: &str) -> bool {
+    language != ""
+        && language.len() <= MAX_CODE_LANGUAGE_LENGTH
+        && language
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "+-#._".contains(c))
+}
+
+#[derive(Serialize, Default, Clone, Copy)]
+pub struct TaskRollup {
+    pub completed: u32,
+    pub total: u32,
+}
+
+impl TaskRollup {
+    pub fn from_state(state: Option<TaskState>) -> TaskRollup {
+        match state {
+            Some(TaskState::Done) => TaskRollup {
+                completed: 1,
+                total: 1,
+            },
+            Some(TaskState::Todo) => TaskRollup {
+                completed: 0,
+                total: 1,
+            },
+            Some(TaskState::Cancelled) | None => TaskRollup::default(),
+        }
+    }
+
+    pub fn add(&mut self, other: &TaskRollup) {
+        self.completed += other.completed;
+        self.total += other.total;
+    }
 }
 
 impl<'a> Item<'a> {
@@ -83,6 +138,46 @@ impl<'a> Item<'a> {
         Self::results_list(connection, items_list)
     }
 
+    fn get_active_for_user(p_user_uuid: uuid::Uuid) -> items::BoxedQuery<'static, Pg> {
+        let user_documents = documents::table
+            .select(documents::id)
+            .filter(documents::user_id.eq(p_user_uuid));
+
+        let trashed_documents = categories::table
+            .select(categories::document_id)
+            .filter(categories::user_id.eq(p_user_uuid))
+            .filter(categories::category_name.eq(data::category::Category::TRASH));
+
+        items
+            .filter(document_id.eq_any(user_documents))
+            .filter(diesel::dsl::not(document_id.eq_any(trashed_documents)))
+            .into_boxed()
+    }
+
+    pub fn get_open_tasks_for_user(
+        connection: &'a Connection,
+        p_user_id: &UserID,
+    ) -> QueryResult<Vec<Item<'a>>> {
+        let items_list = Self::get_active_for_user(**p_user_id)
+            .filter(task_state.eq(TaskState::Todo))
+            .order((due_at.is_null(), due_at.asc(), child_order.asc()))
+            .load::<Data>(&connection.pg_connection)?;
+
+        Self::results_list(connection, items_list)
+    }
+
+    pub fn get_dated_for_user(
+        connection: &'a Connection,
+        p_user_id: &UserID,
+    ) -> QueryResult<Vec<Item<'a>>> {
+        let items_list = Self::get_active_for_user(**p_user_id)
+            .filter(due_at.is_not_null())
+            .order(due_at.asc())
+            .load::<Data>(&connection.pg_connection)?;
+
+        Self::results_list(connection, items_list)
+    }
+
     pub fn remove_children(&mut self) -> QueryResult<usize> {
         let p_item_uuid = self.get_id();
 
@@ -97,6 +192,7 @@ impl<'a> Item<'a> {
         p_value_string: Option<String>,
         p_value_number: Option<i32>,
         p_unit: Option<StyleUnit>,
+        p_inherit: bool,
     ) -> Style<'a> {
         Style::create(
             self.connection,
@@ -105,13 +201,137 @@ impl<'a> Item<'a> {
             p_value_string,
             p_value_number,
             p_unit,
+            p_inherit,
         )
     }
 
-    pub fn update_text(&mut self, update_text: &str) -> QueryResult<&mut Item<'a>> {
+    pub fn get_text(&self) -> ItemText {
+        self.data
+            .rich_text
+            .as_ref()
+            .and_then(ItemText::from_rich_json)
+            .unwrap_or_else(|| ItemText::Plain(self.data.item_text.clone()))
+    }
+
+    // rich text is stored as-is, alongside its plain text projection
+    pub fn update_text(&mut self, update_text: &ItemText) -> QueryResult<&mut Item<'a>> {
+        let update_text = update_text
+            .sanitize()
+            .map_err(|e| diesel::result::Error::QueryBuilderError(Box::new(e)))?;
+
+        update_text
+            .validate()
+            .map_err(|e| diesel::result::Error::QueryBuilderError(Box::new(e)))?;
+
+        let data = diesel::update(items)
+            .filter(id.eq(self.data.id))
+            .set((
+                item_text.eq(update_text.to_plain_text()),
+                rich_text.eq(update_text.to_rich_json()),
+            ))
+            .get_result(&self.connection.pg_connection)?;
+
+        self.data = data;
+
+        Ok(self)
+    }
+
+    pub fn update_task(
+        &mut self,
+        p_task_state: Option<TaskState>,
+        p_due_at: Option<SystemTime>,
+    ) -> QueryResult<&mut Item<'a>> {
+        let data = diesel::update(items)
+            .filter(id.eq(self.data.id))
+            .set((task_state.eq(p_task_state), due_at.eq(p_due_at)))
+            .get_result(&self.connection.pg_connection)?;
+
+        self.data = data;
+
+        Ok(self)
+    }
+
+    pub fn toggle_task(&mut self) -> QueryResult<&mut Item<'a>> {
+        let toggled = match self.data.task_state {
+            Some(TaskState::Done) => TaskState::Todo,
+            _ => TaskState::Done,
+        };
+        let p_due_at = self.data.due_at;
+
+        self.update_task(Some(toggled), p_due_at)
+    }
+
+    // only code items keep a language tag
+    pub fn set_kind(
+        &mut self,
+        p_kind: ItemKind,
+        p_code_language: Option<&str>,
+    ) -> QueryResult<&mut Item<'a>> {
+        let p_code_language = match p_kind {
+            ItemKind::Code => p_code_language,
+            _ => None,
+        };
+
+        let data = diesel::update(items)
+            .filter(id.eq(self.data.id))
+            .set((kind.eq(p_kind), code_language.eq(p_code_language)))
+            .get_result(&self.connection.pg_connection)?;
+
+        self.data = data;
+
+        Ok(self)
+    }
+
+    pub fn set_toc_target(&mut self, p_target: &ItemID) -> QueryResult<&mut Item<'a>> {
+        let data = diesel::update(items)
+            .filter(id.eq(self.data.id))
+            .set(toc_target_id.eq(Some(**p_target)))
+            .get_result(&self.connection.pg_connection)?;
+
+        self.data = data;
+
+        Ok(self)
+    }
+
+    pub fn set_style_preset(&mut self, p_style_preset: Option<&str>) -> QueryResult<&mut Item<'a>> {
+        let data = diesel::update(items)
+            .filter(id.eq(self.data.id))
+            .set(style_preset.eq(p_style_preset))
+            .get_result(&self.connection.pg_connection)?;
+
+        self.data = data;
+
+        Ok(self)
+    }
+
+    pub fn get_locked_by(&self) -> Option<UserID> {
+        self.data.locked_by.map(UserID::from_uuid)
+    }
+
+    // the locking user may since have been deleted, but the lock still holds
+    pub fn is_locked(&self) -> bool {
+        self.data.locked_at.is_some()
+    }
+
+    pub fn lock(&mut self, p_user_id: &UserID) -> QueryResult<&mut Item<'a>> {
+        self.set_lock(Some(p_user_id), Some(SystemTime::now()))
+    }
+
+    pub fn unlock(&mut self) -> QueryResult<&mut Item<'a>> {
+        self.set_lock(None, None)
+    }
+
+    pub fn set_lock(
+        &mut self,
+        p_locked_by: Option<&UserID>,
+        p_locked_at: Option<SystemTime>,
+    ) -> QueryResult<&mut Item<'a>> {
         let data = diesel::update(items)
             .filter(id.eq(self.data.id))
-            .set(item_text.eq(update_text))
+            .set((
+                locked_by.eq(p_locked_by.map(|u| **u)),
+                locked_at.eq(p_locked_at),
+            ))
             .get_result(&self.connection.pg_connection)?;
 
         self.data = data;
@@ -119,6 +339,15 @@ impl<'a> Item<'a> {
         Ok(self)
     }
 
+    pub fn link_attachments(&mut self, p_attachment_ids: &Vec<AttachmentID>) -> QueryResult<usize> {
+        Attachment::move_to_item(
+            self.connection,
+            &DocumentID::from_uuid(self.data.document_id),
+            p_attachment_ids,
+            &self.get_id(),
+        )
+    }
+
     fn add_style(&mut self, style: &Style<'a>) -> QueryResult<Style<'a>> {
         let data = diesel::insert_into(styles)
             .values(&style.data)
@@ -128,6 +357,12 @@ impl<'a> Item<'a> {
     }
 
     pub fn update_styles(&mut self, update_styles: Vec<Style<'a>>) -> QueryResult<Vec<Style<'a>>> {
+        update_styles
+            .iter()
+            .map(|s| s.validate())
+            .collect::<Result<Vec<()>, StyleError>>()
+            .map_err(|e| diesel::result::Error::QueryBuilderError(Box::new(e)))?;
+
         let current_properties: Vec<data::schema::StyleProperty> =
             Style::get_by_item(&self.connection, &self.get_id())?
                 .into_iter()
@@ -151,13 +386,14 @@ impl<'a> Item<'a> {
 fn serialize_item<'a, S>(
     item: &Item<'a>,
     serializer: S,
-    styles_map: Option<&HashMap<StyleProperty, Style<'a>>>,
+    with_styles: Option<&ItemWithStyles<'a>>,
 ) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
 {
-    let mut serialized =
-        serializer.serialize_struct("Item", if styles_map.is_some() { 7 } else { 6 })?;
+    let count_fields = if with_styles.is_some() { 20 } else { 15 };
+
+    let mut serialized = serializer.serialize_struct("Item", count_fields)?;
     serialized.serialize_field("item_id", &item.get_id())?;
 
     serialized.serialize_field(
@@ -174,11 +410,30 @@ where
     )?;
 
     serialized.serialize_field("text", &item.data.item_text)?;
+    serialized.serialize_field("rich_text", &item.data.rich_text)?;
     serialized.serialize_field("child_order", &item.data.child_order)?;
     serialized.serialize_field("collapsed", &item.data.collapsed)?;
-
-    if let Some(s) = styles_map {
-        serialized.serialize_field("styles", s)?;
+    serialized.serialize_field("task_state", &item.data.task_state)?;
+    serialized.serialize_field("due_at", &item.data.due_at)?;
+    serialized.serialize_field("kind", &item.data.kind)?;
+    serialized.serialize_field("code_language", &item.data.code_language)?;
+    serialized.serialize_field(
+        "toc_target_id",
+        &item
+            .data
+            .toc_target_id
+            .and_then(|t| Some(t.hyphenated().to_string())),
+    )?;
+    serialized.serialize_field("style_preset", &item.data.style_preset)?;
+    serialized.serialize_field("locked_by", &item.get_locked_by())?;
+    serialized.serialize_field("locked_at", &item.data.locked_at)?;
+
+    if let Some(w) = with_styles {
+        serialized.serialize_field("styles", &w.styles)?;
+        serialized.serialize_field("effective_styles", &w.effective_styles)?;
+        serialized.serialize_field("tasks", &w.task_rollup)?;
+        serialized.serialize_field("depth", &w.depth)?;
+        serialized.serialize_field("number", &w.number)?;
     }
 
     serialized.end()
@@ -196,16 +451,28 @@ impl<'a> Serialize for Item<'a> {
 pub struct ItemWithStyles<'a> {
     pub item: Item<'a>,
     pub styles: HashMap<StyleProperty, Style<'a>>,
+    pub effective_styles: HashMap<StyleProperty, ComputedStyle>,
+    pub task_rollup: TaskRollup,
+    pub depth: u32,
+    pub number: Option<String>,
 }
 
 impl<'a> ItemWithStyles<'a> {
-    pub fn from(item: Item<'a>) -> QueryResult<ItemWithStyles<'a>> {
-        let item_styles = Style::get_by_item(&item.connection, &(item.get_id()))?;
-
-        Ok(ItemWithStyles::<'a> {
+    // effective styles start out as the item's own, until they are cascaded
+    pub fn new(item: Item<'a>, item_styles: Vec<Style<'a>>) -> ItemWithStyles<'a> {
+        let effective_styles = item_styles
+            .iter()
+            .map(|s| (s.data.property, s.to_computed(false)))
+            .collect();
+
+        ItemWithStyles::<'a> {
+            task_rollup: TaskRollup::from_state(item.data.task_state),
             item: item,
             styles: style_vec_to_map(item_styles),
-        })
+            effective_styles,
+            depth: 0,
+            number: None,
+        }
     }
 }
 
@@ -214,6 +481,6 @@ impl<'a> Serialize for ItemWithStyles<'a> {
     where
         S: Serializer,
     {
-        serialize_item(&self.item, serializer, Some(&self.styles))
+        serialize_item(&self.item, serializer, Some(self))
     }
 }
@@ -0,0 +1,95 @@
+use data::document::DocumentID;
+use data::memory::redis::Connection;
+use data::schema::WebhookEvent;
+use data::webhook::WebhookID;
+use data::webhook_delivery::DeliveryID;
+
+use r2d2_redis::redis::{Commands, RedisResult};
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const QUEUE_KEY: &'static str = "webhook_delivery_queue";
+
+#[derive(Serialize, Deserialize, RedisData)]
+pub struct QueuedDelivery {
+    pub delivery_id: DeliveryID,
+    pub webhook_id: WebhookID,
+    pub document_id: DocumentID,
+    pub event: WebhookEvent,
+    pub payload: String,
+    pub attempt: i32,
+}
+
+fn connection_error(msg: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::ConnectionRefused, msg)
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl QueuedDelivery {
+    pub const MAX_ATTEMPTS: i32 = 5;
+    const BASE_BACKOFF_SECS: u64 = 30;
+
+    pub fn enqueue(&self, connection: &Connection, delay: Duration) -> RedisResult<()> {
+        let score = unix_seconds(SystemTime::now() + delay);
+
+        connection
+            .redis_connection
+            .lock()
+            .or(Err(connection_error(
+                "Could not get a lock on the Redis connection",
+            )))?
+            .zadd(QUEUE_KEY, self, score)
+    }
+
+    // the delay before the attempt after this one, if there is to be one
+    pub fn backoff(attempt: i32) -> Option<Duration> {
+        if attempt >= Self::MAX_ATTEMPTS {
+            return None;
+        }
+
+        Some(Duration::from_secs(
+            Self::BASE_BACKOFF_SECS * 2u64.pow(attempt.max(0) as u32),
+        ))
+    }
+
+    pub fn retry(mut self, connection: &Connection) -> RedisResult<bool> {
+        let backoff = match Self::backoff(self.attempt) {
+            Some(b) => b,
+            None => return Ok(false),
+        };
+
+        self.attempt += 1;
+        self.enqueue(connection, backoff)?;
+
+        Ok(true)
+    }
+
+    // claims due deliveries by removing them from the queue, so that concurrent
+    // workers never send the same attempt twice
+    pub fn take_due(connection: &Connection, limit: isize) -> RedisResult<Vec<QueuedDelivery>> {
+        let mut con = connection.redis_connection.lock().or(Err(connection_error(
+            "Could not get a lock on the Redis connection",
+        )))?;
+
+        let now = unix_seconds(SystemTime::now());
+        let due: Vec<String> = con.zrangebyscore_limit(QUEUE_KEY, "-inf", now, 0, limit)?;
+
+        let mut claimed = Vec::new();
+        for member in due.into_iter() {
+            let removed: i32 = con.zrem(QUEUE_KEY, &member)?;
+
+            if removed > 0 {
+                if let Ok(delivery) = serde_json::from_str::<QueuedDelivery>(&member) {
+                    claimed.push(delivery);
+                }
+            }
+        }
+
+        Ok(claimed)
+    }
+}
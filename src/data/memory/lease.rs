@@ -0,0 +1,120 @@
+use data::document::DocumentID;
+use data::memory::redis::Connection;
+use data::memory::session::SessionID;
+use data::user::UserID;
+
+use r2d2_redis::redis;
+use r2d2_redis::redis::{Commands, RedisResult};
+
+use std::time::{Duration, SystemTime};
+
+fn connection_error(msg: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::ConnectionRefused, msg)
+}
+
+fn lease_key(p_document_id: &DocumentID) -> String {
+    format!("edit_lease:{}", p_document_id)
+}
+
+// a lease belongs to one session rather than a user, so the same user editing
+// in two places counts as two writers
+#[derive(Serialize, Deserialize, RedisData)]
+pub struct EditLease {
+    pub document_id: DocumentID,
+    pub user_id: UserID,
+    pub display_name: String,
+    pub session_id: SessionID,
+    pub acquired_at: SystemTime,
+    pub expires_at: SystemTime,
+}
+
+impl EditLease {
+    // editors are expected to renew well before this runs out
+    pub const DURATION_SECS: u64 = 60;
+
+    pub fn is_held_by(&self, p_session_id: &SessionID) -> bool {
+        self.session_id == *p_session_id
+    }
+
+    pub fn get(
+        connection: &Connection,
+        p_document_id: &DocumentID,
+    ) -> RedisResult<Option<EditLease>> {
+        connection
+            .redis_connection
+            .lock()
+            .or(Err(connection_error(
+                "Could not get a lock on the Redis connection",
+            )))?
+            .get(lease_key(p_document_id))
+    }
+
+    // renews the lease if the session already holds it; otherwise the current
+    // holder's lease is returned as the error. The key is watched, so a lease
+    // that changes hands between the check and the write is checked again
+    pub fn acquire(
+        connection: &Connection,
+        p_document_id: &DocumentID,
+        p_user_id: &UserID,
+        p_display_name: &str,
+        p_session_id: &SessionID,
+    ) -> RedisResult<Result<EditLease, EditLease>> {
+        let mut con = connection.redis_connection.lock().or(Err(connection_error(
+            "Could not get a lock on the Redis connection",
+        )))?;
+        let key = lease_key(p_document_id);
+
+        redis::transaction(&mut **con, &[&key], |con, pipe| {
+            let now = SystemTime::now();
+            let acquired_at = match con.get::<_, Option<EditLease>>(&key)? {
+                Some(current) => {
+                    if !current.is_held_by(p_session_id) {
+                        return Ok(Some(Err(current)));
+                    }
+
+                    current.acquired_at
+                }
+                None => now,
+            };
+
+            let lease = EditLease {
+                document_id: p_document_id.clone(),
+                user_id: p_user_id.clone(),
+                display_name: p_display_name.to_string(),
+                session_id: p_session_id.clone(),
+                acquired_at,
+                expires_at: now + Duration::from_secs(Self::DURATION_SECS),
+            };
+
+            let written: Option<()> = pipe
+                .set_ex(&key, &lease, Self::DURATION_SECS as usize)
+                .ignore()
+                .query(con)?;
+
+            Ok(written.map(|_| Ok(lease)))
+        })
+    }
+
+    // removes the lease only while it still belongs to the given session, so
+    // that one taken over in the meantime is left alone
+    pub fn release(
+        connection: &Connection,
+        p_document_id: &DocumentID,
+        p_session_id: &SessionID,
+    ) -> RedisResult<bool> {
+        let mut con = connection.redis_connection.lock().or(Err(connection_error(
+            "Could not get a lock on the Redis connection",
+        )))?;
+        let key = lease_key(p_document_id);
+
+        redis::transaction(&mut **con, &[&key], |con, pipe| {
+            match con.get::<_, Option<EditLease>>(&key)? {
+                Some(ref current) if current.is_held_by(p_session_id) => {
+                    let deleted: Option<()> = pipe.del(&key).ignore().query(con)?;
+                    Ok(deleted.map(|_| true))
+                }
+                _ => Ok(Some(false)),
+            }
+        })
+    }
+}
@@ -1,2 +1,4 @@
+pub mod delivery_queue;
+pub mod lease;
 pub mod redis;
 pub mod session;
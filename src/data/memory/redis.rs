@@ -8,7 +8,7 @@ use rocket::{Outcome, Request, State};
 
 use std::sync::Mutex;
 
-type RedisPool = r2d2::Pool<RedisConnectionManager>;
+pub type RedisPool = r2d2::Pool<RedisConnectionManager>;
 
 pub fn init_pool(cfg: &SeriatimConfig) -> Result<RedisPool, Box<dyn std::error::Error>> {
     let manager = RedisConnectionManager::new(cfg.redis_url.clone())?;
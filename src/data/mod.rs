@@ -1,9 +1,27 @@
 #![allow(proc_macro_derive_resolution_fallback)]
+pub mod activity;
+pub mod attachment;
+pub mod calendar;
 pub mod category;
+pub mod color;
 pub mod db;
+pub mod diff;
 pub mod document;
+pub mod document_style;
+pub mod folder;
+pub mod fork;
 pub mod item;
 pub mod memory;
+pub mod numbering;
+pub mod rich_text;
+pub mod sanitize;
 pub mod schema;
+pub mod share;
+pub mod star;
+pub mod stats;
 pub mod style;
+pub mod style_preset;
+pub mod toc;
 pub mod user;
+pub mod webhook;
+pub mod webhook_delivery;
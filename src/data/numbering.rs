@@ -0,0 +1,175 @@
+use data::item::{Item, ItemID};
+use data::schema::NumberingScheme;
+
+use std::collections::HashMap;
+
+pub struct Position {
+    pub depth: u32,
+    pub number: Option<String>,
+}
+
+fn to_alpha(n: u32) -> String {
+    let mut n = n;
+    let mut letters = Vec::new();
+
+    while n > 0 {
+        n -= 1;
+        letters.push((b'a' + (n % 26) as u8) as char);
+        n /= 26;
+    }
+
+    letters.into_iter().rev().collect()
+}
+
+fn to_roman(n: u32) -> String {
+    if n == 0 || n >= 4000 {
+        return n.to_string();
+    }
+
+    let numerals = [
+        (1000, "m"),
+        (900, "cm"),
+        (500, "d"),
+        (400, "cd"),
+        (100, "c"),
+        (90, "xc"),
+        (50, "l"),
+        (40, "xl"),
+        (10, "x"),
+        (9, "ix"),
+        (5, "v"),
+        (4, "iv"),
+        (1, "i"),
+    ];
+
+    let mut n = n;
+    let mut roman = String::new();
+    for (value, numeral) in numerals.iter() {
+        while n >= *value {
+            roman.push_str(numeral);
+            n -= value;
+        }
+    }
+
+    roman
+}
+
+fn join_decimal(path: &[u32]) -> String {
+    path.iter()
+        .map(|n| n.to_string())
+        .collect::<Vec<String>>()
+        .join(".")
+}
+
+// path holds the 1-based position of the item among its siblings at each depth
+pub fn format_number(scheme: NumberingScheme, path: &[u32]) -> String {
+    let last = *path.last().unwrap_or(&0);
+
+    match scheme {
+        NumberingScheme::Decimal => join_decimal(path),
+        NumberingScheme::Outline => match (path.len() - 1) % 5 {
+            0 => to_roman(last).to_uppercase() + ".",
+            1 => to_alpha(last).to_uppercase() + ".",
+            2 => last.to_string() + ".",
+            3 => to_alpha(last) + ".",
+            _ => to_roman(last) + ".",
+        },
+        NumberingScheme::Legal => match path.len() {
+            1 => join_decimal(path) + ".",
+            2 | 3 => join_decimal(path),
+            4 => format!("({})", to_alpha(last)),
+            5 => format!("({})", to_roman(last)),
+            _ => format!("({})", to_alpha(last).to_uppercase()),
+        },
+    }
+}
+
+fn number_children(
+    children: &HashMap<ItemID, Vec<(i32, ItemID)>>,
+    parent: &ItemID,
+    path: &mut Vec<u32>,
+    scheme: Option<NumberingScheme>,
+    skip: Option<&ItemID>,
+    positions: &mut HashMap<ItemID, Position>,
+) {
+    let mut counter = 0;
+
+    for (_, child_id) in children.get(parent).iter().flat_map(|c| c.iter()) {
+        let numbered = Some(child_id) != skip;
+        if numbered {
+            counter += 1;
+        }
+
+        path.push(if numbered { counter } else { 0 });
+
+        positions.insert(
+            child_id.clone(),
+            Position {
+                depth: path.len() as u32,
+                number: if numbered {
+                    scheme.map(|s| format_number(s, &path[..]))
+                } else {
+                    None
+                },
+            },
+        );
+        number_children(
+            children,
+            child_id,
+            path,
+            if numbered { scheme } else { None },
+            skip,
+            positions,
+        );
+
+        path.pop();
+    }
+}
+
+// computes the depth of every item below the root, along with its number in
+// the given scheme; the TOC subtree is left unnumbered so that it does not
+// shift the numbers of the items it points to
+pub fn number_outline<'a, 'b, I>(
+    items: I,
+    root_id: &ItemID,
+    toc_id: Option<&ItemID>,
+    scheme: Option<NumberingScheme>,
+) -> HashMap<ItemID, Position>
+where
+    'a: 'b,
+    I: Iterator<Item = &'b Item<'a>>,
+{
+    let mut children = HashMap::<ItemID, Vec<(i32, ItemID)>>::new();
+    for item in items {
+        if let Some(pid) = item.get_parent_id() {
+            children
+                .entry(pid)
+                .or_insert(Vec::new())
+                .push((item.data.child_order, item.get_id()));
+        }
+    }
+
+    for siblings in children.values_mut() {
+        siblings.sort_by_key(|c| c.0);
+    }
+
+    let mut positions = HashMap::<ItemID, Position>::new();
+    positions.insert(
+        root_id.clone(),
+        Position {
+            depth: 0,
+            number: None,
+        },
+    );
+
+    number_children(
+        &children,
+        root_id,
+        &mut Vec::new(),
+        scheme,
+        toc_id,
+        &mut positions,
+    );
+
+    positions
+}
@@ -0,0 +1,163 @@
+use data::sanitize::{check_length, clean_text, sanitize_text, SanitizeError};
+
+use serde_json;
+
+use std::error::Error;
+use std::fmt;
+
+use url::Url;
+
+const MAX_SPANS: usize = 1000;
+const MAX_LINK_LENGTH: usize = 2048;
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Mark {
+    Bold,
+    Italic,
+    Underline,
+    Strikethrough,
+    Code,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Span {
+    pub text: String,
+    #[serde(default)]
+    pub marks: Vec<Mark>,
+    #[serde(default)]
+    pub link: Option<String>,
+}
+
+// item text as sent by clients: either a plain string or a list of spans
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ItemText {
+    Plain(String),
+    Rich(Vec<Span>),
+}
+
+#[derive(Debug)]
+pub struct RichTextError {
+    pub reason: String,
+}
+
+impl fmt::Display for RichTextError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.reason)
+    }
+}
+
+impl Error for RichTextError {}
+
+fn rich_text_error(reason: &str) -> RichTextError {
+    RichTextError {
+        reason: reason.to_string(),
+    }
+}
+
+fn validate_link(link: &str) -> Result<(), RichTextError> {
+    if link.len() > MAX_LINK_LENGTH {
+        return Err(rich_text_error(
+            "link targets must be at most 2048 characters",
+        ));
+    }
+
+    match Url::parse(link) {
+        Ok(ref parsed) if ["http", "https", "mailto"].contains(&parsed.scheme()) => Ok(()),
+        _ => Err(rich_text_error(
+            "link targets must be absolute HTTP, HTTPS or mailto URLs",
+        )),
+    }
+}
+
+fn validate_span(span: &Span) -> Result<(), RichTextError> {
+    if span.text == "" {
+        return Err(rich_text_error("spans must not be empty"));
+    }
+
+    let unique_marks = span
+        .marks
+        .iter()
+        .enumerate()
+        .all(|(i, m)| !span.marks[..i].contains(m));
+
+    if !unique_marks {
+        return Err(rich_text_error("spans must not repeat a mark"));
+    }
+
+    match span.link {
+        Some(ref link) => validate_link(link),
+        None => Ok(()),
+    }
+}
+
+impl ItemText {
+    pub fn from_rich_json(value: &serde_json::Value) -> Option<ItemText> {
+        serde_json::from_value(value.clone())
+            .ok()
+            .map(ItemText::Rich)
+    }
+
+    pub fn is_rich(&self) -> bool {
+        match self {
+            ItemText::Rich(_) => true,
+            ItemText::Plain(_) => false,
+        }
+    }
+
+    // spans left empty once their text has been cleaned are dropped
+    pub fn sanitize(&self) -> Result<ItemText, SanitizeError> {
+        match self {
+            ItemText::Plain(text) => Ok(ItemText::Plain(sanitize_text(text)?)),
+            ItemText::Rich(spans) => {
+                let sanitized = ItemText::Rich(
+                    spans
+                        .iter()
+                        .map(|s| Span {
+                            text: clean_text(&s.text),
+                            marks: s.marks.clone(),
+                            link: s.link.clone(),
+                        })
+                        .filter(|s| s.text != "")
+                        .collect(),
+                );
+
+                check_length(&sanitized.to_plain_text())?;
+                Ok(sanitized)
+            }
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), RichTextError> {
+        match self {
+            ItemText::Plain(_) => Ok(()),
+            ItemText::Rich(spans) => {
+                if spans.len() > MAX_SPANS {
+                    return Err(rich_text_error("items may have at most 1000 spans"));
+                }
+
+                for span in spans.iter() {
+                    validate_span(span)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+
+    // the plain text projection is what gets stored in item_text and searched
+    pub fn to_plain_text(&self) -> String {
+        match self {
+            ItemText::Plain(text) => text.clone(),
+            ItemText::Rich(spans) => spans.iter().map(|s| s.text.as_str()).collect(),
+        }
+    }
+
+    pub fn to_rich_json(&self) -> Option<serde_json::Value> {
+        match self {
+            ItemText::Plain(_) => None,
+            ItemText::Rich(spans) => serde_json::to_value(spans).ok(),
+        }
+    }
+}
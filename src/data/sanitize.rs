@@ -0,0 +1,83 @@
+use std::error::Error;
+use std::fmt;
+
+use unicode_normalization::UnicodeNormalization;
+
+pub const MAX_ITEM_TEXT_LENGTH: usize = 10000;
+
+#[derive(Debug)]
+pub enum SanitizeError {
+    TooLong { max_length: usize, length: usize },
+}
+
+impl SanitizeError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            SanitizeError::TooLong { .. } => "TEXT_TOO_LONG",
+        }
+    }
+}
+
+impl fmt::Display for SanitizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SanitizeError::TooLong { max_length, length } => write!(
+                f,
+                "item text must be at most {} characters long, but is {}",
+                max_length, length
+            ),
+        }
+    }
+}
+
+impl Error for SanitizeError {}
+
+// bidirectional overrides and isolates can make text render differently from
+// how it reads, so they are stripped along with control characters
+fn is_stripped(c: char) -> bool {
+    (c.is_control() && c != '\n' && c != '\t')
+        || (c >= '\u{202A}' && c <= '\u{202E}')
+        || (c >= '\u{2066}' && c <= '\u{2069}')
+}
+
+pub fn clean_text(text: &str) -> String {
+    text.nfc().filter(|c| !is_stripped(*c)).collect()
+}
+
+pub fn check_length(text: &str) -> Result<(), SanitizeError> {
+    let length = text.chars().count();
+
+    if length > MAX_ITEM_TEXT_LENGTH {
+        Err(SanitizeError::TooLong {
+            max_length: MAX_ITEM_TEXT_LENGTH,
+            length,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+pub fn sanitize_text(text: &str) -> Result<String, SanitizeError> {
+    let cleaned = clean_text(text);
+    check_length(&cleaned)?;
+
+    Ok(cleaned)
+}
+
+// every HTML-producing path must pass user text through this
+pub fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
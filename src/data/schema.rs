@@ -7,6 +7,12 @@ pub enum StyleProperty {
     Color,
     FontSize,
     LineHeight,
+    FontWeight,
+    FontStyle,
+    TextDecoration,
+    FontFamily,
+    TextAlign,
+    TextIndent,
 }
 
 impl ToString for StyleProperty {
@@ -16,6 +22,12 @@ impl ToString for StyleProperty {
             StyleProperty::Color => String::from("color"),
             StyleProperty::FontSize => String::from("fontSize"),
             StyleProperty::LineHeight => String::from("lineHeight"),
+            StyleProperty::FontWeight => String::from("fontWeight"),
+            StyleProperty::FontStyle => String::from("fontStyle"),
+            StyleProperty::TextDecoration => String::from("textDecoration"),
+            StyleProperty::FontFamily => String::from("fontFamily"),
+            StyleProperty::TextAlign => String::from("textAlign"),
+            StyleProperty::TextIndent => String::from("textIndent"),
         }
     }
 }
@@ -64,6 +76,122 @@ impl ToString for StyleUnit {
     }
 }
 
+#[derive(Debug, DbEnum, PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TaskState {
+    Todo,
+    Done,
+    Cancelled,
+}
+
+#[derive(Debug, DbEnum, PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ItemKind {
+    Paragraph,
+    Heading,
+    Quote,
+    Code,
+    Divider,
+}
+
+impl Default for ItemKind {
+    fn default() -> ItemKind {
+        ItemKind::Paragraph
+    }
+}
+
+#[derive(Debug, DbEnum, PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityEvent {
+    Create,
+    Rename,
+    Edit,
+    EditText,
+    AddCategory,
+    RemoveCategory,
+    Trash,
+    Publish,
+    Unpublish,
+    Copy,
+}
+
+#[derive(Debug, DbEnum, PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    Rename,
+    Edit,
+    EditText,
+    Publish,
+    Unpublish,
+    Trash,
+    Delete,
+}
+
+impl ToString for WebhookEvent {
+    fn to_string(&self) -> String {
+        match self {
+            WebhookEvent::Rename => String::from("rename"),
+            WebhookEvent::Edit => String::from("edit"),
+            WebhookEvent::EditText => String::from("edit_text"),
+            WebhookEvent::Publish => String::from("publish"),
+            WebhookEvent::Unpublish => String::from("unpublish"),
+            WebhookEvent::Trash => String::from("trash"),
+            WebhookEvent::Delete => String::from("delete"),
+        }
+    }
+}
+
+#[derive(Debug, DbEnum, PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NumberingScheme {
+    Decimal,
+    Outline,
+    Legal,
+}
+
+#[derive(Debug, DbEnum, PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TocGeneration {
+    Headings,
+    Depth,
+}
+
+table! {
+    use diesel::sql_types::{Uuid, Text, Timestamp};
+    use super::ActivityEventMapping;
+    activities (id) {
+        id -> Uuid,
+        document_id -> Uuid,
+        user_id -> Uuid,
+        event -> ActivityEventMapping,
+        summary -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    attachments (id) {
+        id -> Uuid,
+        document_id -> Uuid,
+        item_id -> Nullable<Uuid>,
+        user_id -> Uuid,
+        file_name -> Text,
+        content_type -> Text,
+        size_bytes -> Int8,
+        storage_key -> Text,
+        thumbnail_key -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    calendar_tokens (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        created_at -> Timestamp,
+    }
+}
+
 table! {
     categories (id) {
         id -> Uuid,
@@ -74,6 +202,21 @@ table! {
 }
 
 table! {
+    use diesel::sql_types::{Uuid, Nullable, Int4, Text};
+    use super::StylePropertyMapping;
+    use super::StyleUnitMapping;
+    document_styles (document_id, property) {
+        document_id -> Uuid,
+        property -> StylePropertyMapping,
+        value_number -> Nullable<Int4>,
+        value_string -> Nullable<Text>,
+        unit -> Nullable<StyleUnitMapping>,
+    }
+}
+
+table! {
+    use diesel::sql_types::{Uuid, Nullable, Int4, Bool, Timestamp};
+    use super::{NumberingSchemeMapping, TocGenerationMapping};
     documents (id) {
         id -> Uuid,
         user_id -> Uuid,
@@ -82,10 +225,40 @@ table! {
         modified_at -> Nullable<Timestamp>,
         publicly_viewable -> Bool,
         toc_item_id -> Nullable<Uuid>,
+        toc_generation -> Nullable<TocGenerationMapping>,
+        toc_depth -> Int4,
+        numbering_scheme -> Nullable<NumberingSchemeMapping>,
+        folder_id -> Nullable<Uuid>,
+    }
+}
+
+table! {
+    use diesel::sql_types::{Uuid, Nullable, Timestamp, Jsonb};
+    document_forks (document_id) {
+        document_id -> Uuid,
+        source_document_id -> Nullable<Uuid>,
+        source_modified_at -> Timestamp,
+        base_root_id -> Uuid,
+        base_items -> Jsonb,
+        created_at -> Timestamp,
+        synced_at -> Timestamp,
+    }
+}
+
+table! {
+    use diesel::sql_types::{Uuid, Nullable, Text, Timestamp};
+    folders (id) {
+        id -> Uuid,
+        user_id -> Uuid,
+        parent_id -> Nullable<Uuid>,
+        name -> Text,
+        created_at -> Timestamp,
     }
 }
 
 table! {
+    use diesel::sql_types::{Uuid, Nullable, Int4, Text, Bool, Timestamp, Jsonb};
+    use super::{ItemKindMapping, TaskStateMapping};
     items (id) {
         id -> Uuid,
         document_id -> Uuid,
@@ -93,11 +266,20 @@ table! {
         item_text -> Text,
         child_order -> Int4,
         collapsed -> Bool,
+        task_state -> Nullable<TaskStateMapping>,
+        due_at -> Nullable<Timestamp>,
+        toc_target_id -> Nullable<Uuid>,
+        style_preset -> Nullable<Text>,
+        kind -> ItemKindMapping,
+        code_language -> Nullable<Text>,
+        rich_text -> Nullable<Jsonb>,
+        locked_by -> Nullable<Uuid>,
+        locked_at -> Nullable<Timestamp>,
     }
 }
 
 table! {
-    use diesel::sql_types::{Uuid, Nullable, Int4, Text};
+    use diesel::sql_types::{Uuid, Nullable, Int4, Text, Bool};
     use super::StylePropertyMapping;
     use super::StyleUnitMapping;
     styles (item_id, property) {
@@ -106,6 +288,7 @@ table! {
         value_number -> Nullable<Int4>,
         value_string -> Nullable<Text>,
         unit -> Nullable<StyleUnitMapping>,
+        inherit -> Bool,
     }
 }
 
@@ -116,12 +299,117 @@ table! {
         google_id -> Nullable<Text>,
         twitter_screen_name -> Nullable<Text>,
         facebook_id -> Nullable<Text>,
+        public_profile -> Bool,
+    }
+}
+
+table! {
+    stars (document_id, user_id) {
+        document_id -> Uuid,
+        user_id -> Uuid,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    style_presets (id) {
+        id -> Uuid,
+        user_id -> Nullable<Uuid>,
+        document_id -> Nullable<Uuid>,
+        name -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    use diesel::sql_types::{Uuid, Nullable, Int4, Text};
+    use super::StylePropertyMapping;
+    use super::StyleUnitMapping;
+    style_preset_properties (preset_id, property) {
+        preset_id -> Uuid,
+        property -> StylePropertyMapping,
+        value_number -> Nullable<Int4>,
+        value_string -> Nullable<Text>,
+        unit -> Nullable<StyleUnitMapping>,
+    }
+}
+
+table! {
+    subtree_shares (id) {
+        id -> Uuid,
+        document_id -> Uuid,
+        item_id -> Uuid,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    webhooks (id) {
+        id -> Uuid,
+        document_id -> Uuid,
+        user_id -> Uuid,
+        url -> Text,
+        secret -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    use diesel::sql_types::{Uuid, Nullable, Int4, Text, Bool, Timestamp};
+    use super::WebhookEventMapping;
+    webhook_deliveries (id) {
+        id -> Uuid,
+        webhook_id -> Uuid,
+        delivery_id -> Uuid,
+        event -> WebhookEventMapping,
+        attempt -> Int4,
+        status_code -> Nullable<Int4>,
+        error -> Nullable<Text>,
+        succeeded -> Bool,
+        attempted_at -> Timestamp,
     }
 }
 
+joinable!(activities -> documents (document_id));
+joinable!(activities -> users (user_id));
+joinable!(attachments -> documents (document_id));
+joinable!(attachments -> items (item_id));
+joinable!(attachments -> users (user_id));
+joinable!(calendar_tokens -> users (user_id));
 joinable!(categories -> documents (document_id));
 joinable!(categories -> users (user_id));
+joinable!(document_forks -> documents (document_id));
+joinable!(document_styles -> documents (document_id));
+joinable!(documents -> folders (folder_id));
 joinable!(documents -> users (user_id));
+joinable!(folders -> users (user_id));
+joinable!(stars -> documents (document_id));
+joinable!(stars -> users (user_id));
+joinable!(style_preset_properties -> style_presets (preset_id));
+joinable!(style_presets -> documents (document_id));
+joinable!(style_presets -> users (user_id));
 joinable!(styles -> items (item_id));
+joinable!(subtree_shares -> documents (document_id));
+joinable!(webhook_deliveries -> webhooks (webhook_id));
+joinable!(webhooks -> documents (document_id));
+joinable!(webhooks -> users (user_id));
 
-allow_tables_to_appear_in_same_query!(categories, documents, items, users, styles);
+allow_tables_to_appear_in_same_query!(
+    activities,
+    attachments,
+    calendar_tokens,
+    categories,
+    document_forks,
+    document_styles,
+    documents,
+    folders,
+    items,
+    stars,
+    users,
+    styles,
+    style_presets,
+    style_preset_properties,
+    subtree_shares,
+    webhooks,
+    webhook_deliveries
+);
@@ -0,0 +1,160 @@
+use diesel;
+use diesel::prelude::*;
+
+use data::db::Connection;
+use data::document::DocumentID;
+use data::item::ItemID;
+use data::schema::items;
+use data::schema::subtree_shares;
+use data::schema::subtree_shares::dsl::*;
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use std::time::SystemTime;
+
+use uuid;
+
+#[derive(TaggedID, Serialize, Deserialize)]
+pub struct SubtreeShareID(uuid::Uuid);
+
+// anyone holding a share's ID can view the item it's scoped to and everything
+// below it, but nothing else in the document; items get new IDs whenever the
+// outline is saved, so the share follows them rather than referencing them
+pub struct SubtreeShare<'a> {
+    connection: &'a Connection,
+    pub data: Data,
+}
+
+#[derive(Debug, Queryable, Identifiable)]
+#[table_name = "subtree_shares"]
+pub struct Data {
+    id: uuid::Uuid,
+    document_id: uuid::Uuid,
+    item_id: uuid::Uuid,
+    pub created_at: SystemTime,
+}
+
+#[derive(Insertable)]
+#[table_name = "subtree_shares"]
+struct NewSubtreeShare {
+    document_id: uuid::Uuid,
+    item_id: uuid::Uuid,
+}
+
+impl<'a> SubtreeShare<'a> {
+    pub fn get_id(&self) -> SubtreeShareID {
+        SubtreeShareID::from_uuid(self.data.id.clone())
+    }
+
+    pub fn get_document_id(&self) -> DocumentID {
+        DocumentID::from_uuid(self.data.document_id.clone())
+    }
+
+    pub fn get_item_id(&self) -> ItemID {
+        ItemID::from_uuid(self.data.item_id.clone())
+    }
+
+    pub fn create(
+        connection: &'a Connection,
+        p_document_id: &DocumentID,
+        p_item_id: &ItemID,
+    ) -> QueryResult<SubtreeShare<'a>> {
+        let data = diesel::insert_into(subtree_shares)
+            .values(NewSubtreeShare {
+                document_id: **p_document_id,
+                item_id: **p_item_id,
+            })
+            .get_result::<Data>(&connection.pg_connection)?;
+
+        Ok(SubtreeShare { connection, data })
+    }
+
+    pub fn get_by_id(
+        connection: &'a Connection,
+        p_share_id: &SubtreeShareID,
+    ) -> QueryResult<SubtreeShare<'a>> {
+        let data = subtree_shares
+            .filter(id.eq(&**p_share_id))
+            .first::<Data>(&connection.pg_connection)?;
+
+        Ok(SubtreeShare { connection, data })
+    }
+
+    pub fn get_by_item(
+        connection: &'a Connection,
+        p_document_id: &DocumentID,
+        p_item_id: &ItemID,
+    ) -> QueryResult<Option<SubtreeShare<'a>>> {
+        let data = subtree_shares
+            .filter(document_id.eq(&**p_document_id))
+            .filter(item_id.eq(&**p_item_id))
+            .order(created_at.asc())
+            .first::<Data>(&connection.pg_connection)
+            .optional()?;
+
+        Ok(data.map(|data| SubtreeShare { connection, data }))
+    }
+
+    pub fn get_by_document(
+        connection: &'a Connection,
+        p_document_id: &DocumentID,
+    ) -> QueryResult<Vec<SubtreeShare<'a>>> {
+        let shares = subtree_shares
+            .filter(document_id.eq(&**p_document_id))
+            .order(created_at.asc())
+            .load::<Data>(&connection.pg_connection)?;
+
+        Ok(shares
+            .into_iter()
+            .map(|data| SubtreeShare { connection, data })
+            .collect())
+    }
+
+    pub fn move_to_item(&mut self, p_item_id: &ItemID) -> QueryResult<&mut Self> {
+        self.data = diesel::update(subtree_shares)
+            .filter(id.eq(&self.data.id))
+            .set(item_id.eq(&**p_item_id))
+            .get_result::<Data>(&self.connection.pg_connection)?;
+
+        Ok(self)
+    }
+
+    // shares whose items were removed from the outline have nothing left to show
+    pub fn remove_orphaned(
+        connection: &'a Connection,
+        p_document_id: &DocumentID,
+    ) -> QueryResult<usize> {
+        diesel::delete(subtree_shares)
+            .filter(document_id.eq(&**p_document_id))
+            .filter(
+                item_id.ne_all(
+                    items::table
+                        .select(items::id)
+                        .filter(items::document_id.eq(&**p_document_id)),
+                ),
+            )
+            .execute(&connection.pg_connection)
+    }
+
+    pub fn revoke(&self) -> QueryResult<usize> {
+        diesel::delete(subtree_shares)
+            .filter(id.eq(&self.data.id))
+            .execute(&self.connection.pg_connection)
+    }
+}
+
+impl<'a> Serialize for SubtreeShare<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut serialized = serializer.serialize_struct("SubtreeShare", 4)?;
+
+        serialized.serialize_field("share_id", &self.get_id())?;
+        serialized.serialize_field("document_id", &self.get_document_id())?;
+        serialized.serialize_field("item_id", &self.get_item_id())?;
+        serialized.serialize_field("created_at", &self.data.created_at)?;
+
+        serialized.end()
+    }
+}
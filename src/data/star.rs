@@ -0,0 +1,82 @@
+use diesel;
+use diesel::prelude::*;
+
+use data::db::Connection;
+use data::document::DocumentID;
+use data::schema::stars;
+use data::schema::stars::dsl::*;
+use data::user::UserID;
+
+use std::collections::HashMap;
+
+use uuid;
+
+#[derive(Insertable)]
+#[table_name = "stars"]
+struct NewStar {
+    document_id: uuid::Uuid,
+    user_id: uuid::Uuid,
+}
+
+pub struct Star;
+
+impl Star {
+    // starring twice is harmless, so it isn't treated as an error
+    pub fn add(
+        connection: &Connection,
+        p_document_id: &DocumentID,
+        p_user_id: &UserID,
+    ) -> QueryResult<usize> {
+        diesel::insert_into(stars)
+            .values(NewStar {
+                document_id: **p_document_id,
+                user_id: **p_user_id,
+            })
+            .on_conflict_do_nothing()
+            .execute(&connection.pg_connection)
+    }
+
+    pub fn remove(
+        connection: &Connection,
+        p_document_id: &DocumentID,
+        p_user_id: &UserID,
+    ) -> QueryResult<usize> {
+        diesel::delete(stars)
+            .filter(document_id.eq(&**p_document_id))
+            .filter(user_id.eq(&**p_user_id))
+            .execute(&connection.pg_connection)
+    }
+
+    pub fn count_by_documents(
+        connection: &Connection,
+        p_document_ids: &[DocumentID],
+    ) -> QueryResult<HashMap<DocumentID, i64>> {
+        let document_uuids: Vec<uuid::Uuid> = p_document_ids.iter().map(|d| **d).collect();
+
+        Ok(stars
+            .filter(document_id.eq_any(document_uuids))
+            .group_by(document_id)
+            .select((document_id, diesel::dsl::count_star()))
+            .load::<(uuid::Uuid, i64)>(&connection.pg_connection)?
+            .into_iter()
+            .map(|(d, count)| (DocumentID::from_uuid(d), count))
+            .collect())
+    }
+
+    pub fn get_starred_by_user(
+        connection: &Connection,
+        p_user_id: &UserID,
+        p_document_ids: &[DocumentID],
+    ) -> QueryResult<Vec<DocumentID>> {
+        let document_uuids: Vec<uuid::Uuid> = p_document_ids.iter().map(|d| **d).collect();
+
+        Ok(stars
+            .filter(user_id.eq(&**p_user_id))
+            .filter(document_id.eq_any(document_uuids))
+            .select(document_id)
+            .load::<uuid::Uuid>(&connection.pg_connection)?
+            .into_iter()
+            .map(DocumentID::from_uuid)
+            .collect())
+    }
+}
@@ -0,0 +1,146 @@
+use data::item::{Item, ItemID};
+use data::numbering::number_outline;
+
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+#[derive(Serialize)]
+pub struct BranchStats {
+    pub item_id: ItemID,
+    pub title: String,
+    pub item_count: usize,
+    pub word_count: usize,
+    pub character_count: usize,
+}
+
+#[derive(Serialize)]
+pub struct DocumentStats {
+    pub item_count: usize,
+    pub max_depth: u32,
+    pub average_depth: f64,
+    pub word_count: usize,
+    pub character_count: usize,
+    pub branches: Vec<BranchStats>,
+    pub last_modified: SystemTime,
+    pub seconds_since_modified: u64,
+}
+
+pub fn count_words(text: &str) -> usize {
+    text.split_whitespace().count()
+}
+
+pub fn count_characters(text: &str) -> usize {
+    text.chars().count()
+}
+
+// follows parent links up to the item directly below the root
+fn find_branch(
+    item_id: &ItemID,
+    root_id: &ItemID,
+    parents: &HashMap<ItemID, ItemID>,
+) -> Option<ItemID> {
+    let mut current = item_id;
+
+    // an item can't be nested deeper than there are items
+    for _ in 0..=parents.len() {
+        match parents.get(current) {
+            Some(parent) if parent == root_id => return Some(current.clone()),
+            Some(parent) => current = parent,
+            None => return None,
+        }
+    }
+
+    None
+}
+
+// the root item is the document's title and the TOC is generated from the
+// headings, so neither counts towards the outline's statistics
+pub fn compute_stats(
+    items: &[Item],
+    root_id: &ItemID,
+    toc_id: Option<&ItemID>,
+    last_modified: SystemTime,
+) -> DocumentStats {
+    let positions = number_outline(items.iter(), root_id, None, None);
+
+    let parents: HashMap<ItemID, ItemID> = items
+        .iter()
+        .filter_map(|i| i.get_parent_id().map(|p| (i.get_id(), p)))
+        .collect();
+
+    let mut branches: Vec<(i32, BranchStats)> = items
+        .iter()
+        .filter(|i| i.get_parent_id().as_ref() == Some(root_id))
+        .filter(|i| Some(&i.get_id()) != toc_id)
+        .map(|i| {
+            (
+                i.data.child_order,
+                BranchStats {
+                    item_id: i.get_id(),
+                    title: i.data.item_text.clone(),
+                    item_count: 0,
+                    word_count: 0,
+                    character_count: 0,
+                },
+            )
+        })
+        .collect();
+    branches.sort_by_key(|b| b.0);
+
+    let branch_indices: HashMap<ItemID, usize> = branches
+        .iter()
+        .enumerate()
+        .map(|(index, b)| (b.1.item_id.clone(), index))
+        .collect();
+
+    let mut item_count = 0;
+    let mut total_depth = 0;
+    let mut max_depth = 0;
+    let mut word_count = 0;
+    let mut character_count = 0;
+
+    for item in items.iter() {
+        let item_id = item.get_id();
+
+        let branch_index = match find_branch(&item_id, root_id, &parents) {
+            Some(ref branch_id) => match branch_indices.get(branch_id) {
+                Some(index) => *index,
+                None => continue,
+            },
+            None => continue,
+        };
+
+        let depth = positions.get(&item_id).map(|p| p.depth).unwrap_or(0);
+        let words = count_words(&item.data.item_text);
+        let characters = count_characters(&item.data.item_text);
+
+        item_count += 1;
+        total_depth += depth;
+        max_depth = max_depth.max(depth);
+        word_count += words;
+        character_count += characters;
+
+        let ref mut branch = branches[branch_index].1;
+        branch.item_count += 1;
+        branch.word_count += words;
+        branch.character_count += characters;
+    }
+
+    DocumentStats {
+        item_count,
+        max_depth,
+        average_depth: if item_count > 0 {
+            total_depth as f64 / item_count as f64
+        } else {
+            0.0
+        },
+        word_count,
+        character_count,
+        branches: branches.into_iter().map(|b| b.1).collect(),
+        last_modified,
+        seconds_since_modified: SystemTime::now()
+            .duration_since(last_modified)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+    }
+}
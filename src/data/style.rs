@@ -1,7 +1,10 @@
 use diesel::prelude::*;
 
+use data::color::is_valid_color;
 use data::db::Connection;
+use data::document::DocumentID;
 use data::item::ItemID;
+use data::schema::items;
 use data::schema::styles;
 use data::schema::styles::dsl::*;
 use data::schema::{StyleProperty, StyleUnit};
@@ -9,6 +12,243 @@ use data::schema::{StyleProperty, StyleUnit};
 use serde::ser::{Serialize, SerializeStruct, Serializer};
 
 use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug)]
+pub struct StyleError {
+    pub property: StyleProperty,
+    pub reason: String,
+}
+
+impl fmt::Display for StyleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.property.to_string(), self.reason)
+    }
+}
+
+impl Error for StyleError {}
+
+type LengthRange = (Option<StyleUnit>, i32, i32);
+
+const FONT_SIZE_RANGES: [LengthRange; 5] = [
+    (Some(StyleUnit::Px), 1, 200),
+    (Some(StyleUnit::Pt), 1, 150),
+    (Some(StyleUnit::Em), 1, 10),
+    (Some(StyleUnit::Rem), 1, 10),
+    (Some(StyleUnit::Ppct), 10, 1000),
+];
+
+const LINE_HEIGHT_RANGES: [LengthRange; 6] = [
+    (None, 1, 10),
+    (Some(StyleUnit::Px), 1, 200),
+    (Some(StyleUnit::Pt), 1, 150),
+    (Some(StyleUnit::Em), 1, 10),
+    (Some(StyleUnit::Rem), 1, 10),
+    (Some(StyleUnit::Ppct), 50, 500),
+];
+
+const TEXT_INDENT_RANGES: [LengthRange; 6] = [
+    (Some(StyleUnit::Px), 0, 400),
+    (Some(StyleUnit::Pt), 0, 300),
+    (Some(StyleUnit::Em), 0, 20),
+    (Some(StyleUnit::Rem), 0, 20),
+    (Some(StyleUnit::Ch), 0, 40),
+    (Some(StyleUnit::Ppct), 0, 100),
+];
+
+const FONT_WEIGHT_KEYWORDS: [&'static str; 4] = ["normal", "bold", "lighter", "bolder"];
+const FONT_STYLE_KEYWORDS: [&'static str; 3] = ["normal", "italic", "oblique"];
+const TEXT_DECORATION_KEYWORDS: [&'static str; 3] = ["underline", "line-through", "overline"];
+const TEXT_ALIGN_KEYWORDS: [&'static str; 4] = ["left", "right", "center", "justify"];
+
+const MAX_FONT_FAMILY_LENGTH: usize = 100;
+
+fn style_error(property: StyleProperty, reason: &str) -> StyleError {
+    StyleError {
+        property,
+        reason: reason.to_string(),
+    }
+}
+
+fn validate_keyword(
+    property: StyleProperty,
+    value: Option<&str>,
+    keywords: &[&'static str],
+) -> Result<(), StyleError> {
+    match value {
+        Some(v) if keywords.contains(&v) => Ok(()),
+        _ => Err(style_error(
+            property,
+            &format!("value must be one of {}", keywords.join(", ")),
+        )),
+    }
+}
+
+fn validate_length(
+    property: StyleProperty,
+    value: Option<i32>,
+    p_unit: Option<StyleUnit>,
+    ranges: &[LengthRange],
+) -> Result<(), StyleError> {
+    let n = value.ok_or(style_error(property, "a numeric value is required"))?;
+
+    let (_, min, max) = ranges
+        .iter()
+        .find(|r| r.0 == p_unit)
+        .ok_or(style_error(property, "this unit is not allowed"))?;
+
+    if n < *min || n > *max {
+        Err(style_error(
+            property,
+            &format!("value must be between {} and {}", min, max),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+fn validate_text_decoration(
+    property: StyleProperty,
+    value: Option<&str>,
+) -> Result<(), StyleError> {
+    let v = value.ok_or(style_error(property, "a value is required"))?;
+    if v == "none" {
+        return Ok(());
+    }
+
+    let decorations: Vec<&str> = v.split_whitespace().collect();
+    let all_known = decorations
+        .iter()
+        .all(|d| TEXT_DECORATION_KEYWORDS.contains(d));
+    let all_unique = decorations
+        .iter()
+        .enumerate()
+        .all(|(i, d)| !decorations[..i].contains(d));
+
+    if !decorations.is_empty() && all_known && all_unique {
+        Ok(())
+    } else {
+        Err(style_error(
+            property,
+            "value must be none, or a combination of underline, line-through and overline",
+        ))
+    }
+}
+
+fn validate_font_family(property: StyleProperty, value: Option<&str>) -> Result<(), StyleError> {
+    let v = value.ok_or(style_error(property, "a value is required"))?;
+
+    let valid_chars = v
+        .chars()
+        .all(|c| c.is_alphanumeric() || " -,'\"".contains(c));
+    let valid_families = v.split(',').all(|f| f.trim() != "");
+
+    if v.len() <= MAX_FONT_FAMILY_LENGTH && valid_chars && valid_families {
+        Ok(())
+    } else {
+        Err(style_error(
+            property,
+            "value is not a valid list of font families",
+        ))
+    }
+}
+
+pub fn validate_style(
+    p_property: StyleProperty,
+    p_value_string: Option<&str>,
+    p_value_number: Option<i32>,
+    p_unit: Option<StyleUnit>,
+) -> Result<(), StyleError> {
+    let is_numeric = match p_property {
+        StyleProperty::FontSize | StyleProperty::LineHeight | StyleProperty::TextIndent => true,
+        StyleProperty::FontWeight => p_value_number.is_some(),
+        _ => false,
+    };
+
+    if is_numeric && p_value_string.is_some() {
+        return Err(style_error(p_property, "a string value is not allowed"));
+    } else if !is_numeric && (p_value_number.is_some() || p_unit.is_some()) {
+        return Err(style_error(p_property, "a numeric value is not allowed"));
+    }
+
+    match p_property {
+        StyleProperty::Color | StyleProperty::BackgroundColor => match p_value_string {
+            Some(c) if is_valid_color(c) => Ok(()),
+            _ => Err(style_error(
+                p_property,
+                "value must be a hex, rgb() or named colour",
+            )),
+        },
+        StyleProperty::FontSize => {
+            validate_length(p_property, p_value_number, p_unit, &FONT_SIZE_RANGES)
+        }
+        StyleProperty::LineHeight => {
+            validate_length(p_property, p_value_number, p_unit, &LINE_HEIGHT_RANGES)
+        }
+        StyleProperty::TextIndent => {
+            validate_length(p_property, p_value_number, p_unit, &TEXT_INDENT_RANGES)
+        }
+        StyleProperty::FontWeight => match p_value_number {
+            Some(w) if p_unit.is_none() && w >= 100 && w <= 900 && w % 100 == 0 => Ok(()),
+            Some(_) => Err(style_error(
+                p_property,
+                "value must be a multiple of 100 between 100 and 900",
+            )),
+            None => validate_keyword(p_property, p_value_string, &FONT_WEIGHT_KEYWORDS),
+        },
+        StyleProperty::FontStyle => {
+            validate_keyword(p_property, p_value_string, &FONT_STYLE_KEYWORDS)
+        }
+        StyleProperty::TextAlign => {
+            validate_keyword(p_property, p_value_string, &TEXT_ALIGN_KEYWORDS)
+        }
+        StyleProperty::TextDecoration => validate_text_decoration(p_property, p_value_string),
+        StyleProperty::FontFamily => validate_font_family(p_property, p_value_string),
+    }
+}
+
+// a style value as it applies to an item once inheritance has been resolved
+#[derive(Clone, Serialize)]
+pub struct ComputedStyle {
+    pub property: StyleProperty,
+    pub value_string: Option<String>,
+    pub value_number: Option<i32>,
+    pub unit: Option<StyleUnit>,
+    pub inherited: bool,
+}
+
+fn css_property(property: StyleProperty) -> &'static str {
+    match property {
+        StyleProperty::BackgroundColor => "background-color",
+        StyleProperty::Color => "color",
+        StyleProperty::FontSize => "font-size",
+        StyleProperty::LineHeight => "line-height",
+        StyleProperty::FontWeight => "font-weight",
+        StyleProperty::FontStyle => "font-style",
+        StyleProperty::TextDecoration => "text-decoration",
+        StyleProperty::FontFamily => "font-family",
+        StyleProperty::TextAlign => "text-align",
+        StyleProperty::TextIndent => "text-indent",
+    }
+}
+
+impl ComputedStyle {
+    // values were validated when they were saved, so they are safe as CSS
+    pub fn to_css(&self) -> Option<String> {
+        let value = match (self.value_number, self.value_string.as_ref()) {
+            (Some(n), _) => format!(
+                "{}{}",
+                n,
+                self.unit.map(|u| u.to_string()).unwrap_or_default()
+            ),
+            (None, Some(s)) => s.clone(),
+            (None, None) => return None,
+        };
+
+        Some(format!("{}: {}", css_property(self.property), value))
+    }
+}
 
 pub struct Style<'a> {
     connection: &'a Connection,
@@ -24,6 +264,7 @@ pub struct Data {
     pub value_number: Option<i32>,
     pub value_string: Option<String>,
     pub unit: Option<StyleUnit>,
+    pub inherit: bool,
 }
 
 impl<'a> Style<'a> {
@@ -38,6 +279,7 @@ impl<'a> Style<'a> {
         p_value_string: Option<String>,
         p_value_number: Option<i32>,
         p_unit: Option<StyleUnit>,
+        p_inherit: bool,
     ) -> Style<'a> {
         Style {
             connection,
@@ -47,10 +289,24 @@ impl<'a> Style<'a> {
                 value_string: p_value_string,
                 value_number: p_value_number,
                 unit: p_unit,
+                inherit: p_inherit,
             },
         }
     }
 
+    pub fn get_item_id(&self) -> ItemID {
+        ItemID::from_uuid(self.data.item_id)
+    }
+
+    pub fn validate(&self) -> Result<(), StyleError> {
+        validate_style(
+            self.data.property,
+            self.data.value_string.as_ref().map(|s| s.as_str()),
+            self.data.value_number,
+            self.data.unit,
+        )
+    }
+
     pub fn update(&mut self) -> QueryResult<&mut Self> {
         let data = diesel::update(styles)
             .filter(item_id.eq(&self.data.item_id))
@@ -59,6 +315,7 @@ impl<'a> Style<'a> {
                 value_string.eq(&self.data.value_string),
                 value_number.eq(&self.data.value_number),
                 unit.eq(&self.data.unit),
+                inherit.eq(self.data.inherit),
             ))
             .get_result(&self.connection.pg_connection)?;
 
@@ -81,6 +338,39 @@ impl<'a> Style<'a> {
             .map(|data| Style { connection, data })
             .collect())
     }
+
+    // loads the styles of every item in a document with a single query
+    pub fn get_by_document(
+        connection: &'a Connection,
+        p_document_id: &DocumentID,
+    ) -> QueryResult<Vec<Style<'a>>> {
+        let p_document_uuid = **p_document_id;
+
+        let styles_list = styles
+            .filter(
+                item_id.eq_any(
+                    items::table
+                        .select(items::id)
+                        .filter(items::document_id.eq(p_document_uuid)),
+                ),
+            )
+            .load::<Data>(&connection.pg_connection)?;
+
+        Ok(styles_list
+            .into_iter()
+            .map(|data| Style { connection, data })
+            .collect())
+    }
+
+    pub fn to_computed(&self, p_inherited: bool) -> ComputedStyle {
+        ComputedStyle {
+            property: self.data.property,
+            value_string: self.data.value_string.clone(),
+            value_number: self.data.value_number,
+            unit: self.data.unit,
+            inherited: p_inherited,
+        }
+    }
 }
 
 impl<'a> Serialize for Style<'a> {
@@ -88,12 +378,13 @@ impl<'a> Serialize for Style<'a> {
     where
         S: Serializer,
     {
-        let mut serialized = serializer.serialize_struct("Style", 4)?;
+        let mut serialized = serializer.serialize_struct("Style", 5)?;
 
         serialized.serialize_field("property", &self.data.property.to_string())?;
         serialized.serialize_field("value_string", &self.data.value_string)?;
         serialized.serialize_field("value_number", &self.data.value_number)?;
         serialized.serialize_field("unit", &self.data.unit)?;
+        serialized.serialize_field("inherit", &self.data.inherit)?;
 
         serialized.end()
     }
@@ -105,3 +396,132 @@ pub fn style_vec_to_map<'a>(style_vec: Vec<Style<'a>>) -> HashMap<StyleProperty,
         .map(|se| (se.data.property, se))
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string_value(property: StyleProperty, value: &str) -> bool {
+        validate_style(property, Some(value), None, None).is_ok()
+    }
+
+    fn number_value(property: StyleProperty, value: i32, p_unit: Option<StyleUnit>) -> bool {
+        validate_style(property, None, Some(value), p_unit).is_ok()
+    }
+
+    #[test]
+    fn validates_colors() {
+        for property in [StyleProperty::Color, StyleProperty::BackgroundColor].iter() {
+            assert!(string_value(*property, "#336699"));
+            assert!(string_value(*property, "rgb(0, 0, 0)"));
+            assert!(string_value(*property, "navy"));
+            assert!(!string_value(*property, "nope"));
+            assert!(!number_value(*property, 1, None));
+            assert!(validate_style(*property, None, None, None).is_err());
+        }
+    }
+
+    #[test]
+    fn validates_font_size() {
+        let property = StyleProperty::FontSize;
+
+        assert!(number_value(property, 12, Some(StyleUnit::Px)));
+        assert!(number_value(property, 150, Some(StyleUnit::Pt)));
+        assert!(number_value(property, 10, Some(StyleUnit::Ppct)));
+        assert!(!number_value(property, 0, Some(StyleUnit::Px)));
+        assert!(!number_value(property, 201, Some(StyleUnit::Px)));
+        assert!(!number_value(property, 151, Some(StyleUnit::Pt)));
+        assert!(!number_value(property, 9, Some(StyleUnit::Ppct)));
+        assert!(!number_value(property, 12, None));
+        assert!(!number_value(property, 12, Some(StyleUnit::Vw)));
+        assert!(!string_value(property, "12px"));
+    }
+
+    #[test]
+    fn validates_line_height() {
+        let property = StyleProperty::LineHeight;
+
+        assert!(number_value(property, 2, None));
+        assert!(number_value(property, 50, Some(StyleUnit::Ppct)));
+        assert!(number_value(property, 24, Some(StyleUnit::Px)));
+        assert!(!number_value(property, 11, None));
+        assert!(!number_value(property, 49, Some(StyleUnit::Ppct)));
+        assert!(!number_value(property, 2, Some(StyleUnit::Ch)));
+        assert!(!string_value(property, "normal"));
+    }
+
+    #[test]
+    fn validates_text_indent() {
+        let property = StyleProperty::TextIndent;
+
+        assert!(number_value(property, 0, Some(StyleUnit::Px)));
+        assert!(number_value(property, 40, Some(StyleUnit::Ch)));
+        assert!(number_value(property, 100, Some(StyleUnit::Ppct)));
+        assert!(!number_value(property, -1, Some(StyleUnit::Px)));
+        assert!(!number_value(property, 41, Some(StyleUnit::Ch)));
+        assert!(!number_value(property, 10, None));
+        assert!(validate_style(property, None, None, Some(StyleUnit::Px)).is_err());
+    }
+
+    #[test]
+    fn validates_font_weight() {
+        let property = StyleProperty::FontWeight;
+
+        assert!(number_value(property, 100, None));
+        assert!(number_value(property, 900, None));
+        assert!(string_value(property, "bold"));
+        assert!(!number_value(property, 0, None));
+        assert!(!number_value(property, 450, None));
+        assert!(!number_value(property, 1000, None));
+        assert!(!number_value(property, 400, Some(StyleUnit::Px)));
+        assert!(!string_value(property, "heavy"));
+        assert!(validate_style(property, Some("bold"), Some(700), None).is_err());
+    }
+
+    #[test]
+    fn validates_keywords() {
+        assert!(string_value(StyleProperty::FontStyle, "italic"));
+        assert!(!string_value(StyleProperty::FontStyle, "slanted"));
+        assert!(validate_style(StyleProperty::FontStyle, None, None, None).is_err());
+
+        assert!(string_value(StyleProperty::TextAlign, "justify"));
+        assert!(!string_value(StyleProperty::TextAlign, "middle"));
+        assert!(!number_value(StyleProperty::TextAlign, 1, None));
+    }
+
+    #[test]
+    fn validates_text_decoration() {
+        let property = StyleProperty::TextDecoration;
+
+        assert!(string_value(property, "none"));
+        assert!(string_value(property, "underline"));
+        assert!(string_value(property, "underline line-through overline"));
+        assert!(!string_value(property, ""));
+        assert!(!string_value(property, "blink"));
+        assert!(!string_value(property, "underline underline"));
+        assert!(!string_value(property, "none underline"));
+    }
+
+    #[test]
+    fn validates_font_family() {
+        let property = StyleProperty::FontFamily;
+
+        assert!(string_value(property, "Georgia, 'Times New Roman', serif"));
+        assert!(string_value(property, "\"Noto Sans\""));
+        assert!(!string_value(property, "Arial;"));
+        assert!(!string_value(property, "Arial,,serif"));
+        assert!(!string_value(property, "url(font.woff)"));
+        assert!(!string_value(
+            property,
+            &"a".repeat(MAX_FONT_FAMILY_LENGTH + 1)
+        ));
+    }
+
+    #[test]
+    fn reports_the_rejected_property() {
+        let error = validate_style(StyleProperty::FontSize, Some("12px"), None, None).unwrap_err();
+
+        assert_eq!(error.property, StyleProperty::FontSize);
+        assert_eq!(error.reason, "a string value is not allowed");
+    }
+}
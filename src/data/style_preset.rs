@@ -0,0 +1,365 @@
+use diesel;
+use diesel::prelude::*;
+use diesel::Connection as DieselConnection;
+
+use data::db::Connection;
+use data::document::DocumentID;
+use data::schema::documents;
+use data::schema::items;
+use data::schema::style_preset_properties;
+use data::schema::style_presets;
+use data::schema::style_presets::dsl::*;
+use data::schema::{StyleProperty, StyleUnit};
+use data::style::{validate_style, ComputedStyle, StyleError};
+use data::user::UserID;
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+use uuid;
+
+#[derive(TaggedID, Serialize, Deserialize)]
+pub struct StylePresetID(uuid::Uuid);
+
+pub struct StylePreset<'a> {
+    connection: &'a Connection,
+    pub data: Data,
+    pub properties: Vec<PropertyData>,
+}
+
+#[derive(Debug, Queryable, Identifiable)]
+#[table_name = "style_presets"]
+pub struct Data {
+    id: uuid::Uuid,
+    user_id: Option<uuid::Uuid>,
+    document_id: Option<uuid::Uuid>,
+    pub name: String,
+    pub created_at: SystemTime,
+}
+
+#[derive(Insertable)]
+#[table_name = "style_presets"]
+struct NewStylePreset<'a> {
+    user_id: Option<uuid::Uuid>,
+    document_id: Option<uuid::Uuid>,
+    name: &'a str,
+}
+
+#[derive(Insertable, Queryable)]
+#[table_name = "style_preset_properties"]
+pub struct PropertyData {
+    preset_id: uuid::Uuid,
+    pub property: StyleProperty,
+    pub value_number: Option<i32>,
+    pub value_string: Option<String>,
+    pub unit: Option<StyleUnit>,
+}
+
+impl PropertyData {
+    pub fn to_computed(&self) -> ComputedStyle {
+        ComputedStyle {
+            property: self.property,
+            value_string: self.value_string.clone(),
+            value_number: self.value_number,
+            unit: self.unit,
+            inherited: false,
+        }
+    }
+}
+
+impl<'a> StylePreset<'a> {
+    // loads the properties of every preset in the list with a single query
+    fn results_list(
+        connection: &'a Connection,
+        presets_list: Vec<Data>,
+    ) -> QueryResult<Vec<StylePreset<'a>>> {
+        let preset_ids: Vec<uuid::Uuid> = presets_list.iter().map(|p| p.id).collect();
+
+        let mut properties_by_preset = HashMap::<uuid::Uuid, Vec<PropertyData>>::new();
+        for p in style_preset_properties::table
+            .filter(style_preset_properties::preset_id.eq_any(preset_ids))
+            .load::<PropertyData>(&connection.pg_connection)?
+            .into_iter()
+        {
+            properties_by_preset
+                .entry(p.preset_id)
+                .or_insert(Vec::new())
+                .push(p);
+        }
+
+        Ok(presets_list
+            .into_iter()
+            .map(|data| StylePreset {
+                connection,
+                properties: properties_by_preset.remove(&data.id).unwrap_or(Vec::new()),
+                data,
+            })
+            .collect())
+    }
+
+    pub fn get_id(&self) -> StylePresetID {
+        StylePresetID::from_uuid(self.data.id.clone())
+    }
+
+    pub fn get_user_id(&self) -> Option<UserID> {
+        self.data.user_id.map(UserID::from_uuid)
+    }
+
+    pub fn get_document_id(&self) -> Option<DocumentID> {
+        self.data.document_id.map(DocumentID::from_uuid)
+    }
+
+    fn create(
+        connection: &'a Connection,
+        p_user_id: Option<&UserID>,
+        p_document_id: Option<&DocumentID>,
+        p_name: &str,
+    ) -> QueryResult<Self> {
+        let data = diesel::insert_into(style_presets)
+            .values(NewStylePreset {
+                user_id: p_user_id.map(|u| **u),
+                document_id: p_document_id.map(|d| **d),
+                name: p_name,
+            })
+            .get_result(&connection.pg_connection)?;
+
+        Ok(StylePreset {
+            connection,
+            data,
+            properties: Vec::new(),
+        })
+    }
+
+    pub fn create_for_user(
+        connection: &'a Connection,
+        p_user_id: &UserID,
+        p_name: &str,
+    ) -> QueryResult<Self> {
+        Self::create(connection, Some(p_user_id), None, p_name)
+    }
+
+    pub fn create_for_document(
+        connection: &'a Connection,
+        p_document_id: &DocumentID,
+        p_name: &str,
+    ) -> QueryResult<Self> {
+        Self::create(connection, None, Some(p_document_id), p_name)
+    }
+
+    pub fn get_by_id(connection: &'a Connection, p_preset_id: &StylePresetID) -> QueryResult<Self> {
+        let data = style_presets
+            .filter(id.eq(&**p_preset_id))
+            .first::<Data>(&connection.pg_connection)?;
+
+        let mut presets_list = Self::results_list(connection, vec![data])?;
+        presets_list.pop().ok_or(diesel::result::Error::NotFound)
+    }
+
+    pub fn get_by_user(connection: &'a Connection, p_user_id: &UserID) -> QueryResult<Vec<Self>> {
+        let presets_list = style_presets
+            .filter(user_id.eq(&**p_user_id))
+            .order(name.asc())
+            .load::<Data>(&connection.pg_connection)?;
+
+        Self::results_list(connection, presets_list)
+    }
+
+    pub fn get_by_document(
+        connection: &'a Connection,
+        p_document_id: &DocumentID,
+    ) -> QueryResult<Vec<Self>> {
+        let presets_list = style_presets
+            .filter(document_id.eq(&**p_document_id))
+            .order(name.asc())
+            .load::<Data>(&connection.pg_connection)?;
+
+        Self::results_list(connection, presets_list)
+    }
+
+    // the presets available to a document are those of the document itself and
+    // those of its owner, with document presets taking precedence by name
+    pub fn get_available(
+        connection: &'a Connection,
+        p_document_id: &DocumentID,
+        p_owner_id: &UserID,
+    ) -> QueryResult<Vec<Self>> {
+        let presets_list = style_presets
+            .filter(
+                document_id
+                    .eq(&**p_document_id)
+                    .or(user_id.eq(&**p_owner_id)),
+            )
+            .order(name.asc())
+            .load::<Data>(&connection.pg_connection)?;
+
+        let mut presets_by_name = HashMap::<String, Data>::new();
+        for data in presets_list.into_iter() {
+            let overrides = match presets_by_name.get(&data.name) {
+                Some(existing) => existing.document_id.is_none(),
+                None => true,
+            };
+
+            if overrides {
+                presets_by_name.insert(data.name.clone(), data);
+            }
+        }
+
+        let mut presets_list: Vec<Data> = presets_by_name.into_iter().map(|(_, d)| d).collect();
+        presets_list.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Self::results_list(connection, presets_list)
+    }
+
+    // items refer to presets by name, so they are renamed along with it; the
+    // owner's documents with a preset of their own by the old name keep theirs
+    pub fn rename(&mut self, p_name: &str) -> QueryResult<&mut Self> {
+        let old_name = self.data.name.clone();
+        let p_preset_id = self.data.id;
+        let p_document_id = self.data.document_id;
+        let p_user_id = self.data.user_id;
+        let connection = self.connection;
+
+        let data = connection
+            .pg_connection
+            .transaction::<_, diesel::result::Error, _>(|| {
+                let data = diesel::update(style_presets)
+                    .filter(id.eq(p_preset_id))
+                    .set(name.eq(p_name))
+                    .get_result::<Data>(&connection.pg_connection)?;
+
+                match (p_document_id, p_user_id) {
+                    (Some(d), _) => {
+                        diesel::update(items::table)
+                            .filter(items::document_id.eq(d))
+                            .filter(items::style_preset.eq(&old_name))
+                            .set(items::style_preset.eq(p_name))
+                            .execute(&connection.pg_connection)?;
+                    }
+                    (None, Some(u)) => {
+                        let overridden: Vec<uuid::Uuid> = style_presets
+                            .filter(name.eq(&old_name))
+                            .select(document_id)
+                            .load::<Option<uuid::Uuid>>(&connection.pg_connection)?
+                            .into_iter()
+                            .filter_map(|d| d)
+                            .collect();
+
+                        diesel::update(items::table)
+                            .filter(
+                                items::document_id.eq_any(
+                                    documents::table
+                                        .select(documents::id)
+                                        .filter(documents::user_id.eq(u)),
+                                ),
+                            )
+                            .filter(items::document_id.ne_all(overridden))
+                            .filter(items::style_preset.eq(&old_name))
+                            .set(items::style_preset.eq(p_name))
+                            .execute(&connection.pg_connection)?;
+                    }
+                    (None, None) => {}
+                }
+
+                Ok(data)
+            })?;
+
+        self.data = data;
+        Ok(self)
+    }
+
+    pub fn create_property(
+        &self,
+        p_property: StyleProperty,
+        p_value_string: Option<String>,
+        p_value_number: Option<i32>,
+        p_unit: Option<StyleUnit>,
+    ) -> PropertyData {
+        PropertyData {
+            preset_id: self.data.id,
+            property: p_property,
+            value_string: p_value_string,
+            value_number: p_value_number,
+            unit: p_unit,
+        }
+    }
+
+    // replaces the whole bundle of properties held by the preset
+    pub fn set_properties(&mut self, p_properties: Vec<PropertyData>) -> QueryResult<&mut Self> {
+        p_properties
+            .iter()
+            .map(|p| {
+                validate_style(
+                    p.property,
+                    p.value_string.as_ref().map(|s| s.as_str()),
+                    p.value_number,
+                    p.unit,
+                )
+            })
+            .collect::<Result<Vec<()>, StyleError>>()
+            .map_err(|e| diesel::result::Error::QueryBuilderError(Box::new(e)))?;
+
+        let p_preset_id = self.data.id;
+        let connection = self.connection;
+
+        let properties = connection
+            .pg_connection
+            .transaction::<_, diesel::result::Error, _>(|| {
+                diesel::delete(style_preset_properties::table)
+                    .filter(style_preset_properties::preset_id.eq(p_preset_id))
+                    .execute(&connection.pg_connection)?;
+
+                if p_properties.is_empty() {
+                    return Ok(Vec::new());
+                }
+
+                diesel::insert_into(style_preset_properties::table)
+                    .values(&p_properties)
+                    .get_results::<PropertyData>(&connection.pg_connection)
+            })?;
+
+        self.properties = properties;
+        Ok(self)
+    }
+
+    pub fn delete(&mut self) -> QueryResult<usize> {
+        diesel::delete(style_presets)
+            .filter(id.eq(self.data.id))
+            .execute(&self.connection.pg_connection)
+    }
+}
+
+impl Serialize for PropertyData {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut serialized = serializer.serialize_struct("PresetProperty", 4)?;
+
+        serialized.serialize_field("property", &self.property.to_string())?;
+        serialized.serialize_field("value_string", &self.value_string)?;
+        serialized.serialize_field("value_number", &self.value_number)?;
+        serialized.serialize_field("unit", &self.unit)?;
+
+        serialized.end()
+    }
+}
+
+impl<'a> Serialize for StylePreset<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut serialized = serializer.serialize_struct("StylePreset", 6)?;
+
+        serialized.serialize_field("preset_id", &self.get_id())?;
+        serialized.serialize_field("name", &self.data.name)?;
+        serialized.serialize_field("user_id", &self.get_user_id())?;
+        serialized.serialize_field("document_id", &self.get_document_id())?;
+        serialized.serialize_field("created_at", &self.data.created_at)?;
+        serialized.serialize_field("properties", &self.properties)?;
+
+        serialized.end()
+    }
+}
@@ -0,0 +1,153 @@
+use data::document::Document;
+use data::item::{Item, ItemID};
+use data::schema::{ItemKind, TocGeneration};
+
+use diesel::result::QueryResult;
+
+use std::collections::HashMap;
+
+pub const TOC_TITLE: &'static str = "Table of Contents";
+
+struct TocEntry {
+    target: ItemID,
+    text: String,
+    children: Vec<TocEntry>,
+}
+
+fn children_by_parent<'a>(items: Vec<Item<'a>>) -> HashMap<ItemID, Vec<Item<'a>>> {
+    let mut children = HashMap::<ItemID, Vec<Item<'a>>>::new();
+
+    for item in items.into_iter() {
+        if let Some(pid) = item.get_parent_id() {
+            children.entry(pid).or_insert(Vec::new()).push(item);
+        }
+    }
+
+    for siblings in children.values_mut() {
+        siblings.sort_by_key(|i| i.data.child_order);
+    }
+
+    children
+}
+
+fn collect_entries(
+    children: &HashMap<ItemID, Vec<Item>>,
+    parent: &ItemID,
+    depth: i32,
+    generation: TocGeneration,
+    max_depth: i32,
+    toc_id: &ItemID,
+) -> Vec<TocEntry> {
+    let mut entries = Vec::new();
+
+    for child in children.get(parent).iter().flat_map(|c| c.iter()) {
+        let child_id = child.get_id();
+        if child_id == *toc_id {
+            continue;
+        }
+
+        let (include, descend) = match generation {
+            TocGeneration::Headings => (child.data.kind == ItemKind::Heading, true),
+            TocGeneration::Depth => (depth <= max_depth, depth < max_depth),
+        };
+
+        let sub_entries = if descend {
+            collect_entries(
+                children,
+                &child_id,
+                depth + 1,
+                generation,
+                max_depth,
+                toc_id,
+            )
+        } else {
+            Vec::new()
+        };
+
+        if include {
+            entries.push(TocEntry {
+                target: child_id,
+                text: child.data.item_text.clone(),
+                children: sub_entries,
+            });
+        } else {
+            entries.extend(sub_entries);
+        }
+    }
+
+    entries
+}
+
+fn insert_entries(doc: &mut Document, parent: &ItemID, entries: &Vec<TocEntry>) -> QueryResult<()> {
+    for (order, entry) in entries.iter().enumerate() {
+        let entry_id = {
+            let mut entry_item =
+                doc.add_item(Some(parent.clone()), order as i32, Some(entry.text.clone()))?;
+            entry_item.set_toc_target(&entry.target)?;
+            entry_item.get_id()
+        };
+
+        insert_entries(doc, &entry_id, &entry.children)?;
+    }
+
+    Ok(())
+}
+
+// rebuilds the children of the document's TOC item from its current outline,
+// creating a TOC item at the top of the document if there is not one already
+pub fn regenerate(doc: &mut Document) -> QueryResult<()> {
+    let generation = match doc.data.toc_generation {
+        Some(g) => g,
+        None => return Ok(()),
+    };
+
+    let root_id = doc.get_root()?.get_id();
+    let children = children_by_parent(doc.get_items()?);
+
+    let existing_toc_id = doc.get_serialized_toc_id().and_then(|toc_id| {
+        let in_outline = children
+            .values()
+            .any(|siblings| siblings.iter().any(|i| i.get_id() == toc_id));
+
+        if toc_id != root_id && in_outline {
+            Some(toc_id)
+        } else {
+            None
+        }
+    });
+
+    let toc_id = match existing_toc_id {
+        Some(toc_id) => {
+            doc.get_item(&toc_id)?.remove_children()?;
+            toc_id
+        }
+        None => {
+            let first_order = children
+                .get(&root_id)
+                .and_then(|siblings| siblings.first())
+                .map(|i| i.data.child_order)
+                .unwrap_or(1);
+
+            doc.add_item(
+                Some(root_id.clone()),
+                first_order - 1,
+                Some(TOC_TITLE.to_string()),
+            )?
+            .get_id()
+        }
+    };
+
+    let entries = collect_entries(
+        &children,
+        &root_id,
+        1,
+        generation,
+        doc.data.toc_depth,
+        &toc_id,
+    );
+
+    insert_entries(doc, &toc_id, &entries)?;
+    doc.set_toc_item(&Some(toc_id))?;
+
+    Ok(())
+}
@@ -1,8 +1,15 @@
 use data;
 use data::db::Connection;
+use data::schema::activities;
+use data::schema::attachments;
 use data::schema::documents::dsl::{documents, user_id};
+use data::schema::folders;
+use data::schema::items;
+use data::schema::stars;
+use data::schema::style_presets;
 use data::schema::users;
 use data::schema::users::dsl::*;
+use data::schema::webhooks;
 
 use diesel;
 use diesel::prelude::*;
@@ -42,6 +49,7 @@ pub struct Data {
     pub google_id: Option<String>,
     pub twitter_screen_name: Option<String>,
     pub facebook_id: Option<String>,
+    pub public_profile: bool,
 }
 
 impl<'a> User<'a> {
@@ -49,6 +57,15 @@ impl<'a> User<'a> {
         UserID(self.data.id.clone())
     }
 
+    // users are only named to others if they have opted in to a public profile
+    pub fn public_name(&self) -> Option<String> {
+        if self.data.public_profile {
+            Some(self.data.display_name.clone())
+        } else {
+            None
+        }
+    }
+
     pub fn get_by_id(connection: &'a Connection, p_user_id: &UserID) -> QueryResult<User<'a>> {
         let p_uuid = **p_user_id;
 
@@ -207,6 +224,7 @@ impl<'a> User<'a> {
             } else {
                 rhs.twitter_screen_name.clone()
             },
+            public_profile: lhs.public_profile,
         }
     }
 
@@ -234,6 +252,61 @@ impl<'a> User<'a> {
                     .set(user_id.eq(&self.data.id))
                     .execute(&self.connection.pg_connection)?;
 
+                diesel::update(activities::table)
+                    .filter(activities::user_id.eq(&merge_user.data.id))
+                    .set(activities::user_id.eq(&self.data.id))
+                    .execute(&self.connection.pg_connection)?;
+
+                // attachment rows would otherwise cascade away with the merged
+                // user, leaving their files behind
+                diesel::update(attachments::table)
+                    .filter(attachments::user_id.eq(&merge_user.data.id))
+                    .set(attachments::user_id.eq(&self.data.id))
+                    .execute(&self.connection.pg_connection)?;
+
+                diesel::update(webhooks::table)
+                    .filter(webhooks::user_id.eq(&merge_user.data.id))
+                    .set(webhooks::user_id.eq(&self.data.id))
+                    .execute(&self.connection.pg_connection)?;
+
+                diesel::update(folders::table)
+                    .filter(folders::user_id.eq(&merge_user.data.id))
+                    .set(folders::user_id.eq(&self.data.id))
+                    .execute(&self.connection.pg_connection)?;
+
+                diesel::update(items::table)
+                    .filter(items::locked_by.eq(&merge_user.data.id))
+                    .set(items::locked_by.eq(&self.data.id))
+                    .execute(&self.connection.pg_connection)?;
+
+                // stars the surviving user already gave are dropped along with
+                // the merged user
+                diesel::update(stars::table)
+                    .filter(stars::user_id.eq(&merge_user.data.id))
+                    .filter(
+                        stars::document_id.ne_all(
+                            stars::table
+                                .select(stars::document_id)
+                                .filter(stars::user_id.eq(&self.data.id)),
+                        ),
+                    )
+                    .set(stars::user_id.eq(&self.data.id))
+                    .execute(&self.connection.pg_connection)?;
+
+                // presets whose names clash with the surviving user's are
+                // dropped along with the merged user
+                diesel::update(style_presets::table)
+                    .filter(style_presets::user_id.eq(&merge_user.data.id))
+                    .filter(
+                        style_presets::name.ne_all(
+                            style_presets::table
+                                .select(style_presets::name)
+                                .filter(style_presets::user_id.eq(&self.data.id)),
+                        ),
+                    )
+                    .set(style_presets::user_id.eq(&self.data.id))
+                    .execute(&self.connection.pg_connection)?;
+
                 diesel::delete(users)
                     .filter(id.eq(&merge_user.data.id))
                     .execute(&self.connection.pg_connection)?;
@@ -249,6 +322,15 @@ impl<'a> User<'a> {
         Ok(self)
     }
 
+    pub fn set_public_profile(&mut self, p_public_profile: bool) -> QueryResult<&mut User<'a>> {
+        self.data = diesel::update(users)
+            .filter(id.eq(&self.data.id))
+            .set(public_profile.eq(p_public_profile))
+            .get_result::<Data>(&self.connection.pg_connection)?;
+
+        Ok(self)
+    }
+
     pub fn update_display_name(&mut self, new_display_name: &str) -> QueryResult<&mut User<'a>> {
         let new_data = diesel::update(users)
             .filter(id.eq(&self.data.id))
@@ -296,12 +378,13 @@ impl<'a> Serialize for User<'a> {
     where
         S: Serializer,
     {
-        let mut serialized = serializer.serialize_struct("User", 3)?;
+        let mut serialized = serializer.serialize_struct("User", 6)?;
         serialized.serialize_field("user_id", &self.get_id())?;
         serialized.serialize_field("display_name", &self.data.display_name)?;
         serialized.serialize_field("facebook_id", &self.data.facebook_id)?;
         serialized.serialize_field("google_id", &self.data.google_id)?;
         serialized.serialize_field("twitter_screen_name", &self.data.twitter_screen_name)?;
+        serialized.serialize_field("public_profile", &self.data.public_profile)?;
 
         serialized.end()
     }
@@ -0,0 +1,135 @@
+use diesel;
+use diesel::prelude::*;
+
+use data::db::Connection;
+use data::document::DocumentID;
+use data::schema::webhooks;
+use data::schema::webhooks::dsl::*;
+use data::user::UserID;
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use std::time::SystemTime;
+
+use uuid;
+
+#[derive(TaggedID, Serialize, Deserialize)]
+pub struct WebhookID(uuid::Uuid);
+
+pub struct Webhook<'a> {
+    connection: &'a Connection,
+    pub data: Data,
+}
+
+#[derive(Debug, Queryable, Identifiable)]
+#[table_name = "webhooks"]
+pub struct Data {
+    id: uuid::Uuid,
+    document_id: uuid::Uuid,
+    user_id: uuid::Uuid,
+    pub url: String,
+    pub secret: String,
+    pub created_at: SystemTime,
+}
+
+#[derive(Insertable)]
+#[table_name = "webhooks"]
+struct NewWebhook<'a> {
+    document_id: uuid::Uuid,
+    user_id: uuid::Uuid,
+    url: &'a str,
+    secret: &'a str,
+}
+
+impl<'a> Webhook<'a> {
+    pub fn generate_secret() -> String {
+        (0..32)
+            .map(|_| format!("{:02x}", rand::random::<u8>()))
+            .collect()
+    }
+
+    fn results_list(connection: &'a Connection, webhooks_list: Vec<Data>) -> Vec<Self> {
+        webhooks_list
+            .into_iter()
+            .map(|data| Webhook { connection, data })
+            .collect()
+    }
+
+    pub fn get_id(&self) -> WebhookID {
+        WebhookID::from_uuid(self.data.id.clone())
+    }
+
+    pub fn get_document_id(&self) -> DocumentID {
+        DocumentID::from_uuid(self.data.document_id.clone())
+    }
+
+    pub fn get_user_id(&self) -> UserID {
+        UserID::from_uuid(self.data.user_id.clone())
+    }
+
+    pub fn create(
+        connection: &'a Connection,
+        p_document_id: &DocumentID,
+        p_user_id: &UserID,
+        p_url: &str,
+        p_secret: &str,
+    ) -> QueryResult<Self> {
+        let data = diesel::insert_into(webhooks)
+            .values(NewWebhook {
+                document_id: **p_document_id,
+                user_id: **p_user_id,
+                url: p_url,
+                secret: p_secret,
+            })
+            .get_result(&connection.pg_connection)?;
+
+        Ok(Webhook { connection, data })
+    }
+
+    pub fn get_by_id(
+        connection: &'a Connection,
+        p_document_id: &DocumentID,
+        p_webhook_id: &WebhookID,
+    ) -> QueryResult<Self> {
+        let data = webhooks
+            .filter(id.eq(&**p_webhook_id))
+            .filter(document_id.eq(&**p_document_id))
+            .first::<Data>(&connection.pg_connection)?;
+
+        Ok(Webhook { connection, data })
+    }
+
+    pub fn get_by_document(
+        connection: &'a Connection,
+        p_document_id: &DocumentID,
+    ) -> QueryResult<Vec<Self>> {
+        let webhooks_list = webhooks
+            .filter(document_id.eq(&**p_document_id))
+            .order(created_at.asc())
+            .load::<Data>(&connection.pg_connection)?;
+
+        Ok(Self::results_list(connection, webhooks_list))
+    }
+
+    pub fn delete(&mut self) -> QueryResult<usize> {
+        diesel::delete(webhooks)
+            .filter(id.eq(self.data.id))
+            .execute(&self.connection.pg_connection)
+    }
+}
+
+impl<'a> Serialize for Webhook<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut serialized = serializer.serialize_struct("Webhook", 4)?;
+
+        serialized.serialize_field("webhook_id", &self.get_id())?;
+        serialized.serialize_field("document_id", &self.get_document_id())?;
+        serialized.serialize_field("url", &self.data.url)?;
+        serialized.serialize_field("created_at", &self.data.created_at)?;
+
+        serialized.end()
+    }
+}
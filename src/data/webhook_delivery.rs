@@ -0,0 +1,120 @@
+use diesel;
+use diesel::prelude::*;
+
+use data::db::Connection;
+use data::schema::webhook_deliveries;
+use data::schema::webhook_deliveries::dsl::*;
+use data::schema::WebhookEvent;
+use data::webhook::WebhookID;
+
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+use std::time::SystemTime;
+
+use uuid;
+
+#[derive(TaggedID, Serialize, Deserialize)]
+pub struct DeliveryID(uuid::Uuid);
+
+pub struct WebhookDelivery<'a> {
+    connection: &'a Connection,
+    pub data: Data,
+}
+
+#[derive(Debug, Queryable, Identifiable)]
+#[table_name = "webhook_deliveries"]
+pub struct Data {
+    id: uuid::Uuid,
+    webhook_id: uuid::Uuid,
+    delivery_id: uuid::Uuid,
+    pub event: WebhookEvent,
+    pub attempt: i32,
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+    pub succeeded: bool,
+    pub attempted_at: SystemTime,
+}
+
+#[derive(Insertable)]
+#[table_name = "webhook_deliveries"]
+struct NewWebhookDelivery<'a> {
+    webhook_id: uuid::Uuid,
+    delivery_id: uuid::Uuid,
+    event: WebhookEvent,
+    attempt: i32,
+    status_code: Option<i32>,
+    error: Option<&'a str>,
+    succeeded: bool,
+}
+
+impl<'a> WebhookDelivery<'a> {
+    pub const MAX_LISTED: i64 = 100;
+
+    pub fn get_delivery_id(&self) -> DeliveryID {
+        DeliveryID::from_uuid(self.data.delivery_id.clone())
+    }
+
+    pub fn get_webhook_id(&self) -> WebhookID {
+        WebhookID::from_uuid(self.data.webhook_id.clone())
+    }
+
+    pub fn record(
+        connection: &'a Connection,
+        p_webhook_id: &WebhookID,
+        p_delivery_id: &DeliveryID,
+        p_event: WebhookEvent,
+        p_attempt: i32,
+        p_status_code: Option<i32>,
+        p_error: Option<&str>,
+    ) -> QueryResult<Self> {
+        let data = diesel::insert_into(webhook_deliveries)
+            .values(NewWebhookDelivery {
+                webhook_id: **p_webhook_id,
+                delivery_id: **p_delivery_id,
+                event: p_event,
+                attempt: p_attempt,
+                status_code: p_status_code,
+                error: p_error,
+                succeeded: p_error.is_none(),
+            })
+            .get_result(&connection.pg_connection)?;
+
+        Ok(WebhookDelivery { connection, data })
+    }
+
+    pub fn get_by_webhook(
+        connection: &'a Connection,
+        p_webhook_id: &WebhookID,
+    ) -> QueryResult<Vec<Self>> {
+        let deliveries_list = webhook_deliveries
+            .filter(webhook_id.eq(&**p_webhook_id))
+            .order(attempted_at.desc())
+            .limit(Self::MAX_LISTED)
+            .load::<Data>(&connection.pg_connection)?;
+
+        Ok(deliveries_list
+            .into_iter()
+            .map(|data| WebhookDelivery { connection, data })
+            .collect())
+    }
+}
+
+impl<'a> Serialize for WebhookDelivery<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut serialized = serializer.serialize_struct("WebhookDelivery", 8)?;
+
+        serialized.serialize_field("delivery_id", &self.get_delivery_id())?;
+        serialized.serialize_field("webhook_id", &self.get_webhook_id())?;
+        serialized.serialize_field("event", &self.data.event)?;
+        serialized.serialize_field("attempt", &self.data.attempt)?;
+        serialized.serialize_field("status_code", &self.data.status_code)?;
+        serialized.serialize_field("error", &self.data.error)?;
+        serialized.serialize_field("succeeded", &self.data.succeeded)?;
+        serialized.serialize_field("attempted_at", &self.data.attempted_at)?;
+
+        serialized.end()
+    }
+}
@@ -7,6 +7,9 @@ extern crate diesel;
 extern crate diesel_derive_enum;
 extern crate dotenv;
 extern crate hmacsha1;
+extern crate image;
+extern crate multipart;
+extern crate native_tls;
 extern crate r2d2_redis;
 extern crate rand;
 extern crate regex;
@@ -20,16 +23,20 @@ extern crate serde;
 #[macro_use]
 extern crate serde_derive;
 extern crate serde_json;
+extern crate unicode_normalization;
 extern crate url;
 extern crate uuid;
 
 #[macro_use]
 extern crate seriatim_codegen;
 
+mod attachments;
 mod config;
 mod data;
 mod oauth;
+mod render;
 mod routes;
+mod webhooks;
 
 fn main() {
     dotenv::dotenv().ok();
@@ -42,6 +49,22 @@ fn main() {
     let login_routes = routes::login::routes();
     let document_routes = routes::document::routes();
     let user_routes = routes::user::routes();
+    let calendar_routes = routes::calendar::routes();
+    let webhook_routes = routes::webhook::routes();
+    let attachment_routes = routes::attachment::routes();
+    let user_preset_routes = routes::preset::user_routes();
+    let document_preset_routes = routes::preset::document_routes();
+    let user_folder_routes = routes::folder::user_routes();
+    let document_folder_routes = routes::folder::document_routes();
+    let fork_routes = routes::fork::routes();
+    let public_routes = routes::public::routes();
+    let directory_routes = routes::directory::directory_routes();
+    let profile_routes = routes::directory::profile_routes();
+    let feed_routes = routes::feed::routes();
+    let embed_routes = routes::embed::routes();
+    let share_document_routes = routes::share::document_routes();
+    let share_routes = routes::share::share_routes();
+    let lease_routes = routes::lease::routes();
 
     let cors = rocket_cors::CorsOptions::default()
         .allow_credentials(true)
@@ -53,14 +76,34 @@ fn main() {
 
     let db = data::db::init_pool(&cfg);
     let redis = data::memory::redis::init_pool(&cfg).unwrap();
+    let attachments = attachments::init(&cfg).unwrap();
+
+    webhooks::spawn_worker(db.clone(), redis.clone());
 
     rocket::ignite()
         .manage(db)
         .manage(redis)
+        .manage(attachments)
         .manage(cfg)
         .mount("/document", document_routes)
+        .mount("/document", webhook_routes)
+        .mount("/document", document_preset_routes)
+        .mount("/document", attachment_routes)
+        .mount("/document", document_folder_routes)
+        .mount("/document", fork_routes)
         .mount("/login", login_routes)
         .mount("/user", user_routes)
+        .mount("/user", user_preset_routes)
+        .mount("/user", user_folder_routes)
+        .mount("/calendar", calendar_routes)
+        .mount("/", public_routes)
+        .mount("/directory", directory_routes)
+        .mount("/profile", profile_routes)
+        .mount("/feeds", feed_routes)
+        .mount("/", embed_routes)
+        .mount("/document", share_document_routes)
+        .mount("/shared", share_routes)
+        .mount("/document", lease_routes)
         .attach(cors)
         .launch();
 }
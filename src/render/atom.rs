@@ -0,0 +1,64 @@
+use data::sanitize::escape_html;
+
+use render::time::DateTime;
+
+use std::time::SystemTime;
+
+pub struct AtomEntry {
+    pub id: String,
+    pub title: String,
+    pub url: String,
+    pub published: SystemTime,
+    pub updated: SystemTime,
+    // already rendered HTML, which gets escaped into the feed
+    pub content_html: String,
+}
+
+pub struct AtomFeed {
+    pub id: String,
+    pub title: String,
+    pub self_url: String,
+    pub alternate_url: Option<String>,
+    pub author: String,
+    pub updated: SystemTime,
+    pub entries: Vec<AtomEntry>,
+}
+
+fn timestamp(time: &SystemTime) -> String {
+    DateTime::from_system_time(time).to_rfc3339()
+}
+
+fn render_entry(entry: &AtomEntry) -> String {
+    format!(
+        "<entry>\n<id>{}</id>\n<title>{}</title>\n<link rel=\"alternate\" type=\"text/html\" href=\"{}\"/>\n<published>{}</published>\n<updated>{}</updated>\n<content type=\"html\">{}</content>\n</entry>\n",
+        escape_html(&entry.id),
+        escape_html(&entry.title),
+        escape_html(&entry.url),
+        timestamp(&entry.published),
+        timestamp(&entry.updated),
+        escape_html(&entry.content_html),
+    )
+}
+
+pub fn render_feed(feed: &AtomFeed) -> String {
+    let alternate = match feed.alternate_url {
+        Some(ref url) => format!(
+            "<link rel=\"alternate\" type=\"text/html\" href=\"{}\"/>\n",
+            escape_html(url)
+        ),
+        None => String::new(),
+    };
+
+    let entries: String = feed.entries.iter().map(render_entry).collect();
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\">\n<id>{}</id>\n<title>{}</title>\n<link rel=\"self\" type=\"application/atom+xml\" href=\"{}\"/>\n{}<updated>{}</updated>\n<author><name>{}</name></author>\n<generator>Seriatim</generator>\n{}</feed>\n",
+        escape_html(&feed.id),
+        escape_html(&feed.title),
+        escape_html(&feed.self_url),
+        alternate,
+        timestamp(&feed.updated),
+        escape_html(&feed.author),
+        entries,
+    )
+}
@@ -0,0 +1,315 @@
+use data::item::{Item, ItemID};
+use data::numbering::number_outline;
+use data::rich_text::{ItemText, Mark};
+use data::sanitize::escape_html;
+use data::schema::{ItemKind, NumberingScheme, StyleProperty};
+use data::style::ComputedStyle;
+
+use render::time::DateTime;
+
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+const MAX_DESCRIPTION_LENGTH: usize = 160;
+const SITE_NAME: &'static str = "Seriatim";
+
+pub struct PageItem {
+    pub item_id: ItemID,
+    pub number: Option<String>,
+    pub kind: ItemKind,
+    pub code_language: Option<String>,
+    pub style_preset: Option<String>,
+    pub styles: Vec<ComputedStyle>,
+    pub text: ItemText,
+    pub children: Vec<PageItem>,
+}
+
+pub type ItemStyles = HashMap<ItemID, HashMap<StyleProperty, ComputedStyle>>;
+
+pub struct DocumentPage {
+    pub title: String,
+    pub description: String,
+    pub url: String,
+    pub oembed_url: String,
+    pub modified_at: SystemTime,
+    pub items: Vec<PageItem>,
+}
+
+fn build_children(
+    parent_id: &ItemID,
+    children: &HashMap<ItemID, Vec<&Item>>,
+    numbers: &mut HashMap<ItemID, Option<String>>,
+    styles: &mut ItemStyles,
+) -> Vec<PageItem> {
+    children
+        .get(parent_id)
+        .map(|siblings| {
+            siblings
+                .iter()
+                .map(|item| PageItem {
+                    item_id: item.get_id(),
+                    number: numbers.remove(&item.get_id()).unwrap_or(None),
+                    kind: item.data.kind,
+                    code_language: item.data.code_language.clone(),
+                    style_preset: item.data.style_preset.clone(),
+                    styles: styles
+                        .remove(&item.get_id())
+                        .map(|s| s.into_iter().map(|(_, style)| style).collect())
+                        .unwrap_or_default(),
+                    text: item.get_text(),
+                    children: build_children(&item.get_id(), children, numbers, styles),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// arranges a document's items into the tree below its root, in order
+pub fn build_outline(
+    items: &[Item],
+    root_id: &ItemID,
+    toc_id: Option<&ItemID>,
+    scheme: Option<NumberingScheme>,
+    mut styles: ItemStyles,
+) -> Vec<PageItem> {
+    let mut numbers = number_outline(items.iter(), root_id, toc_id, scheme)
+        .into_iter()
+        .map(|(item_id, position)| (item_id, position.number))
+        .collect();
+
+    let mut children = HashMap::<ItemID, Vec<&Item>>::new();
+    for item in items.iter() {
+        if let Some(pid) = item.get_parent_id() {
+            children.entry(pid).or_insert(Vec::new()).push(item);
+        }
+    }
+
+    for siblings in children.values_mut() {
+        siblings.sort_by_key(|i| i.data.child_order);
+    }
+
+    build_children(root_id, &children, &mut numbers, &mut styles)
+}
+
+pub fn find_subtree<'p>(items: &'p [PageItem], item_id: &ItemID) -> Option<&'p PageItem> {
+    for item in items.iter() {
+        if item.item_id == *item_id {
+            return Some(item);
+        }
+
+        if let Some(found) = find_subtree(&item.children, item_id) {
+            return Some(found);
+        }
+    }
+
+    None
+}
+
+// the first non-empty paragraph stands in for a summary of the document
+pub fn describe_outline(items: &[PageItem]) -> Option<String> {
+    for item in items.iter() {
+        let text = item.text.to_plain_text();
+
+        if item.kind == ItemKind::Paragraph && text.trim() != "" {
+            return Some(truncate(text.trim(), MAX_DESCRIPTION_LENGTH));
+        }
+
+        if let Some(description) = describe_outline(&item.children) {
+            return Some(description);
+        }
+    }
+
+    None
+}
+
+pub fn truncate(text: &str, max_length: usize) -> String {
+    if text.chars().count() <= max_length {
+        return text.to_string();
+    }
+
+    let truncated: String = text.chars().take(max_length - 1).collect();
+    truncated.trim_end().to_string() + "\u{2026}"
+}
+
+fn mark_tag(mark: Mark) -> &'static str {
+    match mark {
+        Mark::Bold => "strong",
+        Mark::Italic => "em",
+        Mark::Underline => "u",
+        Mark::Strikethrough => "s",
+        Mark::Code => "code",
+    }
+}
+
+pub fn render_text(text: &ItemText) -> String {
+    match text {
+        ItemText::Plain(plain) => escape_html(plain),
+        ItemText::Rich(spans) => spans
+            .iter()
+            .map(|span| {
+                let mut html = escape_html(&span.text);
+
+                for mark in span.marks.iter().rev() {
+                    let tag = mark_tag(*mark);
+                    html = format!("<{}>{}</{}>", tag, html, tag);
+                }
+
+                // links come from users, so search engines shouldn't credit them
+                match span.link {
+                    Some(ref link) => format!(
+                        "<a href=\"{}\" rel=\"nofollow ugc noopener\">{}</a>",
+                        escape_html(link),
+                        html
+                    ),
+                    None => html,
+                }
+            })
+            .collect(),
+    }
+}
+
+// presets become classes for themes to hook into, while the computed styles
+// already include what the preset sets
+fn block_attributes(item: &PageItem) -> String {
+    let mut attributes = String::new();
+
+    if let Some(ref preset) = item.style_preset {
+        let class: String = preset
+            .to_lowercase()
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '-' })
+            .collect();
+        attributes.push_str(&format!(" class=\"preset-{}\"", escape_html(&class)));
+    }
+
+    let mut declarations: Vec<String> = item.styles.iter().filter_map(|s| s.to_css()).collect();
+    if !declarations.is_empty() {
+        declarations.sort();
+        attributes.push_str(&format!(
+            " style=\"{}\"",
+            escape_html(&declarations.join("; "))
+        ));
+    }
+
+    attributes
+}
+
+fn render_block(item: &PageItem, depth: usize) -> String {
+    let attributes = block_attributes(item);
+    let number = match item.number {
+        Some(ref n) => format!("<span class=\"number\">{}</span> ", escape_html(n)),
+        None => String::new(),
+    };
+    let text = render_text(&item.text);
+
+    match item.kind {
+        ItemKind::Paragraph => format!("<p{}>{}{}</p>", attributes, number, text),
+        ItemKind::Heading => {
+            // the document title is the page's only h1
+            let level = (depth + 1).min(6);
+            format!("<h{}{}>{}{}</h{}>", level, attributes, number, text, level)
+        }
+        ItemKind::Quote => format!("<blockquote{}>{}{}</blockquote>", attributes, number, text),
+        ItemKind::Code => format!(
+            "<pre{}><code{}>{}</code></pre>",
+            attributes,
+            match item.code_language {
+                Some(ref language) => format!(" class=\"language-{}\"", escape_html(language)),
+                None => String::new(),
+            },
+            escape_html(&item.text.to_plain_text())
+        ),
+        ItemKind::Divider => format!("<hr{}>", attributes),
+    }
+}
+
+pub fn render_outline(items: &[PageItem], depth: usize) -> String {
+    if items.is_empty() {
+        return String::new();
+    }
+
+    let rendered: String = items
+        .iter()
+        .map(|item| {
+            format!(
+                "<li>{}{}</li>",
+                render_block(item, depth),
+                render_outline(&item.children, depth + 1)
+            )
+        })
+        .collect();
+
+    format!("<ul>{}</ul>", rendered)
+}
+
+// only the top-level items, without anything nested below them
+pub fn render_excerpt(items: &[PageItem], max_items: usize) -> String {
+    if items.is_empty() {
+        return String::new();
+    }
+
+    let rendered: String = items
+        .iter()
+        .take(max_items)
+        .map(|item| format!("<li>{}</li>", render_block(item, 1)))
+        .collect();
+
+    format!("<ul>{}</ul>", rendered)
+}
+
+fn meta_tag(attribute: &str, key: &str, value: &str) -> String {
+    format!(
+        "<meta {}=\"{}\" content=\"{}\">\n",
+        attribute,
+        key,
+        escape_html(value)
+    )
+}
+
+pub fn render_document_page(page: &DocumentPage) -> String {
+    let modified = DateTime::from_system_time(&page.modified_at).to_rfc3339();
+
+    let mut head = String::new();
+    head.push_str("<meta charset=\"utf-8\">\n");
+    head.push_str("<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n");
+    head.push_str(&format!("<title>{}</title>\n", escape_html(&page.title)));
+    head.push_str(&meta_tag("name", "description", &page.description));
+    head.push_str(&format!(
+        "<link rel=\"canonical\" href=\"{}\">\n",
+        escape_html(&page.url)
+    ));
+    head.push_str(&meta_tag("property", "og:type", "article"));
+    head.push_str(&meta_tag("property", "og:site_name", SITE_NAME));
+    head.push_str(&meta_tag("property", "og:title", &page.title));
+    head.push_str(&meta_tag("property", "og:description", &page.description));
+    head.push_str(&meta_tag("property", "og:url", &page.url));
+    head.push_str(&meta_tag("property", "article:modified_time", &modified));
+    head.push_str(&meta_tag("name", "twitter:card", "summary"));
+    head.push_str(&format!(
+        "<link rel=\"alternate\" type=\"application/json+oembed\" href=\"{}\" title=\"{}\">\n",
+        escape_html(&page.oembed_url),
+        escape_html(&page.title)
+    ));
+
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n{}</head>\n<body>\n<article>\n<h1>{}</h1>\n{}\n<footer><time datetime=\"{}\">{}</time></footer>\n</article>\n</body>\n</html>\n",
+        head,
+        escape_html(&page.title),
+        render_outline(&page.items, 1),
+        modified,
+        DateTime::from_system_time(&page.modified_at).to_date_string(),
+    )
+}
+
+// a bare page meant to be framed by other sites, so it stays out of search results
+pub fn render_embed_page(page: &DocumentPage, items: &[PageItem]) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"robots\" content=\"noindex\">\n<title>{}</title>\n<link rel=\"canonical\" href=\"{}\">\n<base target=\"_blank\">\n</head>\n<body>\n<article>\n{}\n<footer><a href=\"{}\">{}</a> on {}</footer>\n</article>\n</body>\n</html>\n",
+        escape_html(&page.title),
+        escape_html(&page.url),
+        render_outline(items, 1),
+        escape_html(&page.url),
+        escape_html(&page.title),
+        SITE_NAME,
+    )
+}
@@ -0,0 +1,105 @@
+use render::time::DateTime;
+
+use std::time::SystemTime;
+
+const MAX_LINE_OCTETS: usize = 75;
+
+pub enum EntryKind {
+    Event,
+    Todo { completed: bool, cancelled: bool },
+}
+
+pub struct CalendarEntry {
+    pub uid: String,
+    pub summary: String,
+    pub description: String,
+    pub url: String,
+    pub due: SystemTime,
+    pub kind: EntryKind,
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+        .replace('\r', "")
+}
+
+// content lines longer than 75 octets must be folded onto continuation lines
+fn fold_line(line: &str) -> String {
+    let mut folded = String::new();
+    let mut line_octets = 0;
+
+    for c in line.chars() {
+        if line_octets + c.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            line_octets = 1;
+        }
+
+        folded.push(c);
+        line_octets += c.len_utf8();
+    }
+
+    folded + "\r\n"
+}
+
+fn render_entry(entry: &CalendarEntry, stamp: &str, out: &mut Vec<String>) {
+    let due = DateTime::from_system_time(&entry.due).to_ical();
+
+    match entry.kind {
+        EntryKind::Event => {
+            out.push("BEGIN:VEVENT".to_string());
+            out.push(format!("DTSTART:{}", due));
+        }
+        EntryKind::Todo {
+            completed,
+            cancelled,
+        } => {
+            out.push("BEGIN:VTODO".to_string());
+            out.push(format!("DUE:{}", due));
+            out.push(format!(
+                "STATUS:{}",
+                if cancelled {
+                    "CANCELLED"
+                } else if completed {
+                    "COMPLETED"
+                } else {
+                    "NEEDS-ACTION"
+                }
+            ));
+        }
+    }
+
+    out.push(format!("UID:{}", entry.uid));
+    out.push(format!("DTSTAMP:{}", stamp));
+    out.push(format!("SUMMARY:{}", escape_text(&entry.summary)));
+    out.push(format!("DESCRIPTION:{}", escape_text(&entry.description)));
+    out.push(format!("URL:{}", entry.url));
+
+    out.push(match entry.kind {
+        EntryKind::Event => "END:VEVENT".to_string(),
+        EntryKind::Todo { .. } => "END:VTODO".to_string(),
+    });
+}
+
+pub fn render_calendar(name: &str, entries: &Vec<CalendarEntry>) -> String {
+    let stamp = DateTime::from_system_time(&SystemTime::now()).to_ical();
+
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//Seriatim//Seriatim Server//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        format!("X-WR-CALNAME:{}", escape_text(name)),
+    ];
+
+    for entry in entries.iter() {
+        render_entry(entry, &stamp, &mut lines);
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.iter().map(|l| fold_line(l)).collect()
+}
@@ -0,0 +1,5 @@
+pub mod atom;
+pub mod html;
+pub mod ical;
+pub mod sitemap;
+pub mod time;
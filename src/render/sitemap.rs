@@ -0,0 +1,28 @@
+use data::sanitize::escape_html;
+
+use render::time::DateTime;
+
+use std::time::SystemTime;
+
+pub struct SitemapEntry {
+    pub url: String,
+    pub last_modified: SystemTime,
+}
+
+pub fn render_sitemap(entries: &[SitemapEntry]) -> String {
+    let urls: String = entries
+        .iter()
+        .map(|e| {
+            format!(
+                "<url><loc>{}</loc><lastmod>{}</lastmod></url>\n",
+                escape_html(&e.url),
+                DateTime::from_system_time(&e.last_modified).to_rfc3339()
+            )
+        })
+        .collect();
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n{}</urlset>\n",
+        urls
+    )
+}
@@ -0,0 +1,303 @@
+use attachments::{is_image, make_thumbnail, sanitize_file_name, sniff_content_type, Attachments};
+
+use data::attachment::{Attachment, AttachmentFile, AttachmentID};
+use data::db::Connection;
+use data::document::{Document, DocumentID};
+use data::item::ItemID;
+use data::memory;
+use data::memory::session::Session;
+
+use diesel;
+
+use multipart::server::Multipart;
+
+use rocket;
+use rocket::http::ContentType;
+use rocket::response::Response;
+use rocket::{Data, Route, State};
+
+use routes::document::check_unlocked;
+use routes::error::Error;
+use routes::io::{cors_response, send_success, SeriatimResult};
+use routes::lease::check_lease;
+
+use std::io::{Cursor, Read};
+
+const FILE_FIELD: &'static str = "file";
+
+struct Upload {
+    file_name: String,
+    bytes: Vec<u8>,
+}
+
+// reads the "file" field of a multipart body, refusing anything over the limit
+fn read_upload(content_type: &ContentType, data: Data, max_size: u64) -> Result<Upload, Error> {
+    if !content_type.is_form_data() {
+        return Err(Error::InvalidUpload);
+    }
+
+    let boundary = content_type
+        .params()
+        .find(|&(k, _)| k == "boundary")
+        .map(|(_, v)| v.to_string())
+        .ok_or(Error::InvalidUpload)?;
+
+    let mut multipart = Multipart::with_body(data.open(), boundary);
+
+    while let Some(mut field) = multipart.read_entry()? {
+        if &*field.headers.name != FILE_FIELD {
+            continue;
+        }
+
+        let file_name = sanitize_file_name(&field.headers.filename.clone().unwrap_or_default());
+
+        let mut bytes = Vec::new();
+        field
+            .data
+            .by_ref()
+            .take(max_size + 1)
+            .read_to_end(&mut bytes)?;
+
+        if bytes.len() as u64 > max_size {
+            return Err(Error::AttachmentTooLarge(max_size));
+        }
+
+        return Ok(Upload { file_name, bytes });
+    }
+
+    Err(Error::InvalidUpload)
+}
+
+fn send_file<'r>(
+    content_type: &str,
+    file_name: &str,
+    bytes: Vec<u8>,
+) -> Result<Response<'r>, Error> {
+    let parsed_type = ContentType::parse_flexible(content_type).ok_or(Error::InvalidUpload)?;
+
+    // only images are safe to show inline; everything else is downloaded
+    let disposition = if is_image(content_type) {
+        "inline"
+    } else {
+        "attachment"
+    };
+
+    // header values must be plain ASCII
+    let header_file_name: String = file_name
+        .chars()
+        .map(|c| {
+            if c.is_ascii() && !c.is_ascii_control() {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    Ok(Response::build()
+        .header(parsed_type)
+        .raw_header(
+            "Content-Disposition",
+            format!("{}; filename=\"{}\"", disposition, header_file_name),
+        )
+        .raw_header("X-Content-Type-Options", "nosniff")
+        .sized_body(Cursor::new(bytes))
+        .finalize())
+}
+
+fn get_viewable_document<'a>(
+    connection: &'a Connection,
+    doc_id: &DocumentID,
+    session: &Session,
+) -> Result<Document<'a>, Error> {
+    let doc = Document::get_by_id(connection, doc_id)?;
+
+    if doc.can_be_viewed_by(&session.data.user_id) {
+        Ok(doc)
+    } else {
+        Err(Error::InsufficientPermissions)
+    }
+}
+
+fn get_editable_document<'a>(
+    connection: &'a Connection,
+    doc_id: &DocumentID,
+    session: &Session,
+) -> Result<Document<'a>, Error> {
+    let doc = Document::get_by_id(connection, doc_id)?;
+
+    if doc.can_be_edited_by(&session.data.user_id) {
+        Ok(doc)
+    } else {
+        Err(Error::InsufficientPermissions)
+    }
+}
+
+#[options("/<_doc_id>/items/<_item_id>/attachments")]
+fn item_attachments_options<'a>(
+    _doc_id: DocumentID,
+    _item_id: ItemID,
+) -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+#[get("/<doc_id>/items/<item_id>/attachments")]
+fn list_item_attachments(
+    doc_id: DocumentID,
+    item_id: ItemID,
+    connection: Connection,
+    session: Session,
+) -> SeriatimResult {
+    get_viewable_document(&connection, &doc_id, &session)?;
+
+    Ok(send_success(&Attachment::get_by_item(
+        &connection,
+        &doc_id,
+        &item_id,
+    )?))
+}
+
+#[post("/<doc_id>/items/<item_id>/attachments", data = "<data>")]
+fn upload_attachment(
+    doc_id: DocumentID,
+    item_id: ItemID,
+    content_type: &ContentType,
+    data: Data,
+    connection: Connection,
+    redis: memory::redis::Connection,
+    session: Session,
+    attachments: State<Attachments>,
+) -> SeriatimResult {
+    let mut doc = get_editable_document(&connection, &doc_id, &session)?;
+    check_lease(&redis, &doc_id, &session)?;
+    check_unlocked(&doc, &[item_id.clone()], false)?;
+    let item = doc.get_item(&item_id)?;
+
+    let upload = read_upload(content_type, data, attachments.max_size)?;
+    let file_content_type =
+        sniff_content_type(&upload.bytes).ok_or(Error::UnsupportedAttachmentType)?;
+
+    let attachment_id = Attachment::new_id();
+    attachments
+        .store
+        .put(&Attachment::storage_key_for(&attachment_id), &upload.bytes)?;
+
+    let thumbnail = if is_image(file_content_type) {
+        make_thumbnail(&upload.bytes)
+    } else {
+        None
+    };
+
+    if let Some(ref thumbnail_bytes) = thumbnail {
+        attachments.store.put(
+            &Attachment::thumbnail_key_for(&attachment_id),
+            thumbnail_bytes,
+        )?;
+    }
+
+    let attachment = Attachment::create(
+        &connection,
+        &attachment_id,
+        &item,
+        &session.data.user_id,
+        &AttachmentFile {
+            file_name: &upload.file_name,
+            content_type: file_content_type,
+            size_bytes: upload.bytes.len() as i64,
+            has_thumbnail: thumbnail.is_some(),
+        },
+    )?;
+
+    doc.touch()?;
+
+    Ok(send_success(&attachment))
+}
+
+#[get("/<doc_id>/attachments/<attachment_id>")]
+fn download_attachment<'r>(
+    doc_id: DocumentID,
+    attachment_id: AttachmentID,
+    connection: Connection,
+    session: Session,
+    attachments: State<Attachments>,
+) -> Result<Response<'r>, Error> {
+    get_viewable_document(&connection, &doc_id, &session)?;
+
+    let attachment = Attachment::get_by_id(&connection, &doc_id, &attachment_id)?;
+    let bytes = attachments.store.get(&attachment.data.storage_key)?;
+
+    send_file(
+        &attachment.data.content_type,
+        &attachment.data.file_name,
+        bytes,
+    )
+}
+
+#[get("/<doc_id>/attachments/<attachment_id>/thumbnail")]
+fn download_thumbnail<'r>(
+    doc_id: DocumentID,
+    attachment_id: AttachmentID,
+    connection: Connection,
+    session: Session,
+    attachments: State<Attachments>,
+) -> Result<Response<'r>, Error> {
+    get_viewable_document(&connection, &doc_id, &session)?;
+
+    let attachment = Attachment::get_by_id(&connection, &doc_id, &attachment_id)?;
+    let thumbnail_key = attachment
+        .data
+        .thumbnail_key
+        .as_ref()
+        .ok_or(diesel::result::Error::NotFound)?;
+
+    send_file(
+        "image/png",
+        &attachment.data.file_name,
+        attachments.store.get(thumbnail_key)?,
+    )
+}
+
+#[options("/<_doc_id>/attachments/<_attachment_id>")]
+fn attachment_options<'a>(
+    _doc_id: DocumentID,
+    _attachment_id: AttachmentID,
+) -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+#[delete("/<doc_id>/attachments/<attachment_id>")]
+fn delete_attachment(
+    doc_id: DocumentID,
+    attachment_id: AttachmentID,
+    connection: Connection,
+    redis: memory::redis::Connection,
+    session: Session,
+    attachments: State<Attachments>,
+) -> SeriatimResult {
+    let mut doc = get_editable_document(&connection, &doc_id, &session)?;
+
+    check_lease(&redis, &doc_id, &session)?;
+
+    let mut attachment = Attachment::get_by_id(&connection, &doc_id, &attachment_id)?;
+    if let Some(item_id) = attachment.get_item_id() {
+        check_unlocked(&doc, &[item_id], false)?;
+    }
+    attachment.delete()?;
+    attachments.delete_files(&attachment);
+
+    doc.touch()?;
+
+    Ok(send_success(&attachment))
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![
+        item_attachments_options,
+        list_item_attachments,
+        upload_attachment,
+        download_attachment,
+        download_thumbnail,
+        attachment_options,
+        delete_attachment,
+    ]
+}
@@ -0,0 +1,144 @@
+use config::SeriatimConfig;
+
+use data::calendar::{CalendarToken, CalendarTokenID};
+use data::db::Connection;
+use data::document::{Document, DocumentID};
+use data::item::{Item, ItemID};
+use data::numbering::number_outline;
+use data::schema::TaskState;
+use data::user::User;
+
+use render::ical::{render_calendar, CalendarEntry, EntryKind};
+
+use rocket::http::ContentType;
+use rocket::response::content::Content;
+use rocket::{Route, State};
+
+use routes::error::Error;
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+const FEED_EXTENSION: &'static str = ".ics";
+
+struct DocumentOutline {
+    title: String,
+    parents: HashMap<ItemID, (Option<ItemID>, String)>,
+    numbers: HashMap<ItemID, String>,
+}
+
+impl DocumentOutline {
+    fn load(connection: &Connection, doc_id: &DocumentID) -> Result<DocumentOutline, Error> {
+        let doc = Document::get_by_id(connection, doc_id)?;
+        let items = doc.get_items()?;
+
+        let numbers = number_outline(
+            items.iter(),
+            &doc.get_root()?.get_id(),
+            doc.get_serialized_toc_id().as_ref(),
+            doc.data.numbering_scheme,
+        )
+        .into_iter()
+        .filter_map(|(item_id, position)| position.number.map(|n| (item_id, n)))
+        .collect();
+
+        let parents = items
+            .into_iter()
+            .map(|i| (i.get_id(), (i.get_parent_id(), i.data.item_text)))
+            .collect();
+
+        let title = doc.get_title()?;
+
+        Ok(DocumentOutline {
+            title: if title == "" {
+                "Untitled Document".to_string()
+            } else {
+                title
+            },
+            parents,
+            numbers,
+        })
+    }
+
+    fn get_label(&self, item_id: &ItemID, text: &str) -> String {
+        match self.numbers.get(item_id) {
+            Some(number) => number.clone() + " " + text,
+            None => text.to_string(),
+        }
+    }
+
+    // the root item holds the document title, so it is left out of the path
+    fn get_path(&self, item_id: &ItemID) -> String {
+        let mut path = Vec::new();
+        let mut curr = self.parents.get(item_id).and_then(|p| p.0.clone());
+
+        while let Some(curr_id) = curr {
+            match self.parents.get(&curr_id) {
+                Some((Some(parent_id), text)) => {
+                    path.push(self.get_label(&curr_id, text));
+                    curr = Some(parent_id.clone());
+                }
+                _ => curr = None,
+            }
+        }
+
+        path.push(self.title.clone());
+        path.reverse();
+        path.join(" > ")
+    }
+}
+
+fn calendar_entry(item: &Item, outline: &DocumentOutline, cfg: &SeriatimConfig) -> CalendarEntry {
+    let url = cfg.client.clone() + "document/" + &item.data.document_id.hyphenated().to_string();
+
+    CalendarEntry {
+        uid: item.get_id().json_str() + "@seriatim",
+        summary: outline.get_label(&item.get_id(), &item.data.item_text),
+        description: outline.get_path(&item.get_id()) + "\n" + &url,
+        url,
+        due: item.data.due_at.unwrap_or_else(std::time::SystemTime::now),
+        kind: match item.data.task_state {
+            Some(state) => EntryKind::Todo {
+                completed: state == TaskState::Done,
+                cancelled: state == TaskState::Cancelled,
+            },
+            None => EntryKind::Event,
+        },
+    }
+}
+
+#[get("/<feed>")]
+fn calendar_feed(
+    feed: String,
+    connection: Connection,
+    cfg: State<SeriatimConfig>,
+) -> Result<Content<String>, Error> {
+    let token_id = CalendarTokenID::from_str(feed.trim_end_matches(FEED_EXTENSION))?;
+    let token = CalendarToken::get_by_id(&connection, &token_id)?;
+    let u = User::get_by_id(&connection, &token.get_user_id())?;
+
+    let mut outlines = HashMap::<DocumentID, DocumentOutline>::new();
+    let mut entries = Vec::new();
+
+    for item in Item::get_dated_for_user(&connection, &u.get_id())?.iter() {
+        let doc_id = DocumentID::from_uuid(item.data.document_id);
+
+        if !outlines.contains_key(&doc_id) {
+            let outline = DocumentOutline::load(&connection, &doc_id)?;
+            outlines.insert(doc_id.clone(), outline);
+        }
+
+        if let Some(outline) = outlines.get(&doc_id) {
+            entries.push(calendar_entry(item, outline, &cfg));
+        }
+    }
+
+    Ok(Content(
+        ContentType::new("text", "calendar"),
+        render_calendar(&format!("Seriatim - {}", u.data.display_name), &entries),
+    ))
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![calendar_feed]
+}
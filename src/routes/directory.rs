@@ -0,0 +1,186 @@
+use data::db::Connection;
+use data::document::{Document, DocumentID};
+use data::memory::session::Session;
+use data::star::Star;
+use data::user::{User, UserID};
+
+use diesel;
+
+use rocket;
+use rocket::Route;
+
+use routes::error::Error;
+use routes::io::{cors_response, send_success, SeriatimResult};
+
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+const DEFAULT_PAGE_SIZE: i64 = 20;
+const MAX_PAGE_SIZE: i64 = 100;
+
+#[derive(Clone, Serialize)]
+struct DirectoryOwner {
+    user_id: UserID,
+    display_name: String,
+}
+
+#[derive(Serialize)]
+struct DirectoryEntry {
+    document_id: DocumentID,
+    title: String,
+    created_at: SystemTime,
+    modified_at: SystemTime,
+    owner: Option<DirectoryOwner>,
+    stars: i64,
+    starred: bool,
+}
+
+#[derive(Serialize)]
+struct DirectoryPage {
+    documents: Vec<DirectoryEntry>,
+    page: i64,
+    per_page: i64,
+    total: i64,
+}
+
+#[derive(Serialize)]
+struct Profile {
+    user_id: UserID,
+    display_name: String,
+    documents: Vec<DirectoryEntry>,
+}
+
+fn directory_entries(
+    connection: &Connection,
+    docs: &[Document],
+    session: Option<&Session>,
+) -> Result<Vec<DirectoryEntry>, Error> {
+    let doc_ids: Vec<DocumentID> = docs.iter().map(|d| d.get_id()).collect();
+    let star_counts = Star::count_by_documents(connection, &doc_ids)?;
+    let starred = match session {
+        Some(s) => Star::get_starred_by_user(connection, &s.data.user_id, &doc_ids)?,
+        None => Vec::new(),
+    };
+
+    let mut owners = HashMap::<UserID, Option<DirectoryOwner>>::new();
+    let mut entries = Vec::new();
+
+    for doc in docs.iter() {
+        let owner_id = doc.get_owner_id();
+
+        if !owners.contains_key(&owner_id) {
+            let owner = User::get_by_id(connection, &owner_id)?;
+            owners.insert(
+                owner_id.clone(),
+                owner.public_name().map(|name| DirectoryOwner {
+                    user_id: owner.get_id(),
+                    display_name: name,
+                }),
+            );
+        }
+
+        let doc_id = doc.get_id();
+        entries.push(DirectoryEntry {
+            title: doc.get_title()?,
+            created_at: doc.data.created_at,
+            modified_at: doc.last_modified(),
+            owner: owners.get(&owner_id).cloned().unwrap_or(None),
+            stars: *star_counts.get(&doc_id).unwrap_or(&0),
+            starred: starred.contains(&doc_id),
+            document_id: doc_id,
+        });
+    }
+
+    Ok(entries)
+}
+
+#[get("/?<q>&<page>&<per_page>")]
+fn list_directory(
+    q: Option<String>,
+    page: Option<i64>,
+    per_page: Option<i64>,
+    connection: Connection,
+    session: Option<Session>,
+) -> SeriatimResult {
+    let page = page.unwrap_or(0).max(0);
+    let per_page = per_page
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .max(1)
+        .min(MAX_PAGE_SIZE);
+    let search = q.as_ref().map(|s| s.as_str());
+
+    let docs = Document::search_public(&connection, search, page, per_page)?;
+
+    Ok(send_success(&DirectoryPage {
+        documents: directory_entries(&connection, &docs, session.as_ref())?,
+        page,
+        per_page,
+        total: Document::count_public(&connection, search)?,
+    }))
+}
+
+#[options("/<_doc_id>/star")]
+fn star_options<'a>(_doc_id: DocumentID) -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+#[post("/<doc_id>/star")]
+fn star_document(doc_id: DocumentID, connection: Connection, session: Session) -> SeriatimResult {
+    let doc = Document::get_by_id(&connection, &doc_id)?;
+
+    if !doc.can_be_viewed_anonymously() {
+        return Err(Error::InsufficientPermissions);
+    }
+
+    Star::add(&connection, &doc_id, &session.data.user_id)?;
+
+    Ok(send_success(
+        &directory_entries(&connection, &[doc], Some(&session))?.pop(),
+    ))
+}
+
+#[delete("/<doc_id>/star")]
+fn unstar_document(doc_id: DocumentID, connection: Connection, session: Session) -> SeriatimResult {
+    let doc = Document::get_by_id(&connection, &doc_id)?;
+    Star::remove(&connection, &doc_id, &session.data.user_id)?;
+
+    // stars can still be taken back from documents that have since been made
+    // private, but nothing about those documents is shown
+    if !doc.can_be_viewed_anonymously() {
+        return Ok(send_success(&None::<DirectoryEntry>));
+    }
+
+    Ok(send_success(
+        &directory_entries(&connection, &[doc], Some(&session))?.pop(),
+    ))
+}
+
+#[get("/<user_id>")]
+fn get_profile(
+    user_id: UserID,
+    connection: Connection,
+    session: Option<Session>,
+) -> SeriatimResult {
+    let u = User::get_by_id(&connection, &user_id)?;
+
+    // a private profile looks the same as one that doesn't exist
+    if !u.data.public_profile {
+        return Err(Error::from(diesel::result::Error::NotFound));
+    }
+
+    let docs = Document::get_public_by_user(&connection, &user_id)?;
+
+    Ok(send_success(&Profile {
+        user_id: u.get_id(),
+        display_name: u.data.display_name.clone(),
+        documents: directory_entries(&connection, &docs, session.as_ref())?,
+    }))
+}
+
+pub fn directory_routes() -> Vec<Route> {
+    routes![list_directory, star_options, star_document, unstar_document]
+}
+
+pub fn profile_routes() -> Vec<Route> {
+    routes![get_profile]
+}
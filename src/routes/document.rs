@@ -1,23 +1,45 @@
+use attachments::Attachments;
+
+use data::activity::Activity;
+use data::attachment::{Attachment, AttachmentID};
 use data::category::Category;
 use data::db::Connection;
 use data::document::{Document, DocumentID};
-use data::item::ItemID;
+use data::document_style::DocumentStyle;
+use data::item::{is_valid_code_language, Item, ItemID};
+use data::memory;
 use data::memory::session::Session;
-use data::schema::{StyleProperty, StyleUnit};
+use data::rich_text::ItemText;
+use data::sanitize::sanitize_text;
+use data::schema::{
+    ActivityEvent, ItemKind, NumberingScheme, StyleProperty, StyleUnit, TaskState, TocGeneration,
+    WebhookEvent,
+};
+use data::share::SubtreeShare;
+use data::style::{validate_style, Style};
+use data::style_preset::StylePreset;
+use data::toc;
 use data::user;
 
 use diesel::result::QueryResult;
 
 use routes::error::Error;
 use routes::io::{cors_response, send_success, send_with_permissions, SeriatimResult};
+use routes::lease::check_lease;
+use routes::preset::check_preset_reference;
 
 use rocket;
-use rocket::Route;
+use rocket::{Route, State};
 use rocket_contrib::json::Json;
 
-use std::collections::HashMap;
+use serde_json;
+
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::time::SystemTime;
+
+use webhooks::enqueue_event;
 
 #[derive(Serialize)]
 struct DocumentPermissions {
@@ -28,6 +50,13 @@ struct DocumentPermissions {
 fn create_document(connection: Connection, session: Session) -> SeriatimResult {
     let u = user::User::get_by_id(&connection, &session.data.user_id)?;
     let doc = u.create_document()?;
+    Activity::record(
+        &connection,
+        &doc.get_id(),
+        &session.data.user_id,
+        ActivityEvent::Create,
+        "Created the document",
+    )?;
 
     Ok(send_success(
         &doc.serializable(Some(&session.data.user_id))?,
@@ -40,15 +69,66 @@ fn delete_options<'a>(_doc_id: DocumentID) -> rocket::response::Response<'a> {
 }
 
 #[delete("/<doc_id>")]
-fn delete_document(doc_id: DocumentID, connection: Connection, session: Session) -> SeriatimResult {
+fn delete_document(
+    doc_id: DocumentID,
+    connection: Connection,
+    redis: memory::redis::Connection,
+    session: Session,
+    attachments: State<Attachments>,
+) -> SeriatimResult {
     let mut doc = Document::get_by_id(&connection, &doc_id)?;
     let user_id = &session.data.user_id;
 
     if !doc.is_trashed(user_id)? && doc.can_be_viewed_by(user_id) {
+        // only the owner's trash is reported as a change to the document, so
+        // only it has to wait for the lease
+        if doc.is_owned_by(user_id) {
+            check_lease(&redis, &doc_id, &session)?;
+        }
+
         Category::create(&connection, &doc_id, user_id, Category::TRASH)?;
+
+        // the trash is per user, so only the owner trashing it belongs in the
+        // document's log or its webhooks
+        if doc.is_owned_by(user_id) {
+            Activity::record(
+                &connection,
+                &doc_id,
+                user_id,
+                ActivityEvent::Trash,
+                "Moved the document to the trash",
+            )?;
+            enqueue_event(
+                &connection,
+                &redis,
+                &doc_id,
+                user_id,
+                WebhookEvent::Trash,
+                json!({}),
+            );
+        }
         Ok(send_success(&doc.serializable(Some(user_id))?))
     } else if doc.is_owned_by(user_id) {
+        check_lease(&redis, &doc_id, &session)?;
+
+        // webhooks are removed along with the document, so queue first
+        enqueue_event(
+            &connection,
+            &redis,
+            &doc_id,
+            user_id,
+            WebhookEvent::Delete,
+            json!({}),
+        );
+
+        let doc_attachments = Attachment::get_by_document(&connection, &doc_id)?;
         doc.delete()?;
+
+        // attachment rows go with the document, but their files do not
+        for a in doc_attachments.iter() {
+            attachments.delete_files(a);
+        }
+
         Ok(send_success(&doc.serializable(Some(user_id))?))
     } else {
         Err(Error::InsufficientPermissions)
@@ -70,6 +150,7 @@ fn rename_document(
     doc_id: DocumentID,
     rename: Json<RenameDocumentParams>,
     connection: Connection,
+    redis: memory::redis::Connection,
     session: Session,
 ) -> SeriatimResult {
     let mut doc = Document::get_by_id(&connection, &doc_id)?;
@@ -77,7 +158,29 @@ fn rename_document(
         return Err(Error::InsufficientPermissions);
     }
 
-    doc.rename(&rename.0.name)?;
+    check_lease(&redis, &doc_id, &session)?;
+
+    // the title is the root item's text
+    check_unlocked(&doc, &[doc.get_root()?.get_id()], false)?;
+
+    let name = sanitize_text(&rename.0.name)?;
+
+    doc.rename(&name)?;
+    Activity::record(
+        &connection,
+        &doc_id,
+        &session.data.user_id,
+        ActivityEvent::Rename,
+        &format!("Renamed the document to \"{}\"", name),
+    )?;
+    enqueue_event(
+        &connection,
+        &redis,
+        &doc_id,
+        &session.data.user_id,
+        WebhookEvent::Rename,
+        json!({ "title": name }),
+    );
     Ok(send_success(
         &doc.serializable(Some(&session.data.user_id))?,
     ))
@@ -119,6 +222,13 @@ fn copy_document(doc_id: DocumentID, connection: Connection, session: Session) -
 
     if doc.can_be_viewed_by(&session.data.user_id) {
         let new_doc = doc.copy_to_user(&session.data.user_id)?;
+        Activity::record(
+            &connection,
+            &new_doc.get_id(),
+            &session.data.user_id,
+            ActivityEvent::Copy,
+            &format!("Copied from \"{}\"", doc.get_title()?),
+        )?;
         Ok(send_success(
             &new_doc.serializable(Some(&session.data.user_id))?,
         ))
@@ -133,6 +243,8 @@ struct EditDocumentStyle {
     value_string: Option<String>,
     value_number: Option<i32>,
     unit: Option<StyleUnit>,
+    #[serde(default)]
+    inherit: bool,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -141,8 +253,20 @@ struct EditDocumentItem {
     parent_id: Option<String>,
     child_order: i32,
     children: Vec<String>,
-    item_text: Option<String>,
+    item_text: Option<ItemText>,
     styles: Vec<EditDocumentStyle>,
+    #[serde(default)]
+    task_state: Option<TaskState>,
+    #[serde(default)]
+    due_at: Option<SystemTime>,
+    #[serde(default)]
+    kind: ItemKind,
+    #[serde(default)]
+    code_language: Option<String>,
+    #[serde(default)]
+    style_preset: Option<String>,
+    #[serde(default)]
+    attachments: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -182,9 +306,15 @@ fn merge_edit_subtree<'a>(
             let mut new_item = doc.add_item(
                 Some(parent_uuid),
                 curr_item.child_order,
-                curr_item.item_text.clone(),
+                curr_item.item_text.as_ref().map(|t| t.to_plain_text()),
             )?;
 
+            if let Some(ref text) = curr_item.item_text {
+                if text.is_rich() {
+                    new_item.update_text(text)?;
+                }
+            }
+
             let db_styles = curr_item
                 .styles
                 .iter()
@@ -194,12 +324,38 @@ fn merge_edit_subtree<'a>(
                         s.value_string.clone(),
                         s.value_number,
                         s.unit,
+                        s.inherit,
                     )
                 })
                 .collect();
 
             new_item.update_styles(db_styles)?;
 
+            if curr_item.task_state.is_some() || curr_item.due_at.is_some() {
+                new_item.update_task(curr_item.task_state, curr_item.due_at)?;
+            }
+
+            if curr_item.kind != ItemKind::Paragraph {
+                new_item.set_kind(
+                    curr_item.kind,
+                    curr_item.code_language.as_ref().map(|l| l.as_str()),
+                )?;
+            }
+
+            if let Some(ref preset_name) = curr_item.style_preset {
+                new_item.set_style_preset(Some(preset_name.trim()))?;
+            }
+
+            if !curr_item.attachments.is_empty() {
+                let attachment_ids = curr_item
+                    .attachments
+                    .iter()
+                    .filter_map(|a| AttachmentID::from_str(a).ok())
+                    .collect();
+
+                new_item.link_attachments(&attachment_ids)?;
+            }
+
             curr_item_id = new_item.get_id();
         }
     }
@@ -226,6 +382,54 @@ fn merge_edit_subtree<'a>(
     Ok(child_ids)
 }
 
+fn validate_text(item_id: &str, text: &ItemText) -> Result<(), Error> {
+    text.sanitize()
+        .map_err(|e| Error::InvalidText(Some(item_id.to_string()), e))?
+        .validate()?;
+
+    Ok(())
+}
+
+fn available_preset_names(
+    connection: &Connection,
+    doc: &Document,
+) -> Result<HashSet<String>, Error> {
+    Ok(
+        StylePreset::get_available(connection, &doc.get_id(), &doc.get_owner_id())?
+            .into_iter()
+            .map(|p| p.data.name)
+            .collect(),
+    )
+}
+
+fn validate_items(
+    preset_names: &HashSet<String>,
+    subtree: &EditDocumentParams,
+) -> Result<(), Error> {
+    for (item_id, item) in subtree.items.iter() {
+        validate_code_language(&item.code_language)?;
+
+        if let Some(ref preset_name) = item.style_preset {
+            check_preset_reference(preset_names, preset_name)?;
+        }
+
+        if let Some(ref text) = item.item_text {
+            validate_text(item_id, text)?;
+        }
+
+        for s in item.styles.iter() {
+            validate_style(
+                s.property,
+                s.value_string.as_ref().map(|v| v.as_str()),
+                s.value_number,
+                s.unit,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
 fn update_root(doc: &mut Document, subtree: &EditDocumentParams) -> Result<(), Error> {
     let mut root_item = doc.get_item(&ItemID::from_str(&subtree.root_item)?)?;
     root_item.remove_children()?;
@@ -239,10 +443,204 @@ fn update_root(doc: &mut Document, subtree: &EditDocumentParams) -> Result<(), E
     Ok(())
 }
 
+// locks hold back everyone who can edit, the owner included, until the
+// section is unlocked again
+pub fn check_unlocked(
+    doc: &Document,
+    item_ids: &[ItemID],
+    include_descendants: bool,
+) -> Result<(), Error> {
+    match doc.find_lock(item_ids, include_descendants)? {
+        Some(item_id) => Err(Error::ItemLocked(item_id)),
+        None => Ok(()),
+    }
+}
+
+// regenerating replaces the table of contents, or adds one under the root
+fn find_toc_lock(doc: &Document) -> Result<Option<ItemID>, Error> {
+    Ok(match doc.get_serialized_toc_id() {
+        Some(toc_id) => doc.find_lock(&[toc_id], true)?,
+        None => doc.find_lock(&[doc.get_root()?.get_id()], false)?,
+    })
+}
+
+// edits elsewhere leave a locked table of contents as it is, even if it no
+// longer matches the headings
+fn regenerate_toc(doc: &mut Document) -> Result<(), Error> {
+    if find_toc_lock(doc)?.is_none() {
+        toc::regenerate(doc)?;
+    }
+
+    Ok(())
+}
+
+fn same_text(submitted: Option<&ItemText>, stored: &Item) -> Result<bool, Error> {
+    let submitted = match submitted {
+        Some(t) => t
+            .sanitize()
+            .map_err(|e| Error::InvalidText(Some(stored.get_id().json_str()), e))?,
+        None => ItemText::Plain(String::new()),
+    };
+
+    Ok(serde_json::to_value(&submitted).ok() == serde_json::to_value(&stored.get_text()).ok())
+}
+
+fn same_styles(submitted: &[EditDocumentStyle], stored: &[Style]) -> bool {
+    submitted.len() == stored.len()
+        && submitted.iter().all(|s| {
+            stored.iter().any(|st| {
+                st.data.property == s.property
+                    && st.data.value_string == s.value_string
+                    && st.data.value_number == s.value_number
+                    && st.data.unit == s.unit
+                    && st.data.inherit == s.inherit
+            })
+        })
+}
+
+// a save replaces the whole edited subtree, so each locked item, and everything
+// below it, has to come back exactly as stored, under the same parent and with
+// the same children
+fn check_locked_items(
+    connection: &Connection,
+    doc: &Document,
+    subtree: &EditDocumentParams,
+) -> Result<(), Error> {
+    let root_id = ItemID::from_str(&subtree.root_item)?;
+    let doc_items = doc.get_items()?;
+
+    let mut stored_children = HashMap::<ItemID, Vec<&Item>>::new();
+    for i in doc_items.iter() {
+        if let Some(pid) = i.get_parent_id() {
+            stored_children.entry(pid).or_insert(Vec::new()).push(i);
+        }
+    }
+
+    let mut styles_by_item = HashMap::<ItemID, Vec<Style>>::new();
+    for s in Style::get_by_document(connection, &doc.get_id())?.into_iter() {
+        styles_by_item
+            .entry(s.get_item_id())
+            .or_insert(Vec::new())
+            .push(s);
+    }
+
+    // the structure merge_edit_subtree will build, walking down from the root
+    let mut submitted_parents = HashMap::<&str, &str>::new();
+    let mut pending = vec![subtree.root_item.as_str()];
+    while let Some(curr) = pending.pop() {
+        if let Some(item) = subtree.items.get(curr) {
+            for child in item.children.iter() {
+                if !submitted_parents.contains_key(child.as_str()) {
+                    submitted_parents.insert(child.as_str(), curr);
+                    pending.push(child.as_str());
+                }
+            }
+        }
+    }
+
+    let same_children = |item_id: &ItemID| -> bool {
+        let stored: HashSet<String> = stored_children
+            .get(item_id)
+            .map(|c| c.iter().map(|i| i.get_id().json_str()).collect())
+            .unwrap_or_default();
+        let submitted: HashSet<String> = subtree
+            .items
+            .get(&item_id.json_str())
+            .map(|i| i.children.iter().cloned().collect())
+            .unwrap_or_default();
+
+        stored == submitted
+    };
+
+    // only the root's text is saved in place
+    let root_locked = doc.find_lock(&[root_id.clone()], false)?;
+    if let Some(ref lock_id) = root_locked {
+        let root_item = doc.get_item(&root_id)?;
+        let root_text = subtree
+            .items
+            .get(&subtree.root_item)
+            .and_then(|i| i.item_text.as_ref());
+
+        if (root_text.is_some() && !same_text(root_text, &root_item)?) || !same_children(&root_id) {
+            return Err(Error::ItemLocked(lock_id.clone()));
+        }
+    }
+
+    let mut frozen: Vec<(&Item, ItemID)> = Vec::new();
+    let mut unfrozen: Vec<&Item> = Vec::new();
+    for child in stored_children
+        .get(&root_id)
+        .into_iter()
+        .flat_map(|c| c.iter().cloned())
+    {
+        match root_locked {
+            Some(ref lock_id) => frozen.push((child, lock_id.clone())),
+            None => unfrozen.push(child),
+        }
+    }
+
+    while let Some(i) = unfrozen.pop() {
+        if i.is_locked() {
+            frozen.push((i, i.get_id()));
+        } else {
+            unfrozen.extend(
+                stored_children
+                    .get(&i.get_id())
+                    .into_iter()
+                    .flat_map(|c| c.iter().cloned()),
+            );
+        }
+    }
+
+    while let Some((i, lock_id)) = frozen.pop() {
+        let item_id = i.get_id();
+        let item_id_str = item_id.json_str();
+        let parent_id_str = i.get_parent_id().map(|p| p.json_str());
+
+        let unchanged = match subtree.items.get(&item_id_str) {
+            Some(submitted) => {
+                submitted_parents.get(item_id_str.as_str()).map(|p| *p)
+                    == parent_id_str.as_ref().map(|p| p.as_str())
+                    && same_text(submitted.item_text.as_ref(), i)?
+                    && submitted.kind == i.data.kind
+                    && submitted.code_language == i.data.code_language
+                    && submitted.style_preset.as_ref().map(|p| p.trim())
+                        == i.data.style_preset.as_ref().map(|p| p.as_str())
+                    && submitted.task_state == i.data.task_state
+                    && submitted.due_at == i.data.due_at
+                    && same_styles(
+                        &submitted.styles,
+                        styles_by_item
+                            .get(&item_id)
+                            .map(|s| s.as_slice())
+                            .unwrap_or(&[]),
+                    )
+                    && same_children(&item_id)
+            }
+            None => false,
+        };
+
+        if !unchanged {
+            return Err(Error::ItemLocked(lock_id));
+        }
+
+        for child in stored_children
+            .get(&item_id)
+            .into_iter()
+            .flat_map(|c| c.iter().cloned())
+        {
+            frozen.push((child, lock_id.clone()));
+        }
+    }
+
+    Ok(())
+}
+
 #[post("/<doc_id>/edit", format = "json", data = "<subtree>")]
 fn edit_document(
     doc_id: DocumentID,
     connection: Connection,
+    redis: memory::redis::Connection,
     session: Session,
     subtree: Json<EditDocumentParams>,
 ) -> SeriatimResult {
@@ -252,15 +650,75 @@ fn edit_document(
         return Err(Error::InsufficientPermissions);
     }
 
+    check_lease(&redis, &doc_id, &session)?;
+
+    check_locked_items(&connection, &doc, &subtree)?;
+
+    // items whose preset has since been deleted can still be sent back as
+    // they are
+    let mut preset_names = available_preset_names(&connection, &doc)?;
+    preset_names.extend(
+        doc.get_items()?
+            .into_iter()
+            .filter_map(|i| i.data.style_preset),
+    );
+    validate_items(&preset_names, &subtree)?;
+
+    // replaced items get new IDs, so their locks and shares are carried over
+    // by hand
+    let shares = SubtreeShare::get_by_document(&connection, &doc_id)?;
+    let locks: Vec<(String, Option<user::UserID>, Option<SystemTime>)> = doc
+        .get_items()?
+        .iter()
+        .filter(|i| i.is_locked())
+        .map(|i| (i.get_id().json_str(), i.get_locked_by(), i.data.locked_at))
+        .collect();
+
     update_root(&mut doc, &subtree)?;
     let id_map = merge_edit_subtree(&mut doc, &subtree, &subtree.root_item, None)?;
 
+    for (old_id, p_locked_by, p_locked_at) in locks.iter() {
+        if let Some(Some(new_id)) = id_map.get(old_id) {
+            if new_id.json_str() != *old_id {
+                doc.get_item(new_id)?
+                    .set_lock(p_locked_by.as_ref(), *p_locked_at)?;
+            }
+        }
+    }
+
+    for mut share in shares.into_iter() {
+        if let Some(Some(new_id)) = id_map.get(&share.get_item_id().json_str()) {
+            if *new_id != share.get_item_id() {
+                share.move_to_item(new_id)?;
+            }
+        }
+    }
+
     let toc_item_id = match subtree.toc_item {
         Some(ref t) => id_map.get(t).unwrap_or(&None),
         None => &None,
     };
 
-    doc.set_toc_item(toc_item_id)?.touch()?;
+    doc.set_toc_item(toc_item_id)?;
+    regenerate_toc(&mut doc)?;
+    SubtreeShare::remove_orphaned(&connection, &doc_id)?;
+    doc.touch()?;
+    Activity::record(
+        &connection,
+        &doc_id,
+        &session.data.user_id,
+        ActivityEvent::Edit,
+        &format!("Edited the outline ({} items)", id_map.len()),
+    )?;
+    enqueue_event(
+        &connection,
+        &redis,
+        &doc_id,
+        &session.data.user_id,
+        WebhookEvent::Edit,
+        json!({ "item_count": id_map.len() }),
+    );
+
     Ok(send_success(&id_map))
 }
 
@@ -273,28 +731,419 @@ fn edit_text_options<'a>(_doc_id: DocumentID) -> rocket::response::Response<'a>
 fn edit_document_text(
     doc_id: DocumentID,
     connection: Connection,
+    redis: memory::redis::Connection,
     session: Session,
-    changes: Json<HashMap<String, String>>,
+    changes: Json<HashMap<String, ItemText>>,
 ) -> SeriatimResult {
     let mut doc = Document::get_by_id(&connection, &doc_id)?;
 
     if !doc.can_be_edited_by(&session.data.user_id) {
         Err(Error::InsufficientPermissions)
     } else {
+        for (item_id, text) in changes.iter() {
+            validate_text(item_id, text)?;
+        }
+
+        check_lease(&redis, &doc_id, &session)?;
+
+        let changed_item_ids: Vec<ItemID> = changes
+            .keys()
+            .filter_map(|i| ItemID::from_str(i).ok())
+            .collect();
+        check_unlocked(&doc, &changed_item_ids, false)?;
+
         let mut items = doc.get_items()?;
+        let mut changed_ids = Vec::new();
         for item in items.iter_mut() {
             let item_id = item.get_id().json_str();
 
             if let Some(new_text) = changes.get(&item_id) {
                 item.update_text(&new_text)?;
+                changed_ids.push(item_id);
             }
         }
 
+        if !changed_ids.is_empty() {
+            regenerate_toc(&mut doc)?;
+        }
+
         doc.touch()?;
+        Activity::record(
+            &connection,
+            &doc_id,
+            &session.data.user_id,
+            ActivityEvent::EditText,
+            &format!("Edited the text of {} item(s)", changed_ids.len()),
+        )?;
+        enqueue_event(
+            &connection,
+            &redis,
+            &doc_id,
+            &session.data.user_id,
+            WebhookEvent::EditText,
+            json!({ "item_ids": changed_ids }),
+        );
+
         Ok(send_success(&()))
     }
 }
 
+#[derive(Serialize, Deserialize)]
+struct ItemTaskParams {
+    task_state: Option<TaskState>,
+    due_at: Option<SystemTime>,
+}
+
+#[options("/<_doc_id>/items/<_item_id>/task")]
+fn item_task_options<'a>(_doc_id: DocumentID, _item_id: ItemID) -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+#[post("/<doc_id>/items/<item_id>/task", format = "json", data = "<task>")]
+fn update_item_task(
+    doc_id: DocumentID,
+    item_id: ItemID,
+    connection: Connection,
+    redis: memory::redis::Connection,
+    session: Session,
+    task: Json<ItemTaskParams>,
+) -> SeriatimResult {
+    let mut doc = Document::get_by_id(&connection, &doc_id)?;
+
+    if !doc.can_be_edited_by(&session.data.user_id) {
+        return Err(Error::InsufficientPermissions);
+    }
+
+    check_lease(&redis, &doc_id, &session)?;
+    check_unlocked(&doc, &[item_id.clone()], false)?;
+
+    let mut item = doc.get_item(&item_id)?;
+    item.update_task(task.task_state, task.due_at)?;
+    let response = send_success(&item);
+
+    Activity::record(
+        &connection,
+        &doc_id,
+        &session.data.user_id,
+        ActivityEvent::Edit,
+        "Updated a task",
+    )?;
+
+    doc.touch()?;
+    Ok(response)
+}
+
+#[options("/<_doc_id>/items/<_item_id>/toggle")]
+fn item_toggle_options<'a>(
+    _doc_id: DocumentID,
+    _item_id: ItemID,
+) -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+#[post("/<doc_id>/items/<item_id>/toggle")]
+fn toggle_item_task(
+    doc_id: DocumentID,
+    item_id: ItemID,
+    connection: Connection,
+    redis: memory::redis::Connection,
+    session: Session,
+) -> SeriatimResult {
+    let mut doc = Document::get_by_id(&connection, &doc_id)?;
+
+    if !doc.can_be_edited_by(&session.data.user_id) {
+        return Err(Error::InsufficientPermissions);
+    }
+
+    check_lease(&redis, &doc_id, &session)?;
+    check_unlocked(&doc, &[item_id.clone()], false)?;
+
+    let mut item = doc.get_item(&item_id)?;
+    item.toggle_task()?;
+    let response = send_success(&item);
+
+    Activity::record(
+        &connection,
+        &doc_id,
+        &session.data.user_id,
+        ActivityEvent::Edit,
+        "Toggled a task",
+    )?;
+
+    doc.touch()?;
+    Ok(response)
+}
+
+#[derive(Serialize, Deserialize)]
+struct ItemKindParams {
+    kind: ItemKind,
+    code_language: Option<String>,
+}
+
+fn validate_code_language(code_language: &Option<String>) -> Result<(), Error> {
+    match code_language {
+        Some(ref l) if !is_valid_code_language(l) => Err(Error::InvalidCodeLanguage),
+        _ => Ok(()),
+    }
+}
+
+#[options("/<_doc_id>/items/<_item_id>/kind")]
+fn item_kind_options<'a>(_doc_id: DocumentID, _item_id: ItemID) -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+#[post("/<doc_id>/items/<item_id>/kind", format = "json", data = "<params>")]
+fn set_item_kind(
+    doc_id: DocumentID,
+    item_id: ItemID,
+    connection: Connection,
+    redis: memory::redis::Connection,
+    session: Session,
+    params: Json<ItemKindParams>,
+) -> SeriatimResult {
+    let mut doc = Document::get_by_id(&connection, &doc_id)?;
+
+    if !doc.can_be_edited_by(&session.data.user_id) {
+        return Err(Error::InsufficientPermissions);
+    }
+
+    validate_code_language(&params.code_language)?;
+    check_lease(&redis, &doc_id, &session)?;
+    check_unlocked(&doc, &[item_id.clone()], false)?;
+
+    doc.get_item(&item_id)?.set_kind(
+        params.kind,
+        params.code_language.as_ref().map(|l| l.as_str()),
+    )?;
+    regenerate_toc(&mut doc)?;
+    doc.touch()?;
+
+    Ok(send_success(&doc.get_item(&item_id)?))
+}
+
+#[derive(Serialize, Deserialize)]
+struct ItemPresetParams {
+    style_preset: Option<String>,
+}
+
+#[options("/<_doc_id>/items/<_item_id>/preset")]
+fn item_preset_options<'a>(
+    _doc_id: DocumentID,
+    _item_id: ItemID,
+) -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+#[post("/<doc_id>/items/<item_id>/preset", format = "json", data = "<params>")]
+fn set_item_preset(
+    doc_id: DocumentID,
+    item_id: ItemID,
+    connection: Connection,
+    redis: memory::redis::Connection,
+    session: Session,
+    params: Json<ItemPresetParams>,
+) -> SeriatimResult {
+    let mut doc = Document::get_by_id(&connection, &doc_id)?;
+
+    if !doc.can_be_edited_by(&session.data.user_id) {
+        return Err(Error::InsufficientPermissions);
+    }
+
+    let preset_name = match params.style_preset {
+        Some(ref name) => {
+            let preset_names = available_preset_names(&connection, &doc)?;
+            Some(check_preset_reference(&preset_names, name)?)
+        }
+        None => None,
+    };
+
+    check_lease(&redis, &doc_id, &session)?;
+    check_unlocked(&doc, &[item_id.clone()], false)?;
+
+    doc.get_item(&item_id)?.set_style_preset(preset_name)?;
+    doc.touch()?;
+
+    Ok(send_success(&doc.get_item(&item_id)?))
+}
+
+#[options("/<_doc_id>/items/<_item_id>/lock")]
+fn item_lock_options<'a>(_doc_id: DocumentID, _item_id: ItemID) -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+#[post("/<doc_id>/items/<item_id>/lock")]
+fn lock_item(
+    doc_id: DocumentID,
+    item_id: ItemID,
+    connection: Connection,
+    redis: memory::redis::Connection,
+    session: Session,
+) -> SeriatimResult {
+    let doc = Document::get_by_id(&connection, &doc_id)?;
+
+    if !doc.is_owned_by(&session.data.user_id) {
+        return Err(Error::InsufficientPermissions);
+    }
+
+    check_lease(&redis, &doc_id, &session)?;
+
+    let mut item = doc.get_item(&item_id)?;
+    if !item.is_locked() {
+        item.lock(&session.data.user_id)?;
+    }
+
+    Ok(send_success(&item))
+}
+
+#[delete("/<doc_id>/items/<item_id>/lock")]
+fn unlock_item(
+    doc_id: DocumentID,
+    item_id: ItemID,
+    connection: Connection,
+    redis: memory::redis::Connection,
+    session: Session,
+) -> SeriatimResult {
+    let doc = Document::get_by_id(&connection, &doc_id)?;
+
+    if !doc.is_owned_by(&session.data.user_id) {
+        return Err(Error::InsufficientPermissions);
+    }
+
+    check_lease(&redis, &doc_id, &session)?;
+
+    let mut item = doc.get_item(&item_id)?;
+    item.unlock()?;
+
+    Ok(send_success(&item))
+}
+
+#[derive(Serialize, Deserialize)]
+struct TocGenerationParams {
+    generation: Option<TocGeneration>,
+    depth: Option<i32>,
+}
+
+#[options("/<_doc_id>/toc")]
+fn toc_options<'a>(_doc_id: DocumentID) -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+#[post("/<doc_id>/toc", format = "json", data = "<params>")]
+fn set_toc_generation(
+    doc_id: DocumentID,
+    connection: Connection,
+    redis: memory::redis::Connection,
+    session: Session,
+    params: Json<TocGenerationParams>,
+) -> SeriatimResult {
+    let mut doc = Document::get_by_id(&connection, &doc_id)?;
+
+    if !doc.can_be_edited_by(&session.data.user_id) {
+        return Err(Error::InsufficientPermissions);
+    }
+
+    check_lease(&redis, &doc_id, &session)?;
+
+    if let Some(lock_id) = find_toc_lock(&doc)? {
+        return Err(Error::ItemLocked(lock_id));
+    }
+
+    let depth = params.depth.unwrap_or(doc.data.toc_depth).max(1);
+    doc.set_toc_generation(params.generation, depth)?;
+    regenerate_toc(&mut doc)?;
+    doc.touch()?;
+
+    Ok(send_success(
+        &doc.serialize_with_items(Some(&session.data.user_id))?,
+    ))
+}
+
+#[derive(Serialize, Deserialize)]
+struct NumberingParams {
+    scheme: Option<NumberingScheme>,
+}
+
+#[options("/<_doc_id>/numbering")]
+fn numbering_options<'a>(_doc_id: DocumentID) -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+#[post("/<doc_id>/numbering", format = "json", data = "<params>")]
+fn set_numbering(
+    doc_id: DocumentID,
+    connection: Connection,
+    redis: memory::redis::Connection,
+    session: Session,
+    params: Json<NumberingParams>,
+) -> SeriatimResult {
+    let mut doc = Document::get_by_id(&connection, &doc_id)?;
+
+    if !doc.can_be_edited_by(&session.data.user_id) {
+        return Err(Error::InsufficientPermissions);
+    }
+
+    check_lease(&redis, &doc_id, &session)?;
+
+    doc.set_numbering_scheme(params.scheme)?.touch()?;
+
+    Ok(send_success(
+        &doc.serialize_with_items(Some(&session.data.user_id))?,
+    ))
+}
+
+#[derive(Serialize, Deserialize)]
+struct DocumentStylesParams {
+    styles: Vec<EditDocumentStyle>,
+}
+
+#[options("/<_doc_id>/styles")]
+fn document_styles_options<'a>(_doc_id: DocumentID) -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+#[post("/<doc_id>/styles", format = "json", data = "<params>")]
+fn set_document_styles(
+    doc_id: DocumentID,
+    connection: Connection,
+    redis: memory::redis::Connection,
+    session: Session,
+    params: Json<DocumentStylesParams>,
+) -> SeriatimResult {
+    let mut doc = Document::get_by_id(&connection, &doc_id)?;
+
+    if !doc.can_be_edited_by(&session.data.user_id) {
+        return Err(Error::InsufficientPermissions);
+    }
+
+    check_lease(&redis, &doc_id, &session)?;
+
+    let default_styles: Vec<DocumentStyle> = params
+        .styles
+        .iter()
+        .map(|s| {
+            DocumentStyle::create(
+                &connection,
+                &doc_id,
+                s.property,
+                s.value_string.clone(),
+                s.value_number,
+                s.unit,
+            )
+        })
+        .collect();
+
+    for s in default_styles.iter() {
+        s.validate()?;
+    }
+
+    DocumentStyle::replace_for_document(&connection, &doc_id, default_styles)?;
+    doc.touch()?;
+
+    Ok(send_success(
+        &doc.serialize_with_items(Some(&session.data.user_id))?,
+    ))
+}
+
 #[derive(Serialize, Deserialize)]
 struct DocumentViewabilityParams {
     publicly_viewable: bool,
@@ -313,6 +1162,7 @@ fn public_viewability_options<'a>(_doc_id: DocumentID) -> rocket::response::Resp
 fn public_viewability(
     doc_id: DocumentID,
     connection: Connection,
+    redis: memory::redis::Connection,
     session: Session,
     viewability: Json<DocumentViewabilityParams>,
 ) -> SeriatimResult {
@@ -321,9 +1171,41 @@ fn public_viewability(
     if !doc.is_owned_by(&session.data.user_id) {
         Err(Error::InsufficientPermissions)
     } else {
+        check_lease(&redis, &doc_id, &session)?;
+
+        doc.set_publicly_viewable(viewability.publicly_viewable)?;
+        if viewability.publicly_viewable {
+            Activity::record(
+                &connection,
+                &doc_id,
+                &session.data.user_id,
+                ActivityEvent::Publish,
+                "Made the document public",
+            )?;
+        } else {
+            Activity::record(
+                &connection,
+                &doc_id,
+                &session.data.user_id,
+                ActivityEvent::Unpublish,
+                "Made the document private",
+            )?;
+        }
+        enqueue_event(
+            &connection,
+            &redis,
+            &doc_id,
+            &session.data.user_id,
+            if viewability.publicly_viewable {
+                WebhookEvent::Publish
+            } else {
+                WebhookEvent::Unpublish
+            },
+            json!({ "publicly_viewable": viewability.publicly_viewable }),
+        );
+
         Ok(send_success(
-            &doc.set_publicly_viewable(viewability.publicly_viewable)?
-                .serializable(Some(&session.data.user_id))?,
+            &doc.serializable(Some(&session.data.user_id))?,
         ))
     }
 }
@@ -350,12 +1232,23 @@ fn add_category(
     if !doc.can_be_viewed_by(&session.data.user_id) {
         Err(Error::InsufficientPermissions)
     } else {
-        Category::create(
+        let category = Category::create(
             &connection,
             &doc_id,
             &session.data.user_id,
             &new_category.name,
         )?;
+
+        // categories are per user, so a viewer's own stay out of the owner's log
+        if doc.is_owned_by(&session.data.user_id) {
+            Activity::record(
+                &connection,
+                &doc_id,
+                &session.data.user_id,
+                ActivityEvent::AddCategory,
+                &format!("Added the category \"{}\"", category.data.category_name),
+            )?;
+        }
         Ok(send_success(
             &doc.serializable(Some(&session.data.user_id))?,
         ))
@@ -386,12 +1279,88 @@ fn delete_category(
             Category::get_category(&connection, &doc_id, &session.data.user_id, &cat_name)?;
         category.delete()?;
 
+        if doc.is_owned_by(&session.data.user_id) {
+            Activity::record(
+                &connection,
+                &doc_id,
+                &session.data.user_id,
+                ActivityEvent::RemoveCategory,
+                &format!("Removed the category \"{}\"", category.data.category_name),
+            )?;
+        }
+
         Ok(send_success(
             &doc.serializable(Some(&session.data.user_id))?,
         ))
     }
 }
 
+#[derive(Serialize)]
+struct ActivityPage<'a> {
+    activities: Vec<Activity<'a>>,
+    page: i64,
+    per_page: i64,
+    total: i64,
+}
+
+#[get("/<doc_id>/activity?<page>&<per_page>")]
+fn get_activity(
+    doc_id: DocumentID,
+    page: Option<i64>,
+    per_page: Option<i64>,
+    connection: Connection,
+    session: Session,
+) -> SeriatimResult {
+    let doc = Document::get_by_id(&connection, &doc_id)?;
+
+    if !doc.can_be_edited_by(&session.data.user_id) {
+        return Err(Error::InsufficientPermissions);
+    }
+
+    let page = page.unwrap_or(0).max(0);
+    let per_page = per_page
+        .unwrap_or(Activity::DEFAULT_PAGE_SIZE)
+        .max(1)
+        .min(Activity::MAX_PAGE_SIZE);
+
+    Ok(send_success(&ActivityPage {
+        activities: Activity::get_by_document(&connection, &doc_id, page, per_page)?,
+        page,
+        per_page,
+        total: Activity::count_by_document(&connection, &doc_id)?,
+    }))
+}
+
+#[get("/<doc_id>/stats")]
+fn get_stats(doc_id: DocumentID, connection: Connection, session: Session) -> SeriatimResult {
+    let doc = Document::get_by_id(&connection, &doc_id)?;
+
+    if !doc.can_be_viewed_by(&session.data.user_id) {
+        return Err(Error::InsufficientPermissions);
+    }
+
+    Ok(send_success(&doc.get_stats()?))
+}
+
+#[get("/<doc_id>/diff/<other_doc_id>")]
+fn diff_documents(
+    doc_id: DocumentID,
+    other_doc_id: DocumentID,
+    connection: Connection,
+    session: Session,
+) -> SeriatimResult {
+    let doc = Document::get_by_id(&connection, &doc_id)?;
+    let other_doc = Document::get_by_id(&connection, &other_doc_id)?;
+
+    if !doc.can_be_viewed_by(&session.data.user_id)
+        || !other_doc.can_be_viewed_by(&session.data.user_id)
+    {
+        return Err(Error::InsufficientPermissions);
+    }
+
+    Ok(send_success(&doc.diff_against(&other_doc)?))
+}
+
 #[get("/<_path..>", rank = 2)]
 fn not_logged_in_get(_path: PathBuf) -> SeriatimResult {
     Err(Error::NotLoggedIn)
@@ -416,12 +1385,32 @@ pub fn routes() -> Vec<Route> {
         edit_document,
         edit_text_options,
         edit_document_text,
+        item_task_options,
+        update_item_task,
+        item_toggle_options,
+        toggle_item_task,
+        item_kind_options,
+        set_item_kind,
+        item_preset_options,
+        set_item_preset,
+        item_lock_options,
+        lock_item,
+        unlock_item,
+        toc_options,
+        set_toc_generation,
+        numbering_options,
+        set_numbering,
+        document_styles_options,
+        set_document_styles,
         public_viewability_options,
         public_viewability,
         category_options,
         add_category,
         delete_category_options,
         delete_category,
+        get_activity,
+        get_stats,
+        diff_documents,
         not_logged_in_get,
         not_logged_in_post,
     ]
@@ -0,0 +1,210 @@
+use config::SeriatimConfig;
+
+use data::db::Connection;
+use data::document::DocumentID;
+use data::item::ItemID;
+use data::sanitize::escape_html;
+use data::share::{SubtreeShare, SubtreeShareID};
+use data::user::User;
+
+use render::html::{find_subtree, render_embed_page, truncate};
+
+use rocket::response::content::Html;
+use rocket::{Route, State};
+use rocket_contrib::json::Json;
+
+use routes::error::Error;
+use routes::public::{get_public_document, load_page};
+
+use std::slice;
+use std::str::FromStr;
+
+use url::Url;
+
+const DEFAULT_EMBED_WIDTH: u32 = 600;
+const DEFAULT_EMBED_HEIGHT: u32 = 400;
+const MAX_EMBED_TITLE_LENGTH: usize = 100;
+const PROVIDER_NAME: &'static str = "Seriatim";
+
+#[derive(Serialize)]
+struct OEmbed {
+    #[serde(rename = "type")]
+    embed_type: &'static str,
+    version: &'static str,
+    title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    author_name: Option<String>,
+    provider_name: &'static str,
+    provider_url: String,
+    html: String,
+    width: u32,
+    height: u32,
+}
+
+// only links to this site's public pages can be embedded
+fn parse_page_url(cfg: &SeriatimConfig, page_url: &str) -> Option<DocumentID> {
+    let parsed = Url::parse(page_url).ok()?;
+    let domain = Url::parse(&cfg.domain).ok()?;
+
+    if parsed.host_str() != domain.host_str()
+        || parsed.port_or_known_default() != domain.port_or_known_default()
+    {
+        return None;
+    }
+
+    let prefix = domain.path().to_string() + "public/";
+    if !parsed.path().starts_with(&prefix) {
+        return None;
+    }
+
+    DocumentID::from_str(parsed.path()[prefix.len()..].trim_end_matches('/')).ok()
+}
+
+fn embed_url(
+    cfg: &SeriatimConfig,
+    doc_id: &DocumentID,
+    share_id: Option<&SubtreeShareID>,
+) -> String {
+    let url = cfg.domain.clone() + "public/" + &doc_id.json_str() + "/embed";
+
+    match share_id {
+        Some(s) => url + "?share=" + &s.json_str(),
+        None => url,
+    }
+}
+
+// item IDs change whenever the outline is saved, so embedded subtrees are
+// anchored to one of the document's shares, which follows its item
+fn find_share<'a>(
+    connection: &'a Connection,
+    doc_id: &DocumentID,
+    item: Option<&String>,
+    share: Option<&String>,
+) -> Result<Option<SubtreeShare<'a>>, Error> {
+    let found = match (share, item) {
+        (Some(s), _) => Some(SubtreeShare::get_by_id(
+            connection,
+            &SubtreeShareID::from_str(s)?,
+        )?),
+        (None, Some(i)) => SubtreeShare::get_by_item(connection, doc_id, &ItemID::from_str(i)?)?,
+        (None, None) => None,
+    };
+
+    match found {
+        Some(ref s) if s.get_document_id() != *doc_id => Err(Error::InsufficientPermissions),
+        _ => Ok(found),
+    }
+}
+
+#[get("/oembed?<url>&<format>&<maxwidth>&<maxheight>&<item>&<share>")]
+fn oembed(
+    url: String,
+    format: Option<String>,
+    maxwidth: Option<u32>,
+    maxheight: Option<u32>,
+    item: Option<String>,
+    share: Option<String>,
+    connection: Connection,
+    cfg: State<SeriatimConfig>,
+) -> Result<Option<Json<OEmbed>>, Error> {
+    if format.as_ref().map(|f| f != "json").unwrap_or(false) {
+        return Err(Error::UnsupportedEmbedFormat);
+    }
+
+    let doc_id = match parse_page_url(&cfg, &url) {
+        Some(d) => d,
+        None => return Ok(None),
+    };
+    let doc = match get_public_document(&connection, &doc_id)? {
+        Some(d) => d,
+        None => return Ok(None),
+    };
+
+    // only subtrees the owner has shared can be embedded on their own
+    let subtree_share = if item.is_some() || share.is_some() {
+        match find_share(&connection, &doc_id, item.as_ref(), share.as_ref())? {
+            Some(s) => Some(s),
+            None => return Ok(None),
+        }
+    } else {
+        None
+    };
+
+    let page = load_page(&doc, &cfg)?;
+    let title = match subtree_share {
+        Some(ref s) => match find_subtree(&page.items, &s.get_item_id()) {
+            Some(subtree) => truncate(subtree.text.to_plain_text().trim(), MAX_EMBED_TITLE_LENGTH),
+            None => return Ok(None),
+        },
+        None => page.title.clone(),
+    };
+
+    let author_name = User::get_by_id(&connection, &doc.get_owner_id())?.public_name();
+
+    let width = maxwidth
+        .unwrap_or(DEFAULT_EMBED_WIDTH)
+        .min(DEFAULT_EMBED_WIDTH);
+    let height = maxheight
+        .unwrap_or(DEFAULT_EMBED_HEIGHT)
+        .min(DEFAULT_EMBED_HEIGHT);
+
+    let html = format!(
+        "<iframe src=\"{}\" width=\"{}\" height=\"{}\" title=\"{}\" frameborder=\"0\" loading=\"lazy\"></iframe>",
+        escape_html(&embed_url(
+            &cfg,
+            &doc_id,
+            subtree_share.as_ref().map(|s| s.get_id()).as_ref()
+        )),
+        width,
+        height,
+        escape_html(&title)
+    );
+
+    Ok(Some(Json(OEmbed {
+        embed_type: "rich",
+        version: "1.0",
+        title,
+        author_name,
+        provider_name: PROVIDER_NAME,
+        provider_url: cfg.domain.clone(),
+        html,
+        width,
+        height,
+    })))
+}
+
+#[get("/public/<doc_id>/embed?<share>")]
+fn embed_page(
+    doc_id: DocumentID,
+    share: Option<String>,
+    connection: Connection,
+    cfg: State<SeriatimConfig>,
+) -> Result<Option<Html<String>>, Error> {
+    let doc = match get_public_document(&connection, &doc_id)? {
+        Some(d) => d,
+        None => return Ok(None),
+    };
+
+    let subtree_share = match share {
+        Some(ref s) => match find_share(&connection, &doc_id, None, Some(s))? {
+            Some(found) => Some(found),
+            None => return Ok(None),
+        },
+        None => None,
+    };
+
+    let page = load_page(&doc, &cfg)?;
+    let rendered = match subtree_share {
+        Some(ref s) => match find_subtree(&page.items, &s.get_item_id()) {
+            Some(subtree) => render_embed_page(&page, slice::from_ref(subtree)),
+            None => return Ok(None),
+        },
+        None => render_embed_page(&page, &page.items),
+    };
+
+    Ok(Some(Html(rendered)))
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![oembed, embed_page]
+}
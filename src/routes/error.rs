@@ -1,3 +1,8 @@
+use data::item::ItemID;
+use data::rich_text::RichTextError;
+use data::sanitize::SanitizeError;
+use data::style::StyleError;
+
 use diesel;
 
 use r2d2_redis::redis::RedisError;
@@ -6,6 +11,8 @@ use rocket::http::ContentType;
 use rocket::request::Request;
 use rocket::response::{self, Responder, Response};
 
+use serde_json;
+
 use std;
 use std::ops::Deref;
 
@@ -16,6 +23,23 @@ pub enum Error {
     InsufficientPermissions,
     NotLoggedIn,
     TooFewLoginMethods,
+    InvalidWebhookURL,
+    InvalidStyle(StyleError),
+    InvalidPresetName,
+    UnknownPreset(String),
+    InvalidCodeLanguage,
+    InvalidRichText(RichTextError),
+    InvalidText(Option<String>, SanitizeError),
+    InvalidUpload,
+    UnsupportedAttachmentType,
+    AttachmentTooLarge(u64),
+    InvalidCategoryName,
+    CategoryExists,
+    InvalidFolderName,
+    InvalidFolderMove,
+    UnsupportedEmbedFormat,
+    ItemLocked(ItemID),
+    EditLeaseHeld(String),
     DatabaseError(Box<diesel::result::Error>),
     RedisError(Box<RedisError>),
     OtherError(Box<dyn std::error::Error>),
@@ -27,6 +51,23 @@ impl Error {
             Error::InsufficientPermissions => "INSUFFICIENT_PERMISSIONS",
             Error::NotLoggedIn => "NOT_LOGGED_IN",
             Error::TooFewLoginMethods => "TOO_FEW_LOGIN_METHODS",
+            Error::InvalidWebhookURL => "INVALID_WEBHOOK_URL",
+            Error::InvalidStyle(_) => "INVALID_STYLE",
+            Error::InvalidPresetName => "INVALID_PRESET_NAME",
+            Error::UnknownPreset(_) => "UNKNOWN_PRESET",
+            Error::InvalidCodeLanguage => "INVALID_CODE_LANGUAGE",
+            Error::InvalidRichText(_) => "INVALID_RICH_TEXT",
+            Error::InvalidText(_, e) => e.code(),
+            Error::InvalidUpload => "INVALID_UPLOAD",
+            Error::UnsupportedAttachmentType => "UNSUPPORTED_ATTACHMENT_TYPE",
+            Error::AttachmentTooLarge(_) => "ATTACHMENT_TOO_LARGE",
+            Error::InvalidCategoryName => "INVALID_CATEGORY_NAME",
+            Error::CategoryExists => "CATEGORY_EXISTS",
+            Error::InvalidFolderName => "INVALID_FOLDER_NAME",
+            Error::InvalidFolderMove => "INVALID_FOLDER_MOVE",
+            Error::UnsupportedEmbedFormat => "UNSUPPORTED_EMBED_FORMAT",
+            Error::ItemLocked(_) => "ITEM_LOCKED",
+            Error::EditLeaseHeld(_) => "EDIT_LEASE_HELD",
             Error::DatabaseError(e) => match e.deref() {
                 diesel::result::Error::NotFound => "NOT_FOUND",
                 _ => "DATABASE_ERROR",
@@ -35,17 +76,48 @@ impl Error {
             _ => "OTHER_ERROR",
         }
     }
+
+    // validation errors carry enough detail for clients to point at the problem
+    pub fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            Error::InvalidStyle(e) => Some(serde_json::json!({
+                "property": e.property.to_string(),
+                "reason": e.reason,
+            })),
+            Error::UnknownPreset(name) => Some(serde_json::json!({ "name": name })),
+            Error::InvalidRichText(e) => Some(serde_json::json!({ "reason": e.reason })),
+            Error::InvalidText(item_id, SanitizeError::TooLong { max_length, length }) => {
+                Some(serde_json::json!({
+                    "item_id": item_id,
+                    "max_length": max_length,
+                    "length": length,
+                }))
+            }
+            Error::AttachmentTooLarge(max_size) => {
+                Some(serde_json::json!({ "max_size": max_size }))
+            }
+            Error::ItemLocked(item_id) => Some(serde_json::json!({ "item_id": item_id })),
+            Error::EditLeaseHeld(name) => Some(serde_json::json!({ "display_name": name })),
+            _ => None,
+        }
+    }
 }
 
 impl<'r> Responder<'r> for Error {
     fn respond_to(self, _: &Request) -> response::Result<'r> {
+        let mut body = serde_json::json!({
+            "status": "error",
+            "code": self.code(),
+            "error": self.to_string(),
+        });
+
+        if let Some(details) = self.details() {
+            body["details"] = details;
+        }
+
         Response::build()
             .header(ContentType::JSON)
-            .sized_body(std::io::Cursor::new(format!(
-                "{{\"status\": \"error\", \"code\": \"{}\", \"error\": \"{}\" }}",
-                self.code(),
-                self
-            )))
+            .sized_body(std::io::Cursor::new(body.to_string()))
             .ok()
     }
 }
@@ -56,6 +128,23 @@ impl std::fmt::Display for Error {
 			Error::InsufficientPermissions => write!(f, "Insufficient Permissions - you do not have the permissions necessary to perform this action"),
 			Error::NotLoggedIn => write!(f, "Not Logged In - you must be logged in to access this URL"),
 			Error::TooFewLoginMethods => write!(f, "Too Few Login Methods - you can only remove a login method if you have at least one remaining way to log in"),
+			Error::InvalidWebhookURL => write!(f, "Invalid Webhook URL - webhook URLs must be absolute HTTP or HTTPS URLs of publicly reachable hosts"),
+			Error::InvalidStyle(e) => write!(f, "Invalid Style - {}", e),
+			Error::InvalidPresetName => write!(f, "Invalid Preset Name - preset names must be between 1 and 50 characters long"),
+			Error::UnknownPreset(name) => write!(f, "Unknown Preset - there is no preset named \"{}\" available to this document", name),
+			Error::InvalidCodeLanguage => write!(f, "Invalid Code Language - language tags must be at most 32 letters, digits or +-#._ characters"),
+			Error::InvalidRichText(e) => write!(f, "Invalid Rich Text - {}", e),
+			Error::InvalidText(_, e) => write!(f, "Invalid Text - {}", e),
+			Error::InvalidUpload => write!(f, "Invalid Upload - attachments must be uploaded as multipart form data in a field named \"file\""),
+			Error::UnsupportedAttachmentType => write!(f, "Unsupported Attachment Type - attachments must be PNG, JPEG, GIF or WebP images, or PDF documents"),
+			Error::AttachmentTooLarge(max_size) => write!(f, "Attachment Too Large - attachments must be at most {} bytes", max_size),
+			Error::InvalidCategoryName => write!(f, "Invalid Category Name - category names must not be empty, and the trash can only be managed per document"),
+			Error::CategoryExists => write!(f, "Category Exists - a category with that name already exists; merge the categories instead"),
+			Error::InvalidFolderName => write!(f, "Invalid Folder Name - folder names must be between 1 and 100 characters long"),
+			Error::InvalidFolderMove => write!(f, "Invalid Folder Move - a folder cannot be moved into itself or one of its subfolders"),
+			Error::UnsupportedEmbedFormat => write!(f, "Unsupported Embed Format - oEmbed responses are only available as JSON"),
+			Error::ItemLocked(_) => write!(f, "Item Locked - this section is locked and can't be edited until it's unlocked"),
+			Error::EditLeaseHeld(name) => write!(f, "Edit Lease Held - {} is editing this document right now, so it is read-only until they finish", name),
             Error::DatabaseError(e) => write!(f, "Database Error - {}", e),
             Error::RedisError(e) => write!(f, "Redis Error - {}", e),
 			Error::OtherError(e) => write!(f, "Other Error - {}", e),
@@ -69,6 +158,24 @@ impl std::convert::From<diesel::result::Error> for Error {
     }
 }
 
+impl std::convert::From<StyleError> for Error {
+    fn from(error: StyleError) -> Self {
+        Error::InvalidStyle(error)
+    }
+}
+
+impl std::convert::From<RichTextError> for Error {
+    fn from(error: RichTextError) -> Self {
+        Error::InvalidRichText(error)
+    }
+}
+
+impl std::convert::From<SanitizeError> for Error {
+    fn from(error: SanitizeError) -> Self {
+        Error::InvalidText(None, error)
+    }
+}
+
 impl std::convert::From<RedisError> for Error {
     fn from(error: RedisError) -> Self {
         Error::RedisError(Box::new(error))
@@ -92,3 +199,5 @@ impl<E: std::error::Error + NotSeriatimError + 'static> std::convert::From<E> fo
 impl NotSeriatimError for ParseError {}
 
 impl NotSeriatimError for reqwest::Error {}
+
+impl NotSeriatimError for std::io::Error {}
@@ -0,0 +1,150 @@
+use config::SeriatimConfig;
+
+use data::activity::Activity;
+use data::db::Connection;
+use data::document::{Document, DocumentID};
+use data::sanitize::escape_html;
+use data::schema::ActivityEvent;
+use data::user::{User, UserID};
+
+use render::atom::{render_feed, AtomEntry, AtomFeed};
+use render::html::render_excerpt;
+
+use rocket::http::ContentType;
+use rocket::response::content::Content;
+use rocket::{Route, State};
+
+use routes::error::Error;
+use routes::public::{get_public_document, load_page, page_url};
+
+use std::str::FromStr;
+use std::time::SystemTime;
+
+const FEED_EXTENSION: &'static str = ".atom";
+const MAX_FEED_ENTRIES: usize = 50;
+const MAX_EXCERPT_ITEMS: usize = 10;
+
+fn atom_content(feed: &AtomFeed) -> Content<String> {
+    Content(
+        ContentType::new("application", "atom+xml"),
+        render_feed(feed),
+    )
+}
+
+fn latest_update(entries: &[AtomEntry]) -> SystemTime {
+    entries
+        .iter()
+        .map(|e| e.updated)
+        .max()
+        .unwrap_or_else(SystemTime::now)
+}
+
+// categories and the trash are private to each user, so only changes to the
+// content itself show up in a document's public feed
+fn is_public_event(event: ActivityEvent) -> bool {
+    match event {
+        ActivityEvent::Create
+        | ActivityEvent::Rename
+        | ActivityEvent::Edit
+        | ActivityEvent::EditText
+        | ActivityEvent::Publish => true,
+        _ => false,
+    }
+}
+
+#[get("/user/<feed>")]
+fn user_feed(
+    feed: String,
+    connection: Connection,
+    cfg: State<SeriatimConfig>,
+) -> Result<Option<Content<String>>, Error> {
+    let user_id = UserID::from_str(feed.trim_end_matches(FEED_EXTENSION))?;
+    let u = match User::get_by_id(&connection, &user_id) {
+        Ok(u) => u,
+        Err(_) => return Ok(None),
+    };
+
+    // feeds are part of a user's public profile, which is opt-in
+    if !u.data.public_profile {
+        return Ok(None);
+    }
+
+    let mut entries = Vec::new();
+    for doc in Document::get_public_by_user(&connection, &user_id)?
+        .iter()
+        .take(MAX_FEED_ENTRIES)
+    {
+        let page = load_page(doc, &cfg)?;
+
+        entries.push(AtomEntry {
+            id: "urn:uuid:".to_string() + &doc.get_id().json_str(),
+            title: page.title.clone(),
+            url: page.url.clone(),
+            published: doc.data.created_at,
+            updated: page.modified_at,
+            content_html: render_excerpt(&page.items, MAX_EXCERPT_ITEMS),
+        });
+    }
+
+    let self_url = cfg.domain.clone() + "feeds/user/" + &user_id.json_str() + FEED_EXTENSION;
+
+    Ok(Some(atom_content(&AtomFeed {
+        id: self_url.clone(),
+        title: format!("{} - Seriatim", u.data.display_name),
+        self_url,
+        alternate_url: None,
+        author: u.data.display_name.clone(),
+        updated: latest_update(&entries),
+        entries,
+    })))
+}
+
+#[get("/document/<feed>")]
+fn document_feed(
+    feed: String,
+    connection: Connection,
+    cfg: State<SeriatimConfig>,
+) -> Result<Option<Content<String>>, Error> {
+    let doc_id = DocumentID::from_str(feed.trim_end_matches(FEED_EXTENSION))?;
+    let doc = match get_public_document(&connection, &doc_id)? {
+        Some(d) => d,
+        None => return Ok(None),
+    };
+
+    let title = doc.get_title()?;
+    let url = page_url(&cfg, &doc_id);
+    let owner = User::get_by_id(&connection, &doc.get_owner_id())?;
+
+    let entries: Vec<AtomEntry> =
+        Activity::get_since_published(&connection, &doc_id, Activity::MAX_PAGE_SIZE)?
+            .iter()
+            .filter(|a| is_public_event(a.data.event))
+            .take(MAX_FEED_ENTRIES)
+            .map(|a| AtomEntry {
+                id: "urn:uuid:".to_string() + &a.get_id().json_str(),
+                title: a.data.summary.clone(),
+                url: url.clone(),
+                published: a.data.created_at,
+                updated: a.data.created_at,
+                content_html: format!("<p>{}</p>", escape_html(&a.data.summary)),
+            })
+            .collect();
+
+    let self_url = cfg.domain.clone() + "feeds/document/" + &doc_id.json_str() + FEED_EXTENSION;
+
+    Ok(Some(atom_content(&AtomFeed {
+        id: self_url.clone(),
+        title: format!("Changes to {}", title),
+        self_url,
+        alternate_url: Some(url),
+        author: owner
+            .public_name()
+            .unwrap_or_else(|| "Seriatim".to_string()),
+        updated: latest_update(&entries),
+        entries,
+    })))
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![user_feed, document_feed]
+}
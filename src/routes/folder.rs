@@ -0,0 +1,189 @@
+use data::db::Connection;
+use data::document::{Document, DocumentID};
+use data::folder::{Folder, FolderID};
+use data::memory::session::Session;
+use data::sanitize::clean_text;
+
+use rocket;
+use rocket::Route;
+use rocket_contrib::json::Json;
+
+use routes::error::Error;
+use routes::io::{cors_response, send_success, SeriatimResult};
+
+#[derive(Deserialize)]
+struct CreateFolderParams {
+    name: String,
+    #[serde(default)]
+    parent_id: Option<FolderID>,
+}
+
+#[derive(Deserialize)]
+struct RenameFolderParams {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct MoveFolderParams {
+    #[serde(default)]
+    parent_id: Option<FolderID>,
+}
+
+#[derive(Deserialize)]
+struct MoveDocumentParams {
+    #[serde(default)]
+    folder_id: Option<FolderID>,
+}
+
+fn clean_folder_name(name: &str) -> Result<String, Error> {
+    let cleaned = clean_text(name).replace('\n', " ").trim().to_string();
+    let length = cleaned.chars().count();
+
+    if length == 0 || length > Folder::MAX_NAME_LENGTH {
+        Err(Error::InvalidFolderName)
+    } else {
+        Ok(cleaned)
+    }
+}
+
+#[options("/folders")]
+fn folders_options<'a>() -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+#[get("/folders")]
+fn list_folders(connection: Connection, session: Session) -> SeriatimResult {
+    Ok(send_success(&Folder::get_by_user(
+        &connection,
+        &session.data.user_id,
+    )?))
+}
+
+#[post("/folders", format = "json", data = "<params>")]
+fn create_folder(
+    connection: Connection,
+    session: Session,
+    params: Json<CreateFolderParams>,
+) -> SeriatimResult {
+    let user_id = &session.data.user_id;
+    let folder_name = clean_folder_name(&params.name)?;
+
+    if let Some(ref parent_id) = params.parent_id {
+        Folder::get_by_id(&connection, user_id, parent_id)?;
+    }
+
+    Ok(send_success(&Folder::create(
+        &connection,
+        user_id,
+        params.parent_id.as_ref(),
+        &folder_name,
+    )?))
+}
+
+#[options("/folders/<_folder_id>")]
+fn folder_options<'a>(_folder_id: FolderID) -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+#[delete("/folders/<folder_id>")]
+fn delete_folder(folder_id: FolderID, connection: Connection, session: Session) -> SeriatimResult {
+    let mut folder = Folder::get_by_id(&connection, &session.data.user_id, &folder_id)?;
+    folder.delete()?;
+
+    Ok(send_success(&folder))
+}
+
+#[options("/folders/<_folder_id>/rename")]
+fn rename_folder_options<'a>(_folder_id: FolderID) -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+#[post("/folders/<folder_id>/rename", format = "json", data = "<params>")]
+fn rename_folder(
+    folder_id: FolderID,
+    connection: Connection,
+    session: Session,
+    params: Json<RenameFolderParams>,
+) -> SeriatimResult {
+    let folder_name = clean_folder_name(&params.name)?;
+
+    let mut folder = Folder::get_by_id(&connection, &session.data.user_id, &folder_id)?;
+    folder.rename(&folder_name)?;
+
+    Ok(send_success(&folder))
+}
+
+#[options("/folders/<_folder_id>/move")]
+fn move_folder_options<'a>(_folder_id: FolderID) -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+#[post("/folders/<folder_id>/move", format = "json", data = "<params>")]
+fn move_folder(
+    folder_id: FolderID,
+    connection: Connection,
+    session: Session,
+    params: Json<MoveFolderParams>,
+) -> SeriatimResult {
+    let user_id = &session.data.user_id;
+    let mut folder = Folder::get_by_id(&connection, user_id, &folder_id)?;
+
+    if let Some(ref parent_id) = params.parent_id {
+        Folder::get_by_id(&connection, user_id, parent_id)?;
+
+        // a folder can't be moved into itself or any of its own subfolders
+        if folder.contains(parent_id)? {
+            return Err(Error::InvalidFolderMove);
+        }
+    }
+
+    folder.move_to(params.parent_id.as_ref())?;
+
+    Ok(send_success(&folder))
+}
+
+#[options("/<_doc_id>/folder")]
+fn document_folder_options<'a>(_doc_id: DocumentID) -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+#[post("/<doc_id>/folder", format = "json", data = "<params>")]
+fn move_document(
+    doc_id: DocumentID,
+    connection: Connection,
+    session: Session,
+    params: Json<MoveDocumentParams>,
+) -> SeriatimResult {
+    let user_id = &session.data.user_id;
+    let mut doc = Document::get_by_id(&connection, &doc_id)?;
+
+    if !doc.is_owned_by(user_id) {
+        return Err(Error::InsufficientPermissions);
+    }
+
+    if let Some(ref folder_id) = params.folder_id {
+        Folder::get_by_id(&connection, user_id, folder_id)?;
+    }
+
+    doc.move_to_folder(params.folder_id.as_ref())?;
+
+    Ok(send_success(&doc.serializable(Some(user_id))?))
+}
+
+pub fn user_routes() -> Vec<Route> {
+    routes![
+        folders_options,
+        list_folders,
+        create_folder,
+        folder_options,
+        delete_folder,
+        rename_folder_options,
+        rename_folder,
+        move_folder_options,
+        move_folder,
+    ]
+}
+
+pub fn document_routes() -> Vec<Route> {
+    routes![document_folder_options, move_document]
+}
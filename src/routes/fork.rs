@@ -0,0 +1,122 @@
+use data::db::Connection;
+use data::diff::MergePreview;
+use data::document::{Document, DocumentID};
+use data::fork::Fork;
+use data::memory::session::Session;
+use data::user::UserID;
+
+use diesel;
+
+use rocket;
+use rocket::Route;
+
+use routes::error::Error;
+use routes::io::{cors_response, send_success, SeriatimResult};
+
+use std::time::SystemTime;
+
+#[derive(Serialize)]
+struct ForkStatus<'a> {
+    fork: Fork<'a>,
+    upstream_available: bool,
+    upstream_version: Option<SystemTime>,
+    upstream_changed: bool,
+}
+
+#[derive(Serialize)]
+struct ForkPreview {
+    upstream_changed: bool,
+    preview: MergePreview,
+}
+
+// only the owner of a copy tracks its upstream
+fn get_owned_fork<'a>(
+    connection: &'a Connection,
+    doc_id: &DocumentID,
+    user_id: &UserID,
+) -> Result<(Document<'a>, Fork<'a>), Error> {
+    let doc = Document::get_by_id(connection, doc_id)?;
+
+    if !doc.is_owned_by(user_id) {
+        return Err(Error::InsufficientPermissions);
+    }
+
+    let fork = Fork::get_by_document(connection, doc_id)?;
+
+    Ok((doc, fork))
+}
+
+// the source may since have been deleted or made private
+fn get_source<'a>(
+    connection: &'a Connection,
+    fork: &Fork,
+    user_id: &UserID,
+) -> Result<Option<Document<'a>>, Error> {
+    let source_id = match fork.get_source_document_id() {
+        Some(s) => s,
+        None => return Ok(None),
+    };
+
+    match Document::get_by_id(connection, &source_id) {
+        Ok(source) => Ok(if source.can_be_viewed_by(user_id) {
+            Some(source)
+        } else {
+            None
+        }),
+        Err(diesel::result::Error::NotFound) => Ok(None),
+        Err(e) => Err(Error::from(e)),
+    }
+}
+
+#[get("/<doc_id>/fork")]
+fn get_fork(doc_id: DocumentID, connection: Connection, session: Session) -> SeriatimResult {
+    let user_id = &session.data.user_id;
+    let (_, fork) = get_owned_fork(&connection, &doc_id, user_id)?;
+    let source = get_source(&connection, &fork, user_id)?;
+
+    Ok(send_success(&ForkStatus {
+        upstream_available: source.is_some(),
+        upstream_version: source.as_ref().map(|s| s.last_modified()),
+        upstream_changed: source
+            .as_ref()
+            .map(|s| fork.upstream_changed(s))
+            .unwrap_or(false),
+        fork,
+    }))
+}
+
+#[get("/<doc_id>/fork/preview")]
+fn preview_fork_merge(
+    doc_id: DocumentID,
+    connection: Connection,
+    session: Session,
+) -> SeriatimResult {
+    let user_id = &session.data.user_id;
+    let (doc, fork) = get_owned_fork(&connection, &doc_id, user_id)?;
+    let source = get_source(&connection, &fork, user_id)?.ok_or(Error::InsufficientPermissions)?;
+
+    Ok(send_success(&ForkPreview {
+        upstream_changed: fork.upstream_changed(&source),
+        preview: fork.preview_merge(&source, &doc)?,
+    }))
+}
+
+#[options("/<_doc_id>/fork/sync")]
+fn sync_fork_options<'a>(_doc_id: DocumentID) -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+#[post("/<doc_id>/fork/sync")]
+fn sync_fork(doc_id: DocumentID, connection: Connection, session: Session) -> SeriatimResult {
+    let user_id = &session.data.user_id;
+    let (_, mut fork) = get_owned_fork(&connection, &doc_id, user_id)?;
+    let source = get_source(&connection, &fork, user_id)?.ok_or(Error::InsufficientPermissions)?;
+
+    fork.sync(&source)?;
+
+    Ok(send_success(&fork))
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![get_fork, preview_fork_merge, sync_fork_options, sync_fork]
+}
@@ -0,0 +1,140 @@
+use data::db::Connection;
+use data::document::{Document, DocumentID};
+use data::memory;
+use data::memory::lease::EditLease;
+use data::memory::session::Session;
+use data::user::{User, UserID};
+
+use rocket;
+use rocket::Route;
+
+use routes::error::Error;
+use routes::io::{cors_response, send_success, SeriatimResult};
+
+use std::time::SystemTime;
+
+#[derive(Serialize)]
+struct LeaseHolder {
+    user_id: UserID,
+    display_name: String,
+    acquired_at: SystemTime,
+    expires_at: SystemTime,
+    this_session: bool,
+}
+
+#[derive(Serialize)]
+struct LeaseStatus {
+    read_only: bool,
+    lease: Option<LeaseHolder>,
+}
+
+fn lease_status(lease: Option<EditLease>, session: &Session) -> LeaseStatus {
+    let this_session = lease
+        .as_ref()
+        .map(|l| l.is_held_by(&session.data.session_id))
+        .unwrap_or(false);
+
+    LeaseStatus {
+        read_only: lease.is_some() && !this_session,
+        lease: lease.map(|l| LeaseHolder {
+            user_id: l.user_id,
+            display_name: l.display_name,
+            acquired_at: l.acquired_at,
+            expires_at: l.expires_at,
+            this_session,
+        }),
+    }
+}
+
+// edits are only turned away while another session holds a lease, so clients
+// that never take one keep working as before
+pub fn check_lease(
+    redis: &memory::redis::Connection,
+    doc_id: &DocumentID,
+    session: &Session,
+) -> Result<(), Error> {
+    match EditLease::get(redis, doc_id)? {
+        Some(ref lease) if !lease.is_held_by(&session.data.session_id) => {
+            Err(Error::EditLeaseHeld(lease.display_name.clone()))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[get("/<doc_id>/lease")]
+fn get_lease(
+    doc_id: DocumentID,
+    connection: Connection,
+    redis: memory::redis::Connection,
+    session: Session,
+) -> SeriatimResult {
+    let doc = Document::get_by_id(&connection, &doc_id)?;
+
+    if !doc.can_be_viewed_by(&session.data.user_id) {
+        return Err(Error::InsufficientPermissions);
+    }
+
+    Ok(send_success(&lease_status(
+        EditLease::get(&redis, &doc_id)?,
+        &session,
+    )))
+}
+
+#[options("/<_doc_id>/lease")]
+fn lease_options<'a>(_doc_id: DocumentID) -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+// acquires the lease, or renews it when called again as a heartbeat
+#[post("/<doc_id>/lease")]
+fn acquire_lease(
+    doc_id: DocumentID,
+    connection: Connection,
+    redis: memory::redis::Connection,
+    session: Session,
+) -> SeriatimResult {
+    let doc = Document::get_by_id(&connection, &doc_id)?;
+
+    if !doc.can_be_edited_by(&session.data.user_id) {
+        return Err(Error::InsufficientPermissions);
+    }
+
+    let u = User::get_by_id(&connection, &session.data.user_id)?;
+    let lease = match EditLease::acquire(
+        &redis,
+        &doc_id,
+        &session.data.user_id,
+        &u.data.display_name,
+        &session.data.session_id,
+    )? {
+        Ok(lease) => lease,
+        Err(holder) => holder,
+    };
+
+    Ok(send_success(&lease_status(Some(lease), &session)))
+}
+
+// holders release their own lease, while owners can break anyone's
+#[delete("/<doc_id>/lease")]
+fn release_lease(
+    doc_id: DocumentID,
+    connection: Connection,
+    redis: memory::redis::Connection,
+    session: Session,
+) -> SeriatimResult {
+    let doc = Document::get_by_id(&connection, &doc_id)?;
+
+    if let Some(lease) = EditLease::get(&redis, &doc_id)? {
+        if !lease.is_held_by(&session.data.session_id) && !doc.is_owned_by(&session.data.user_id) {
+            return Err(Error::InsufficientPermissions);
+        }
+
+        EditLease::release(&redis, &doc_id, &lease.session_id)?;
+    }
+
+    Ok(send_success(&lease_status(None, &session)))
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![get_lease, lease_options, acquire_lease, release_lease]
+}
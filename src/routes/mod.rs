@@ -1,5 +1,17 @@
+pub mod attachment;
+pub mod calendar;
+pub mod directory;
 pub mod document;
+pub mod embed;
 mod error;
+pub mod feed;
+pub mod folder;
+pub mod fork;
 mod io;
+pub mod lease;
 pub mod login;
+pub mod preset;
+pub mod public;
+pub mod share;
 pub mod user;
+pub mod webhook;
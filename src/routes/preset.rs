@@ -0,0 +1,310 @@
+use data::db::Connection;
+use data::document::{Document, DocumentID};
+use data::memory;
+use data::memory::session::Session;
+use data::schema::{StyleProperty, StyleUnit};
+use data::style::validate_style;
+use data::style_preset::{StylePreset, StylePresetID};
+
+use rocket;
+use rocket::Route;
+use rocket_contrib::json::Json;
+
+use routes::error::Error;
+use routes::io::{cors_response, send_success, SeriatimResult};
+use routes::lease::check_lease;
+
+use std::collections::HashSet;
+
+const MAX_PRESET_NAME_LENGTH: usize = 50;
+
+#[derive(Serialize, Deserialize)]
+struct PresetPropertyParams {
+    property: StyleProperty,
+    value_string: Option<String>,
+    value_number: Option<i32>,
+    unit: Option<StyleUnit>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct PresetParams {
+    name: String,
+    #[serde(default)]
+    properties: Vec<PresetPropertyParams>,
+}
+
+// names are limited in characters rather than bytes, so that names in other
+// scripts aren't held to a shorter limit
+fn check_preset_name(name: &str) -> Result<&str, Error> {
+    let trimmed = name.trim();
+    if trimmed == "" || trimmed.chars().count() > MAX_PRESET_NAME_LENGTH {
+        Err(Error::InvalidPresetName)
+    } else {
+        Ok(trimmed)
+    }
+}
+
+// items refer to presets by name, so the name is only stored if it's one of
+// the names the caller allows
+pub fn check_preset_reference<'a>(
+    preset_names: &HashSet<String>,
+    name: &'a str,
+) -> Result<&'a str, Error> {
+    let trimmed = check_preset_name(name)?;
+
+    if preset_names.contains(trimmed) {
+        Ok(trimmed)
+    } else {
+        Err(Error::UnknownPreset(trimmed.to_string()))
+    }
+}
+
+impl PresetParams {
+    fn validate(&self) -> Result<(), Error> {
+        check_preset_name(&self.name)?;
+
+        for p in self.properties.iter() {
+            validate_style(
+                p.property,
+                p.value_string.as_ref().map(|v| v.as_str()),
+                p.value_number,
+                p.unit,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+fn save_preset(preset: &mut StylePreset, params: &PresetParams) -> Result<(), Error> {
+    let p_name = params.name.trim();
+    if preset.data.name != p_name {
+        preset.rename(p_name)?;
+    }
+
+    let properties = params
+        .properties
+        .iter()
+        .map(|p| preset.create_property(p.property, p.value_string.clone(), p.value_number, p.unit))
+        .collect();
+
+    preset.set_properties(properties)?;
+
+    Ok(())
+}
+
+#[options("/presets")]
+fn user_presets_options<'a>() -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+#[get("/presets")]
+fn list_user_presets(connection: Connection, session: Session) -> SeriatimResult {
+    Ok(send_success(&StylePreset::get_by_user(
+        &connection,
+        &session.data.user_id,
+    )?))
+}
+
+#[post("/presets", format = "json", data = "<params>")]
+fn create_user_preset(
+    connection: Connection,
+    session: Session,
+    params: Json<PresetParams>,
+) -> SeriatimResult {
+    params.validate()?;
+
+    let mut preset =
+        StylePreset::create_for_user(&connection, &session.data.user_id, params.name.trim())?;
+    save_preset(&mut preset, &params)?;
+
+    Ok(send_success(&preset))
+}
+
+fn get_user_preset<'a>(
+    connection: &'a Connection,
+    preset_id: &StylePresetID,
+    session: &Session,
+) -> Result<StylePreset<'a>, Error> {
+    let preset = StylePreset::get_by_id(connection, preset_id)?;
+
+    if preset.get_user_id().as_ref() == Some(&session.data.user_id) {
+        Ok(preset)
+    } else {
+        Err(Error::InsufficientPermissions)
+    }
+}
+
+#[options("/presets/<_preset_id>")]
+fn user_preset_options<'a>(_preset_id: StylePresetID) -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+#[post("/presets/<preset_id>", format = "json", data = "<params>")]
+fn update_user_preset(
+    preset_id: StylePresetID,
+    connection: Connection,
+    session: Session,
+    params: Json<PresetParams>,
+) -> SeriatimResult {
+    params.validate()?;
+
+    let mut preset = get_user_preset(&connection, &preset_id, &session)?;
+    save_preset(&mut preset, &params)?;
+
+    Ok(send_success(&preset))
+}
+
+#[delete("/presets/<preset_id>")]
+fn delete_user_preset(
+    preset_id: StylePresetID,
+    connection: Connection,
+    session: Session,
+) -> SeriatimResult {
+    let mut preset = get_user_preset(&connection, &preset_id, &session)?;
+    preset.delete()?;
+
+    Ok(send_success(&preset))
+}
+
+fn get_document_preset<'a>(
+    connection: &'a Connection,
+    doc: &Document,
+    preset_id: &StylePresetID,
+) -> Result<StylePreset<'a>, Error> {
+    let preset = StylePreset::get_by_id(connection, preset_id)?;
+
+    if preset.get_document_id() == Some(doc.get_id()) {
+        Ok(preset)
+    } else {
+        Err(Error::InsufficientPermissions)
+    }
+}
+
+#[options("/<_doc_id>/presets")]
+fn document_presets_options<'a>(_doc_id: DocumentID) -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+#[get("/<doc_id>/presets")]
+fn list_document_presets(
+    doc_id: DocumentID,
+    connection: Connection,
+    session: Session,
+) -> SeriatimResult {
+    let doc = Document::get_by_id(&connection, &doc_id)?;
+
+    if !doc.can_be_viewed_by(&session.data.user_id) {
+        return Err(Error::InsufficientPermissions);
+    }
+
+    Ok(send_success(&StylePreset::get_available(
+        &connection,
+        &doc_id,
+        &doc.get_owner_id(),
+    )?))
+}
+
+#[post("/<doc_id>/presets", format = "json", data = "<params>")]
+fn create_document_preset(
+    doc_id: DocumentID,
+    connection: Connection,
+    redis: memory::redis::Connection,
+    session: Session,
+    params: Json<PresetParams>,
+) -> SeriatimResult {
+    let mut doc = Document::get_by_id(&connection, &doc_id)?;
+
+    if !doc.can_be_edited_by(&session.data.user_id) {
+        return Err(Error::InsufficientPermissions);
+    }
+
+    check_lease(&redis, &doc_id, &session)?;
+
+    params.validate()?;
+
+    let mut preset = StylePreset::create_for_document(&connection, &doc_id, params.name.trim())?;
+    save_preset(&mut preset, &params)?;
+    doc.touch()?;
+
+    Ok(send_success(&preset))
+}
+
+#[options("/<_doc_id>/presets/<_preset_id>")]
+fn document_preset_options<'a>(
+    _doc_id: DocumentID,
+    _preset_id: StylePresetID,
+) -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+#[post("/<doc_id>/presets/<preset_id>", format = "json", data = "<params>")]
+fn update_document_preset(
+    doc_id: DocumentID,
+    preset_id: StylePresetID,
+    connection: Connection,
+    redis: memory::redis::Connection,
+    session: Session,
+    params: Json<PresetParams>,
+) -> SeriatimResult {
+    let mut doc = Document::get_by_id(&connection, &doc_id)?;
+
+    if !doc.can_be_edited_by(&session.data.user_id) {
+        return Err(Error::InsufficientPermissions);
+    }
+
+    check_lease(&redis, &doc_id, &session)?;
+
+    params.validate()?;
+
+    let mut preset = get_document_preset(&connection, &doc, &preset_id)?;
+    save_preset(&mut preset, &params)?;
+    doc.touch()?;
+
+    Ok(send_success(&preset))
+}
+
+#[delete("/<doc_id>/presets/<preset_id>")]
+fn delete_document_preset(
+    doc_id: DocumentID,
+    preset_id: StylePresetID,
+    connection: Connection,
+    redis: memory::redis::Connection,
+    session: Session,
+) -> SeriatimResult {
+    let mut doc = Document::get_by_id(&connection, &doc_id)?;
+
+    if !doc.can_be_edited_by(&session.data.user_id) {
+        return Err(Error::InsufficientPermissions);
+    }
+
+    check_lease(&redis, &doc_id, &session)?;
+
+    let mut preset = get_document_preset(&connection, &doc, &preset_id)?;
+    preset.delete()?;
+    doc.touch()?;
+
+    Ok(send_success(&preset))
+}
+
+pub fn user_routes() -> Vec<Route> {
+    routes![
+        user_presets_options,
+        list_user_presets,
+        create_user_preset,
+        user_preset_options,
+        update_user_preset,
+        delete_user_preset,
+    ]
+}
+
+pub fn document_routes() -> Vec<Route> {
+    routes![
+        document_presets_options,
+        list_document_presets,
+        create_document_preset,
+        document_preset_options,
+        update_document_preset,
+        delete_document_preset,
+    ]
+}
@@ -0,0 +1,115 @@
+use config::SeriatimConfig;
+
+use data::db::Connection;
+use data::document::{Document, DocumentID};
+
+use diesel;
+
+use render::html::{build_outline, describe_outline, render_document_page, DocumentPage};
+use render::sitemap::{render_sitemap, SitemapEntry};
+
+use rocket::http::ContentType;
+use rocket::response::content::{Content, Html};
+use rocket::{Route, State};
+
+use routes::error::Error;
+
+use url;
+
+// the sitemap protocol allows at most 50,000 URLs per file
+const MAX_SITEMAP_ENTRIES: i64 = 50000;
+
+pub fn page_url(cfg: &SeriatimConfig, doc_id: &DocumentID) -> String {
+    cfg.domain.clone() + "public/" + &doc_id.json_str()
+}
+
+pub fn oembed_url(cfg: &SeriatimConfig, page_url: &str) -> String {
+    let encoded: String = url::form_urlencoded::byte_serialize(page_url.as_bytes()).collect();
+    cfg.domain.clone() + "oembed?url=" + &encoded
+}
+
+// private and missing documents are indistinguishable from outside
+pub fn get_public_document<'a>(
+    connection: &'a Connection,
+    doc_id: &DocumentID,
+) -> Result<Option<Document<'a>>, Error> {
+    match Document::get_by_id(connection, doc_id) {
+        Ok(doc) => Ok(if doc.can_be_viewed_anonymously() {
+            Some(doc)
+        } else {
+            None
+        }),
+        Err(diesel::result::Error::NotFound) => Ok(None),
+        Err(e) => Err(Error::from(e)),
+    }
+}
+
+pub fn load_page(doc: &Document, cfg: &SeriatimConfig) -> Result<DocumentPage, Error> {
+    let title = doc.get_title()?;
+    let items = build_outline(
+        &doc.get_items()?,
+        &doc.get_root()?.get_id(),
+        doc.get_serialized_toc_id().as_ref(),
+        doc.data.numbering_scheme,
+        doc.get_effective_styles()?,
+    );
+
+    let title = if title.trim() == "" {
+        "Untitled Document".to_string()
+    } else {
+        title
+    };
+
+    let url = page_url(cfg, &doc.get_id());
+
+    Ok(DocumentPage {
+        description: describe_outline(&items).unwrap_or_else(|| title.clone()),
+        title,
+        oembed_url: oembed_url(cfg, &url),
+        url,
+        modified_at: doc.last_modified(),
+        items,
+    })
+}
+
+#[get("/public/<doc_id>")]
+fn document_page(
+    doc_id: DocumentID,
+    connection: Connection,
+    cfg: State<SeriatimConfig>,
+) -> Result<Option<Html<String>>, Error> {
+    let doc = match get_public_document(&connection, &doc_id)? {
+        Some(d) => d,
+        None => return Ok(None),
+    };
+
+    Ok(Some(Html(render_document_page(&load_page(&doc, &cfg)?))))
+}
+
+#[get("/sitemap.xml")]
+fn sitemap(connection: Connection, cfg: State<SeriatimConfig>) -> Result<Content<String>, Error> {
+    let entries: Vec<SitemapEntry> = Document::get_public(&connection, MAX_SITEMAP_ENTRIES)?
+        .iter()
+        .map(|doc| SitemapEntry {
+            url: page_url(&cfg, &doc.get_id()),
+            last_modified: doc.last_modified(),
+        })
+        .collect();
+
+    Ok(Content(ContentType::XML, render_sitemap(&entries)))
+}
+
+#[get("/robots.txt")]
+fn robots(cfg: State<SeriatimConfig>) -> Content<String> {
+    Content(
+        ContentType::Plain,
+        format!(
+            "User-agent: *\nAllow: /public/\nDisallow: /\nSitemap: {}sitemap.xml\n",
+            cfg.domain
+        ),
+    )
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![document_page, sitemap, robots]
+}
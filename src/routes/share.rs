@@ -0,0 +1,113 @@
+use data::db::Connection;
+use data::document::{Document, DocumentID};
+use data::item::ItemID;
+use data::memory::session::Session;
+use data::share::{SubtreeShare, SubtreeShareID};
+use data::user::UserID;
+
+use rocket;
+use rocket::Route;
+
+use routes::error::Error;
+use routes::io::{cors_response, send_success, SeriatimResult};
+
+fn get_owned_document<'a>(
+    connection: &'a Connection,
+    doc_id: &DocumentID,
+    user_id: &UserID,
+) -> Result<Document<'a>, Error> {
+    let doc = Document::get_by_id(connection, doc_id)?;
+
+    if !doc.is_owned_by(user_id) {
+        return Err(Error::InsufficientPermissions);
+    }
+
+    Ok(doc)
+}
+
+#[get("/<doc_id>/shares")]
+fn list_shares(doc_id: DocumentID, connection: Connection, session: Session) -> SeriatimResult {
+    get_owned_document(&connection, &doc_id, &session.data.user_id)?;
+
+    Ok(send_success(&SubtreeShare::get_by_document(
+        &connection,
+        &doc_id,
+    )?))
+}
+
+#[options("/<_doc_id>/items/<_item_id>/share")]
+fn create_share_options<'a>(
+    _doc_id: DocumentID,
+    _item_id: ItemID,
+) -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+#[post("/<doc_id>/items/<item_id>/share")]
+fn create_share(
+    doc_id: DocumentID,
+    item_id: ItemID,
+    connection: Connection,
+    session: Session,
+) -> SeriatimResult {
+    let doc = get_owned_document(&connection, &doc_id, &session.data.user_id)?;
+
+    // fails the same way a share would, for items outside the document
+    doc.serialize_subtree(&item_id)?;
+
+    Ok(send_success(&SubtreeShare::create(
+        &connection,
+        &doc_id,
+        &item_id,
+    )?))
+}
+
+#[options("/<_doc_id>/shares/<_share_id>")]
+fn share_options<'a>(
+    _doc_id: DocumentID,
+    _share_id: SubtreeShareID,
+) -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+#[delete("/<doc_id>/shares/<share_id>")]
+fn revoke_share(
+    doc_id: DocumentID,
+    share_id: SubtreeShareID,
+    connection: Connection,
+    session: Session,
+) -> SeriatimResult {
+    get_owned_document(&connection, &doc_id, &session.data.user_id)?;
+
+    let share = SubtreeShare::get_by_id(&connection, &share_id)?;
+    if share.get_document_id() != doc_id {
+        return Err(Error::InsufficientPermissions);
+    }
+
+    share.revoke()?;
+
+    Ok(send_success(&share))
+}
+
+// shares are viewed without logging in, by anyone who has the link
+#[get("/<share_id>")]
+fn get_shared_subtree(share_id: SubtreeShareID, connection: Connection) -> SeriatimResult {
+    let share = SubtreeShare::get_by_id(&connection, &share_id)?;
+    let doc = Document::get_by_id(&connection, &share.get_document_id())?;
+
+    Ok(send_success(&doc.serialize_subtree(&share.get_item_id())?))
+}
+
+pub fn document_routes() -> Vec<Route> {
+    routes![
+        list_shares,
+        create_share_options,
+        create_share,
+        share_options,
+        revoke_share
+    ]
+}
+
+pub fn share_routes() -> Vec<Route> {
+    routes![get_shared_subtree]
+}
@@ -1,19 +1,28 @@
+use config::SeriatimConfig;
+
+use data::calendar::CalendarToken;
+use data::category::Category;
 use data::db::Connection;
-use data::document::SerializableDocument;
+use data::document::{Document, DocumentID, SerializableDocument};
+use data::folder::{Folder, FolderID};
+use data::item::Item;
 use data::memory::session::Session;
 use data::user::User;
 
+use diesel;
 use diesel::result::QueryResult;
 
 use oauth::LoginMethod;
 
-use rocket::{self, Route};
+use rocket::{self, Route, State};
 use rocket_contrib::json::Json;
 
 use routes::error::Error;
 use routes::io::{cors_response, send_success, SeriatimResult};
 
 use std;
+use std::collections::HashMap;
+use std::str::FromStr;
 
 #[get("/current")]
 fn current_user(connection: Connection, mut session: Session) -> SeriatimResult {
@@ -23,10 +32,28 @@ fn current_user(connection: Connection, mut session: Session) -> SeriatimResult
     Ok(send_success(&u))
 }
 
-#[get("/documents")]
-fn list_documents(connection: Connection, session: Session) -> SeriatimResult {
+// "root" lists the documents outside of any folder
+const ROOT_FOLDER: &'static str = "root";
+
+#[get("/documents?<folder>")]
+fn list_documents(
+    folder: Option<String>,
+    connection: Connection,
+    session: Session,
+) -> SeriatimResult {
     let u = User::get_by_id(&connection, &session.data.user_id)?;
-    let docs = u.get_documents()?;
+
+    let docs = match folder {
+        None => u.get_documents()?,
+        Some(ref f) if f == ROOT_FOLDER => {
+            Document::get_by_user_in_folder(&connection, &session.data.user_id, None)?
+        }
+        Some(ref f) => {
+            let folder_id = FolderID::from_str(f)?;
+            Folder::get_by_id(&connection, &session.data.user_id, &folder_id)?;
+            Document::get_by_user_in_folder(&connection, &session.data.user_id, Some(&folder_id))?
+        }
+    };
 
     let serializable_docs = docs
         .iter()
@@ -36,9 +63,206 @@ fn list_documents(connection: Connection, session: Session) -> SeriatimResult {
     Ok(send_success(&serializable_docs))
 }
 
+#[derive(Serialize)]
+struct AgendaEntry<'a> {
+    document_title: String,
+    item: Item<'a>,
+}
+
+#[get("/agenda")]
+fn agenda(connection: Connection, session: Session) -> SeriatimResult {
+    let tasks = Item::get_open_tasks_for_user(&connection, &session.data.user_id)?;
+    let mut titles = HashMap::<DocumentID, String>::new();
+
+    let mut entries = Vec::new();
+    for item in tasks.into_iter() {
+        let doc_id = DocumentID::from_uuid(item.data.document_id);
+
+        if !titles.contains_key(&doc_id) {
+            let doc = Document::get_by_id(&connection, &doc_id)?;
+            titles.insert(doc_id.clone(), doc.get_title()?);
+        }
+
+        entries.push(AgendaEntry {
+            document_title: titles.get(&doc_id).cloned().unwrap_or_default(),
+            item,
+        });
+    }
+
+    Ok(send_success(&entries))
+}
+
+#[derive(Serialize)]
+struct CalendarFeed {
+    token: String,
+    url: String,
+}
+
+#[options("/calendar")]
+fn calendar_options<'a>() -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+#[get("/calendar")]
+fn get_calendar(
+    connection: Connection,
+    session: Session,
+    cfg: State<SeriatimConfig>,
+) -> SeriatimResult {
+    let token = match CalendarToken::get_by_user(&connection, &session.data.user_id) {
+        Ok(t) => Some(t),
+        Err(diesel::result::Error::NotFound) => None,
+        Err(e) => return Err(Error::from(e)),
+    };
+
+    Ok(send_success(&token.map(|t| CalendarFeed {
+        token: t.get_id().json_str(),
+        url: cfg.domain.clone() + "calendar/" + &t.get_id().json_str() + ".ics",
+    })))
+}
+
+#[post("/calendar")]
+fn reset_calendar(
+    connection: Connection,
+    session: Session,
+    cfg: State<SeriatimConfig>,
+) -> SeriatimResult {
+    let token = CalendarToken::reset_for_user(&connection, &session.data.user_id)?;
+
+    Ok(send_success(&CalendarFeed {
+        token: token.get_id().json_str(),
+        url: cfg.domain.clone() + "calendar/" + &token.get_id().json_str() + ".ics",
+    }))
+}
+
+#[delete("/calendar")]
+fn revoke_calendar(connection: Connection, session: Session) -> SeriatimResult {
+    CalendarToken::revoke_for_user(&connection, &session.data.user_id)?;
+
+    Ok(send_success(&()))
+}
+
+#[derive(Deserialize)]
+struct RenameCategoryParams {
+    from: String,
+    to: String,
+}
+
+#[derive(Deserialize)]
+struct MergeCategoriesParams {
+    from: Vec<String>,
+    into: String,
+}
+
+// the trash is a category too, but emptying or filling it wholesale from here
+// would be far too easy to do by accident; the trimmed name is handed back so
+// that the same value gets stored as was checked
+fn check_category_name(name: &str) -> Result<&str, Error> {
+    let trimmed = name.trim();
+    let sanitized = Category::sanitize_name(trimmed);
+
+    if sanitized == "" || sanitized == Category::TRASH {
+        Err(Error::InvalidCategoryName)
+    } else {
+        Ok(trimmed)
+    }
+}
+
+#[get("/categories")]
+fn list_categories(connection: Connection, session: Session) -> SeriatimResult {
+    Ok(send_success(&Category::get_summaries_for_user(
+        &connection,
+        &session.data.user_id,
+    )?))
+}
+
+#[options("/categories/rename")]
+fn rename_category_options<'a>() -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+#[post("/categories/rename", format = "json", data = "<params>")]
+fn rename_category(
+    connection: Connection,
+    session: Session,
+    params: Json<RenameCategoryParams>,
+) -> SeriatimResult {
+    let user_id = &session.data.user_id;
+    let from = check_category_name(&params.from)?;
+    let to = check_category_name(&params.to)?;
+
+    if !Category::exists_for_user(&connection, user_id, from)? {
+        return Err(Error::from(diesel::result::Error::NotFound));
+    }
+
+    if Category::sanitize_name(from) != Category::sanitize_name(to)
+        && Category::exists_for_user(&connection, user_id, to)?
+    {
+        return Err(Error::CategoryExists);
+    }
+
+    Category::rename_for_user(&connection, user_id, from, to)?;
+
+    Ok(send_success(&Category::get_summaries_for_user(
+        &connection,
+        user_id,
+    )?))
+}
+
+#[options("/categories/merge")]
+fn merge_categories_options<'a>() -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+#[post("/categories/merge", format = "json", data = "<params>")]
+fn merge_categories(
+    connection: Connection,
+    session: Session,
+    params: Json<MergeCategoriesParams>,
+) -> SeriatimResult {
+    let user_id = &session.data.user_id;
+    let into = check_category_name(&params.into)?;
+    let from = params
+        .from
+        .iter()
+        .map(|name| check_category_name(name))
+        .collect::<Result<Vec<&str>, Error>>()?;
+
+    for name in from {
+        Category::rename_for_user(&connection, user_id, name, into)?;
+    }
+
+    Ok(send_success(&Category::get_summaries_for_user(
+        &connection,
+        user_id,
+    )?))
+}
+
+#[options("/categories/<_name>")]
+fn category_options<'a>(_name: String) -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+#[delete("/categories/<name>")]
+fn delete_category(connection: Connection, session: Session, name: String) -> SeriatimResult {
+    let user_id = &session.data.user_id;
+    let name = check_category_name(&name)?;
+
+    if Category::delete_for_user(&connection, user_id, name)? == 0 {
+        return Err(Error::from(diesel::result::Error::NotFound));
+    }
+
+    Ok(send_success(&Category::get_summaries_for_user(
+        &connection,
+        user_id,
+    )?))
+}
+
 #[derive(Deserialize)]
 struct UpdateUserParams {
     display_name: String,
+    #[serde(default)]
+    public_profile: Option<bool>,
 }
 
 #[options("/update")]
@@ -55,6 +279,10 @@ fn update_user(
     let mut u = User::get_by_id(&con, &session.data.user_id)?;
     u.update_display_name(&update_params.display_name)?;
 
+    if let Some(p_public_profile) = update_params.public_profile {
+        u.set_public_profile(p_public_profile)?;
+    }
+
     Ok(send_success(&u))
 }
 
@@ -78,6 +306,18 @@ pub fn routes() -> Vec<Route> {
     routes![
         current_user,
         list_documents,
+        agenda,
+        calendar_options,
+        get_calendar,
+        reset_calendar,
+        revoke_calendar,
+        list_categories,
+        rename_category_options,
+        rename_category,
+        merge_categories_options,
+        merge_categories,
+        category_options,
+        delete_category,
         update_options,
         update_user,
         remove_login,
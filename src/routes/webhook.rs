@@ -0,0 +1,138 @@
+use data::db::Connection;
+use data::document::{Document, DocumentID};
+use data::memory::session::Session;
+use data::webhook::{Webhook, WebhookID};
+use data::webhook_delivery::WebhookDelivery;
+
+use rocket;
+use rocket::Route;
+use rocket_contrib::json::Json;
+
+use routes::error::Error;
+use routes::io::{cors_response, send_success, SeriatimResult};
+
+use webhooks::check_destination;
+
+#[derive(Serialize, Deserialize)]
+struct CreateWebhookParams {
+    url: String,
+    secret: Option<String>,
+}
+
+#[derive(Serialize)]
+struct CreatedWebhook<'a> {
+    webhook: Webhook<'a>,
+    secret: String,
+}
+
+fn validate_url(url: &str) -> Result<(), Error> {
+    check_destination(url)
+        .map(|_| ())
+        .map_err(|_| Error::InvalidWebhookURL)
+}
+
+fn get_owned_document<'a>(
+    connection: &'a Connection,
+    doc_id: &DocumentID,
+    session: &Session,
+) -> Result<Document<'a>, Error> {
+    let doc = Document::get_by_id(connection, doc_id)?;
+
+    if doc.is_owned_by(&session.data.user_id) {
+        Ok(doc)
+    } else {
+        Err(Error::InsufficientPermissions)
+    }
+}
+
+#[options("/<_doc_id>/webhooks")]
+fn webhooks_options<'a>(_doc_id: DocumentID) -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+#[get("/<doc_id>/webhooks")]
+fn list_webhooks(doc_id: DocumentID, connection: Connection, session: Session) -> SeriatimResult {
+    get_owned_document(&connection, &doc_id, &session)?;
+
+    Ok(send_success(&Webhook::get_by_document(
+        &connection,
+        &doc_id,
+    )?))
+}
+
+#[post("/<doc_id>/webhooks", format = "json", data = "<params>")]
+fn create_webhook(
+    doc_id: DocumentID,
+    connection: Connection,
+    session: Session,
+    params: Json<CreateWebhookParams>,
+) -> SeriatimResult {
+    get_owned_document(&connection, &doc_id, &session)?;
+    validate_url(&params.url)?;
+
+    let secret = match params.secret {
+        Some(ref s) if s != "" => s.clone(),
+        _ => Webhook::generate_secret(),
+    };
+
+    let webhook = Webhook::create(
+        &connection,
+        &doc_id,
+        &session.data.user_id,
+        &params.url,
+        &secret,
+    )?;
+
+    Ok(send_success(&CreatedWebhook { webhook, secret }))
+}
+
+#[options("/<_doc_id>/webhooks/<_webhook_id>")]
+fn webhook_options<'a>(
+    _doc_id: DocumentID,
+    _webhook_id: WebhookID,
+) -> rocket::response::Response<'a> {
+    cors_response::<'a>()
+}
+
+#[delete("/<doc_id>/webhooks/<webhook_id>")]
+fn delete_webhook(
+    doc_id: DocumentID,
+    webhook_id: WebhookID,
+    connection: Connection,
+    session: Session,
+) -> SeriatimResult {
+    get_owned_document(&connection, &doc_id, &session)?;
+
+    let mut webhook = Webhook::get_by_id(&connection, &doc_id, &webhook_id)?;
+    webhook.delete()?;
+
+    Ok(send_success(&webhook))
+}
+
+#[get("/<doc_id>/webhooks/<webhook_id>/deliveries")]
+fn list_deliveries(
+    doc_id: DocumentID,
+    webhook_id: WebhookID,
+    connection: Connection,
+    session: Session,
+) -> SeriatimResult {
+    get_owned_document(&connection, &doc_id, &session)?;
+
+    let webhook = Webhook::get_by_id(&connection, &doc_id, &webhook_id)?;
+
+    Ok(send_success(&WebhookDelivery::get_by_webhook(
+        &connection,
+        &webhook.get_id(),
+    )?))
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![
+        webhooks_options,
+        list_webhooks,
+        create_webhook,
+        webhook_options,
+        delete_webhook,
+        list_deliveries,
+    ]
+}
@@ -0,0 +1,588 @@
+use data::db::{Connection, PgPool};
+use data::document::DocumentID;
+use data::memory;
+use data::memory::delivery_queue::QueuedDelivery;
+use data::memory::redis::RedisPool;
+use data::schema::WebhookEvent;
+use data::user::UserID;
+use data::webhook::Webhook;
+use data::webhook_delivery::{DeliveryID, WebhookDelivery};
+
+use diesel;
+use diesel::result::QueryResult;
+
+use hmacsha1;
+
+use native_tls::TlsConnector;
+
+use r2d2_redis::redis::RedisResult;
+
+use serde_json;
+
+use std::io;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use url::{Host, Position, Url};
+
+const POLL_INTERVAL_SECS: u64 = 1;
+const REQUEST_TIMEOUT_SECS: u64 = 10;
+const DELIVERIES_PER_POLL: isize = 20;
+
+#[derive(Serialize)]
+struct Payload<'a> {
+    delivery_id: &'a DeliveryID,
+    event: WebhookEvent,
+    document_id: &'a DocumentID,
+    actor_id: &'a UserID,
+    timestamp: SystemTime,
+    details: &'a serde_json::Value,
+}
+
+fn build_payload(
+    delivery_id: &DeliveryID,
+    event: WebhookEvent,
+    p_document_id: &DocumentID,
+    p_actor_id: &UserID,
+    details: &serde_json::Value,
+) -> serde_json::Result<String> {
+    serde_json::to_string(&Payload {
+        delivery_id,
+        event,
+        document_id: p_document_id,
+        actor_id: p_actor_id,
+        timestamp: SystemTime::now(),
+        details,
+    })
+}
+
+pub fn sign(secret: &str, body: &str) -> String {
+    let hashed = hmacsha1::hmac_sha1(secret.as_bytes(), body.as_bytes());
+
+    "sha1=".to_string()
+        + &hashed
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<String>()
+}
+
+// deliveries are sent from inside our own network, so they must never reach it
+fn is_public_address(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let octets = v4.octets();
+
+            !(v4.is_private()
+                || v4.is_loopback()
+                || v4.is_link_local()
+                || v4.is_unspecified()
+                || v4.is_broadcast()
+                || v4.is_multicast()
+                || v4.is_documentation()
+                || octets[0] == 0
+                || (octets[0] == 100 && (octets[1] & 0xc0) == 64))
+        }
+        IpAddr::V6(v6) => {
+            let first = v6.segments()[0];
+
+            if v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80
+            {
+                return false;
+            }
+
+            match v6.to_ipv4() {
+                Some(v4) => is_public_address(&IpAddr::V4(v4)),
+                None => true,
+            }
+        }
+    }
+}
+
+// a destination's host is resolved once, and deliveries connect to exactly the
+// addresses that were checked rather than resolving it again
+pub struct Destination {
+    url: Url,
+    addresses: Vec<SocketAddr>,
+}
+
+// checked when a webhook is registered and again before every delivery, since
+// the host may have been pointed somewhere else in the meantime
+pub fn check_destination(url: &str) -> Result<Destination, String> {
+    let parsed = Url::parse(url).map_err(|e| e.to_string())?;
+
+    if parsed.scheme() != "http" && parsed.scheme() != "https" {
+        return Err(format!("Unsupported scheme {}", parsed.scheme()));
+    }
+
+    let port = parsed.port_or_known_default().unwrap_or(80);
+    let addresses: Vec<SocketAddr> = match parsed.host() {
+        Some(Host::Domain(domain)) => (domain, port)
+            .to_socket_addrs()
+            .map_err(|e| e.to_string())?
+            .collect(),
+        Some(Host::Ipv4(ip)) => vec![SocketAddr::new(IpAddr::V4(ip), port)],
+        Some(Host::Ipv6(ip)) => vec![SocketAddr::new(IpAddr::V6(ip), port)],
+        None => Vec::new(),
+    };
+
+    if addresses.is_empty() {
+        return Err("The webhook host could not be resolved".to_string());
+    }
+
+    match addresses.iter().find(|a| !is_public_address(&a.ip())) {
+        Some(a) => Err(format!("{} is not a public address", a.ip())),
+        None => Ok(Destination {
+            url: parsed,
+            addresses,
+        }),
+    }
+}
+
+fn queue_deliveries(
+    connection: &Connection,
+    redis: &memory::redis::Connection,
+    p_document_id: &DocumentID,
+    p_actor_id: &UserID,
+    event: WebhookEvent,
+    details: serde_json::Value,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for webhook in Webhook::get_by_document(connection, p_document_id)?.into_iter() {
+        let delivery_id = DeliveryID::generate();
+
+        let payload = build_payload(&delivery_id, event, p_document_id, p_actor_id, &details)?;
+
+        // the URL and secret are looked up again when the delivery is sent, so
+        // deleting the webhook stops anything still queued for it
+        QueuedDelivery {
+            delivery_id,
+            webhook_id: webhook.get_id(),
+            document_id: p_document_id.clone(),
+            event,
+            payload,
+            attempt: 1,
+        }
+        .enqueue(redis, Duration::from_secs(0))?;
+    }
+
+    Ok(())
+}
+
+// events are queued once their change has been saved, so a failure here is
+// logged rather than turning a change that was made into an error
+pub fn enqueue_event(
+    connection: &Connection,
+    redis: &memory::redis::Connection,
+    p_document_id: &DocumentID,
+    p_actor_id: &UserID,
+    event: WebhookEvent,
+    details: serde_json::Value,
+) {
+    if let Err(e) = queue_deliveries(connection, redis, p_document_id, p_actor_id, event, details) {
+        println!(
+            "Could not queue {} webhooks for document {}: {}",
+            event.to_string(),
+            p_document_id,
+            e
+        );
+    }
+}
+
+fn send(url: &str, secret: &str, delivery: &QueuedDelivery) -> (Option<i32>, Option<String>) {
+    match check_destination(url) {
+        Ok(destination) => post(&destination, secret, delivery),
+        Err(e) => (None, Some(e)),
+    }
+}
+
+fn connect(destination: &Destination) -> io::Result<TcpStream> {
+    let timeout = Duration::from_secs(REQUEST_TIMEOUT_SECS);
+    let mut last_error = io::Error::new(io::ErrorKind::NotFound, "No addresses to connect to");
+
+    for address in destination.addresses.iter() {
+        match TcpStream::connect_timeout(address, timeout) {
+            Ok(stream) => {
+                stream.set_read_timeout(Some(timeout))?;
+                stream.set_write_timeout(Some(timeout))?;
+                return Ok(stream);
+            }
+            Err(e) => last_error = e,
+        }
+    }
+
+    Err(last_error)
+}
+
+// only the status matters, and redirects are never followed, since they could
+// lead past the destination check
+fn exchange<S: Read + Write>(mut stream: S, head: &str, body: &str) -> io::Result<i32> {
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(body.as_bytes())?;
+    stream.flush()?;
+
+    let mut status_line = String::new();
+    BufReader::new(stream).read_line(&mut status_line)?;
+
+    status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|s| s.parse::<i32>().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Malformed response"))
+}
+
+fn request(destination: &Destination, secret: &str, delivery: &QueuedDelivery) -> io::Result<i32> {
+    let host = destination.url.host_str().unwrap_or("");
+    let head = format!(
+        "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nX-Seriatim-Event: {}\r\nX-Seriatim-Delivery: {}\r\nX-Seriatim-Signature: {}\r\nConnection: close\r\n\r\n",
+        &destination.url[Position::BeforePath..Position::AfterQuery],
+        &destination.url[Position::BeforeHost..Position::AfterPort],
+        delivery.payload.len(),
+        delivery.event.to_string(),
+        delivery.delivery_id.json_str(),
+        sign(secret, &delivery.payload),
+    );
+
+    let stream = connect(destination)?;
+    if destination.url.scheme() == "https" {
+        // the certificate is still verified against the host name
+        let connector = TlsConnector::new().map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        let tls = connector
+            .connect(host.trim_start_matches('[').trim_end_matches(']'), stream)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+
+        exchange(tls, &head, &delivery.payload)
+    } else {
+        exchange(stream, &head, &delivery.payload)
+    }
+}
+
+fn post(
+    destination: &Destination,
+    secret: &str,
+    delivery: &QueuedDelivery,
+) -> (Option<i32>, Option<String>) {
+    match request(destination, secret, delivery) {
+        Ok(status) if status >= 200 && status < 300 => (Some(status), None),
+        Ok(status) => (
+            Some(status),
+            Some(format!("Unexpected response status {}", status)),
+        ),
+        Err(e) => (None, Some(e.to_string())),
+    }
+}
+
+fn record(
+    connection: &Connection,
+    delivery: &QueuedDelivery,
+    result: &(Option<i32>, Option<String>),
+) -> QueryResult<()> {
+    WebhookDelivery::record(
+        connection,
+        &delivery.webhook_id,
+        &delivery.delivery_id,
+        delivery.event,
+        delivery.attempt,
+        result.0,
+        result.1.as_ref().map(|e| e.as_str()),
+    )?;
+
+    Ok(())
+}
+
+fn process_due(pg_pool: &PgPool, redis_pool: &RedisPool) -> Result<(), Box<dyn std::error::Error>> {
+    let redis = memory::redis::Connection {
+        redis_connection: Mutex::new(redis_pool.get()?),
+    };
+    let connection = Connection {
+        pg_connection: pg_pool.get()?,
+    };
+
+    for delivery in QueuedDelivery::take_due(&redis, DELIVERIES_PER_POLL)?.into_iter() {
+        let webhook =
+            match Webhook::get_by_id(&connection, &delivery.document_id, &delivery.webhook_id) {
+                Ok(w) => w,
+                Err(diesel::result::Error::NotFound) => {
+                    println!(
+                        "Dropping webhook delivery {}, since its webhook was deleted",
+                        delivery.delivery_id
+                    );
+                    continue;
+                }
+                Err(e) => {
+                    println!(
+                        "Could not load the webhook for delivery {}: {}",
+                        delivery.delivery_id, e
+                    );
+                    retry(delivery, &redis)?;
+                    continue;
+                }
+            };
+
+        let result = send(&webhook.data.url, &webhook.data.secret, &delivery);
+
+        if let Err(e) = record(&connection, &delivery, &result) {
+            println!(
+                "Could not record webhook delivery {}: {}",
+                delivery.delivery_id, e
+            );
+        }
+
+        if result.1.is_some() {
+            retry(delivery, &redis)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn retry(delivery: QueuedDelivery, redis: &memory::redis::Connection) -> RedisResult<()> {
+    let delivery_id = delivery.delivery_id.clone();
+
+    if !delivery.retry(redis)? {
+        println!("Giving up on webhook delivery {}", delivery_id);
+    }
+
+    Ok(())
+}
+
+pub fn spawn_worker(pg_pool: PgPool, redis_pool: RedisPool) -> thread::JoinHandle<()> {
+    thread::spawn(move || loop {
+        if let Err(e) = process_due(&pg_pool, &redis_pool) {
+            println!("Webhook delivery error: {}", e);
+        }
+
+        thread::sleep(Duration::from_secs(POLL_INTERVAL_SECS));
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use data::webhook::WebhookID;
+
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    struct Request {
+        headers: Vec<(String, String)>,
+        body: String,
+    }
+
+    impl Request {
+        fn header(&self, name: &str) -> Option<&str> {
+            self.headers
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.as_str())
+        }
+    }
+
+    // answers a single request with the given status and hands it back
+    fn listen(status: &'static str) -> (String, mpsc::Receiver<Request>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut headers = Vec::new();
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            loop {
+                line.clear();
+                reader.read_line(&mut line).unwrap();
+                let trimmed = line.trim_end();
+                if trimmed.is_empty() {
+                    break;
+                }
+
+                let mut parts = trimmed.splitn(2, ':');
+                headers.push((
+                    parts.next().unwrap_or("").trim().to_string(),
+                    parts.next().unwrap_or("").trim().to_string(),
+                ));
+            }
+
+            let length = headers
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case("content-length"))
+                .and_then(|(_, v)| v.parse::<usize>().ok())
+                .unwrap_or(0);
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+
+            write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                status
+            )
+            .unwrap();
+
+            tx.send(Request {
+                headers,
+                body: String::from_utf8(body).unwrap(),
+            })
+            .unwrap();
+        });
+
+        (url, rx)
+    }
+
+    // skips the destination check, which would turn the local listener away
+    fn unchecked(url: &str) -> Destination {
+        let url = Url::parse(url).unwrap();
+        let addresses = url.socket_addrs(|| None).unwrap();
+
+        Destination { url, addresses }
+    }
+
+    const SECRET: &'static str = "secret";
+
+    fn delivery() -> QueuedDelivery {
+        QueuedDelivery {
+            delivery_id: DeliveryID::generate(),
+            webhook_id: WebhookID::generate(),
+            document_id: DocumentID::generate(),
+            event: WebhookEvent::Publish,
+            payload: "{\"event\":\"publish\"}".to_string(),
+            attempt: 1,
+        }
+    }
+
+    #[test]
+    fn signs_with_hmac_sha1() {
+        assert_eq!(
+            sign("Jefe", "what do ya want for nothing?"),
+            "sha1=effcdf6ae5eb2fa2d27416d5f184df9c259a7c79"
+        );
+    }
+
+    #[test]
+    fn builds_payload() {
+        let delivery_id = DeliveryID::generate();
+        let p_document_id = DocumentID::generate();
+        let p_actor_id = UserID::generate();
+
+        let payload = build_payload(
+            &delivery_id,
+            WebhookEvent::EditText,
+            &p_document_id,
+            &p_actor_id,
+            &serde_json::from_str(r#"{ "title": "Notes" }"#).unwrap(),
+        )
+        .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&payload).unwrap();
+
+        assert_eq!(value["delivery_id"], delivery_id.json_str());
+        assert_eq!(value["event"], "edit_text");
+        assert_eq!(value["document_id"], p_document_id.json_str());
+        assert_eq!(value["actor_id"], p_actor_id.json_str());
+        assert!(value["timestamp"].is_object());
+        assert_eq!(value["details"]["title"], "Notes");
+    }
+
+    #[test]
+    fn backs_off_exponentially() {
+        assert_eq!(QueuedDelivery::backoff(1), Some(Duration::from_secs(60)));
+        assert_eq!(QueuedDelivery::backoff(2), Some(Duration::from_secs(120)));
+        assert_eq!(QueuedDelivery::backoff(4), Some(Duration::from_secs(480)));
+        assert_eq!(QueuedDelivery::backoff(QueuedDelivery::MAX_ATTEMPTS), None);
+    }
+
+    #[test]
+    fn posts_signed_delivery() {
+        let (url, rx) = listen("200 OK");
+        let d = delivery();
+
+        assert_eq!(post(&unchecked(&url), SECRET, &d), (Some(200), None));
+
+        let request = rx.recv().unwrap();
+        assert_eq!(request.body, d.payload);
+        assert_eq!(request.header("X-Seriatim-Event"), Some("publish"));
+        assert_eq!(
+            request.header("X-Seriatim-Delivery"),
+            Some(d.delivery_id.json_str().as_str())
+        );
+        assert_eq!(
+            request.header("X-Seriatim-Signature"),
+            Some(sign(SECRET, &d.payload).as_str())
+        );
+        assert_eq!(request.header("Content-Type"), Some("application/json"));
+    }
+
+    #[test]
+    fn reports_failed_status() {
+        let (url, rx) = listen("500 Internal Server Error");
+
+        let (status, error) = post(&unchecked(&url), SECRET, &delivery());
+        assert_eq!(status, Some(500));
+        assert!(error.is_some());
+
+        rx.recv().unwrap();
+    }
+
+    #[test]
+    fn reports_unreachable_destination() {
+        let address = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let url = format!("http://{}/hook", address);
+        let (status, error) = post(&unchecked(&url), SECRET, &delivery());
+        assert_eq!(status, None);
+        assert!(error.is_some());
+    }
+
+    #[test]
+    fn connects_to_checked_address() {
+        let (url, rx) = listen("204 No Content");
+        let destination = Destination {
+            url: Url::parse("http://hooks.example.com/seriatim?v=1").unwrap(),
+            addresses: unchecked(&url).addresses,
+        };
+
+        assert_eq!(post(&destination, SECRET, &delivery()), (Some(204), None));
+
+        let request = rx.recv().unwrap();
+        assert_eq!(request.header("Host"), Some("hooks.example.com"));
+    }
+
+    #[test]
+    fn refuses_to_send_to_internal_destinations() {
+        let (url, rx) = listen("200 OK");
+
+        let (status, error) = send(&url, SECRET, &delivery());
+        assert_eq!(status, None);
+        assert!(error.is_some());
+        assert!(rx.recv_timeout(Duration::from_millis(200)).is_err());
+    }
+
+    #[test]
+    fn rejects_internal_destinations() {
+        for url in [
+            "http://127.0.0.1/hook",
+            "http://10.0.0.1/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://[::1]/hook",
+            "http://[::ffff:192.168.0.1]/hook",
+            "ftp://example.com/hook",
+        ]
+        .iter()
+        {
+            assert!(check_destination(url).is_err(), "{} was allowed", url);
+        }
+
+        assert!(check_destination("https://93.184.216.34/hook").is_ok());
+    }
+}